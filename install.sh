@@ -15,3 +15,7 @@ popd > /dev/null
 pushd ${self_dir}/karo-bus-monitor/ > /dev/null
 bash ./install.sh
 popd > /dev/null
+
+pushd ${self_dir}/karo-bus-ctl/ > /dev/null
+bash ./install.sh
+popd > /dev/null
@@ -0,0 +1,129 @@
+use std::{collections::HashMap, fmt::Display};
+
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+/// Hub admin tool service name. The only service allowed to make hub admin calls
+pub const CTL_SERVICE_NAME: &str = "karo.bus.ctl";
+
+/// Privileged hub request. The hub replies with a serialized response type
+/// mentioned for each request
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum AdminRequest {
+    /// List registered services. Returns Vec<[ServiceInfo]>
+    ListServices,
+    /// List connection requests waiting for a target service to register.
+    /// Returns Vec<[PendingConnectionInfo]>
+    PendingConnections,
+    /// Drop a client connection. Returns ()
+    Kick { service_name: String },
+    /// Reread service files policy. Returns ()
+    ReloadPolicy,
+    /// Hub statistics. Returns [HubStats]
+    Stats,
+    /// Peer connections graph. Returns [crate::topology::TopologyData]
+    Topology,
+    /// Hub metrics in Prometheus text exposition format. Returns String
+    Metrics,
+}
+
+impl Display for AdminRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ListServices => write!(f, "List services"),
+            Self::PendingConnections => write!(f, "List pending connections"),
+            Self::Kick { service_name } => write!(f, "Kick service '{}'", service_name),
+            Self::ReloadPolicy => write!(f, "Reload policy"),
+            Self::Stats => write!(f, "Hub stats"),
+            Self::Topology => write!(f, "Connection topology"),
+            Self::Metrics => write!(f, "Hub metrics"),
+        }
+    }
+}
+
+/// Registered service info
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceInfo {
+    pub service_name: String,
+    /// Client process id if available
+    pub pid: Option<i32>,
+    pub uid: u32,
+    /// Services connected to the service
+    pub peers: Vec<String>,
+}
+
+impl Display for ServiceInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} (pid: {}, uid: {})",
+            self.service_name.bright_blue(),
+            self.pid
+                .map(|pid| pid.to_string())
+                .unwrap_or_else(|| "unknown".into()),
+            self.uid
+        )?;
+
+        for peer in self.peers.iter() {
+            writeln!(f, "\t{} {}", "<->".bright_green(), peer)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Connection request waiting for a target service to register
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PendingConnectionInfo {
+    pub requester_service_name: String,
+    pub target_service_name: String,
+}
+
+impl Display for PendingConnectionInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.requester_service_name,
+            "-->".bright_yellow(),
+            self.target_service_name
+        )
+    }
+}
+
+/// Hub statistics
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HubStats {
+    pub uptime_secs: u64,
+    pub clients: u64,
+    pub anonymous_clients: u64,
+    pub pending_connections: u64,
+    /// Number of quota violations for each quota name
+    pub quota_violations: HashMap<String, u64>,
+}
+
+impl Display for HubStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {}s", "uptime".bright_blue(), self.uptime_secs)?;
+        writeln!(f, "{}: {}", "clients".bright_blue(), self.clients)?;
+        writeln!(
+            f,
+            "{}: {}",
+            "anonymous clients".bright_blue(),
+            self.anonymous_clients
+        )?;
+        writeln!(
+            f,
+            "{}: {}",
+            "pending connections".bright_blue(),
+            self.pending_connections
+        )?;
+
+        writeln!(f, "{}:", "quota violations".bright_yellow())?;
+        for (quota, count) in self.quota_violations.iter() {
+            writeln!(f, "\t{}: {}", quota, count)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    inspect_data::{endpoint_name, InspectData},
+    schema::Schema,
+};
+
+/// How an interface change affects existing peers
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Existing peers keep working
+    Compatible,
+    /// Existing peers may fail
+    Breaking,
+}
+
+/// Single interface change
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompatIssue {
+    pub severity: Severity,
+    /// Endpoint type: method, signal, state or channel
+    pub endpoint_type: String,
+    pub endpoint_name: String,
+    pub message: String,
+}
+
+/// Result of comparing two interface versions. See [check]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatReport {
+    pub issues: Vec<CompatIssue>,
+}
+
+impl CompatReport {
+    /// If existing peers may fail with the new interface
+    pub fn is_breaking(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity == Severity::Breaking)
+    }
+
+    fn add(
+        &mut self,
+        severity: Severity,
+        endpoint_type: &str,
+        endpoint_name: &str,
+        message: String,
+    ) {
+        self.issues.push(CompatIssue {
+            severity,
+            endpoint_type: endpoint_type.into(),
+            endpoint_name: endpoint_name.into(),
+            message,
+        });
+    }
+}
+
+impl Display for CompatReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.issues.is_empty() {
+            return writeln!(f, "{}", "No interface changes".bright_green());
+        }
+
+        for issue in &self.issues {
+            let severity = match issue.severity {
+                Severity::Compatible => "compatible".bright_green(),
+                Severity::Breaking => "breaking".bright_red(),
+            };
+
+            writeln!(
+                f,
+                "{} {} `{}`: {}",
+                severity, issue.endpoint_type, issue.endpoint_name, issue.message
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Data flow direction. Decides which side has to accept values of the other side
+#[derive(Clone, Copy)]
+enum Direction {
+    /// New service reads values written by existing peers. Method parameters
+    Incoming,
+    /// Existing peers read values written by the new service.
+    /// Return values, signals and states
+    Outgoing,
+}
+
+/// Compare **old** and **new** inspection data of a service.
+/// Structured schemas are compared if both versions describe the endpoint,
+/// type names otherwise
+pub fn check(old: &InspectData, new: &InspectData) -> CompatReport {
+    let mut report = CompatReport::default();
+
+    check_endpoints(&mut report, "method", &old.methods, &new.methods);
+    check_endpoints(&mut report, "signal", &old.signals, &new.signals);
+    check_endpoints(&mut report, "state", &old.states, &new.states);
+    check_endpoints(&mut report, "channel", &old.channels, &new.channels);
+
+    for (name, old_schema) in &old.method_schemas {
+        if let Some(new_schema) = new.method_schemas.get(name) {
+            check_schema(
+                &mut report,
+                "method",
+                name,
+                "parameters",
+                Direction::Incoming,
+                &old_schema.params,
+                &new_schema.params,
+            );
+            check_schema(
+                &mut report,
+                "method",
+                name,
+                "return value",
+                Direction::Outgoing,
+                &old_schema.returns,
+                &new_schema.returns,
+            );
+        }
+    }
+
+    for (endpoint_type, old_schemas, new_schemas) in [
+        ("signal", &old.signal_schemas, &new.signal_schemas),
+        ("state", &old.state_schemas, &new.state_schemas),
+    ] {
+        for (name, old_schema) in old_schemas {
+            if let Some(new_schema) = new_schemas.get(name) {
+                check_schema(
+                    &mut report,
+                    endpoint_type,
+                    name,
+                    "value",
+                    Direction::Outgoing,
+                    old_schema,
+                    new_schema,
+                );
+            }
+        }
+    }
+
+    for (endpoint_type, old_metadata, new_metadata) in [
+        ("method", &old.method_metadata, &new.method_metadata),
+        ("signal", &old.signal_metadata, &new.signal_metadata),
+        ("state", &old.state_metadata, &new.state_metadata),
+    ] {
+        for (name, metadata) in new_metadata {
+            let was_deprecated = old_metadata
+                .get(name)
+                .map(|metadata| metadata.deprecated.is_some())
+                .unwrap_or_default();
+
+            if let (Some(deprecation), false) = (&metadata.deprecated, was_deprecated) {
+                report.add(
+                    Severity::Compatible,
+                    endpoint_type,
+                    name,
+                    deprecation.to_string(),
+                );
+            }
+        }
+    }
+
+    report
+}
+
+/// Compare endpoint lists by names and type names
+fn check_endpoints(report: &mut CompatReport, endpoint_type: &str, old: &[String], new: &[String]) {
+    let old = endpoint_types(old);
+    let new = endpoint_types(new);
+
+    for (name, old_type) in &old {
+        match new.get(name) {
+            None => report.add(Severity::Breaking, endpoint_type, name, "removed".into()),
+            Some(new_type) if new_type != old_type => report.add(
+                Severity::Breaking,
+                endpoint_type,
+                name,
+                format!("type changed from `{}` to `{}`", old_type, new_type),
+            ),
+            _ => {}
+        }
+    }
+
+    for name in new.keys().filter(|name| !old.contains_key(*name)) {
+        report.add(Severity::Compatible, endpoint_type, name, "added".into());
+    }
+}
+
+/// Split inspection lines into endpoint names and type names
+fn endpoint_types(lines: &[String]) -> BTreeMap<&str, &str> {
+    lines
+        .iter()
+        .map(|line| {
+            let name = endpoint_name(line);
+            let type_name = line[name.len()..].trim_start_matches(':').trim();
+
+            (name, type_name)
+        })
+        .collect()
+}
+
+/// Compare schemas of the same endpoint. Type name changes are already reported by
+/// [check_endpoints], so here we only replace them with structural differences
+fn check_schema(
+    report: &mut CompatReport,
+    endpoint_type: &str,
+    endpoint_name: &str,
+    what: &str,
+    direction: Direction,
+    old: &Schema,
+    new: &Schema,
+) {
+    // Type names are not reliable if structures are described
+    report.issues.retain(|issue| {
+        !(issue.endpoint_type == endpoint_type
+            && issue.endpoint_name == endpoint_name
+            && issue.message.starts_with("type changed"))
+    });
+
+    let (reader, writer) = match direction {
+        Direction::Incoming => (new, old),
+        Direction::Outgoing => (old, new),
+    };
+
+    let mut problems = vec![];
+    accepts(reader, writer, what, &mut problems);
+
+    if problems.is_empty() && old != new {
+        report.add(
+            Severity::Compatible,
+            endpoint_type,
+            endpoint_name,
+            format!("{} changed from `{}` to `{}`", what, old, new),
+        );
+    }
+
+    for problem in problems {
+        report.add(Severity::Breaking, endpoint_type, endpoint_name, problem);
+    }
+}
+
+/// Check if every value matching the **writer** schema is accepted by the **reader** schema
+fn accepts(reader: &Schema, writer: &Schema, path: &str, problems: &mut Vec<String>) {
+    match (reader, writer) {
+        (Schema::Any, _) => {}
+        (reader, writer) if reader == writer => {}
+        (Schema::Float, Schema::Int) => {}
+        (Schema::Optional { value: reader }, Schema::Optional { value: writer }) => {
+            accepts(reader, writer, path, problems)
+        }
+        (Schema::Optional { .. }, Schema::Null) => {}
+        (Schema::Optional { value: reader }, writer) => accepts(reader, writer, path, problems),
+        (reader, Schema::Optional { .. }) => problems.push(format!(
+            "{} may be null, but `{}` is expected",
+            path, reader
+        )),
+        (Schema::Array { items: reader }, Schema::Array { items: writer }) => {
+            accepts(reader, writer, &format!("{}[]", path), problems)
+        }
+        (Schema::Map { values: reader }, Schema::Map { values: writer }) => {
+            accepts(reader, writer, &format!("{}{{}}", path), problems)
+        }
+        (Schema::Tuple { items: reader }, Schema::Tuple { items: writer })
+            if reader.len() == writer.len() =>
+        {
+            for (i, (reader, writer)) in reader.iter().zip(writer).enumerate() {
+                accepts(reader, writer, &format!("{}[{}]", path, i), problems);
+            }
+        }
+        (
+            Schema::Object {
+                fields: reader_fields,
+                ..
+            },
+            Schema::Object {
+                fields: writer_fields,
+                ..
+            },
+        ) => {
+            for field in reader_fields {
+                let field_path = format!("{}.{}", path, field.name);
+
+                match writer_fields
+                    .iter()
+                    .find(|writer_field| writer_field.name == field.name)
+                {
+                    Some(writer_field) => {
+                        accepts(&field.schema, &writer_field.schema, &field_path, problems)
+                    }
+                    // Missing options deserialize into None
+                    None if matches!(field.schema, Schema::Optional { .. }) => {}
+                    None => problems.push(format!("{} is required, but missing", field_path)),
+                }
+            }
+        }
+        (
+            Schema::Enum {
+                variants: reader_variants,
+                ..
+            },
+            Schema::Enum {
+                variants: writer_variants,
+                ..
+            },
+        ) => {
+            for variant in writer_variants {
+                if !reader_variants.contains(variant) {
+                    problems.push(format!("{} has unknown variant `{}`", path, variant));
+                }
+            }
+        }
+        (reader, writer) => problems.push(format!(
+            "{}: `{}` can't be read as `{}`",
+            path, writer, reader
+        )),
+    }
+}
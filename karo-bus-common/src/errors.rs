@@ -18,6 +18,8 @@ pub enum Error {
     NotRegistered,
     #[error("Invalid protocol version. Please update Karo dependencies")]
     InvalidProtocol,
+    #[error("Invalid object path: {0}")]
+    InvalidObjectPath(String),
     #[error("Invalid parameters passed into a function {0}")]
     InvalidParameters(String),
     #[error("Invalid return type from a function. Can't deserialize response")]
@@ -26,6 +28,14 @@ pub enum Error {
     InvalidMessage,
     #[error("Service not connected")]
     NotConnected,
+    #[error("Service quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error("Hub is shutting down")]
+    HubShutdown,
+    #[error("Method is busy. Too many calls are waiting to be handled")]
+    Busy,
+    #[error("Call deadline exceeded")]
+    Timeout,
     #[error("Internal bus error. See logs for details. Please fill bug report")]
     Internal,
 }
@@ -0,0 +1,225 @@
+use std::{cmp::Ordering, fmt::Display, ops::Not};
+
+use bson::Bson;
+use serde::{Deserialize, Serialize};
+
+/// Signal subscription filter. The publisher evaluates the filter on every emission
+/// and sends only matching ones.\
+/// Fields are addressed with dotted paths into the signal value, e.g. `sensor.zone`.
+/// Array items are addressed by indices. Empty path refers to the value itself
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Filter {
+    /// Field equals the value. Integers and floats are compared by value
+    Eq { field: String, value: Bson },
+    /// Field exists and doesn't equal the value
+    Ne { field: String, value: Bson },
+    /// Field is less than the value
+    Lt { field: String, value: Bson },
+    /// Field is less than or equal to the value
+    Le { field: String, value: Bson },
+    /// Field is greater than the value
+    Gt { field: String, value: Bson },
+    /// Field is greater than or equal to the value
+    Ge { field: String, value: Bson },
+    /// Field exists
+    Exists { field: String },
+    /// All filters match
+    And { filters: Vec<Filter> },
+    /// Any of filters matches
+    Or { filters: Vec<Filter> },
+    /// The filter doesn't match
+    Not { filter: Box<Filter> },
+}
+
+impl Filter {
+    pub fn eq(field: &str, value: impl Into<Bson>) -> Self {
+        Self::Eq {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn ne(field: &str, value: impl Into<Bson>) -> Self {
+        Self::Ne {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn lt(field: &str, value: impl Into<Bson>) -> Self {
+        Self::Lt {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn le(field: &str, value: impl Into<Bson>) -> Self {
+        Self::Le {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn gt(field: &str, value: impl Into<Bson>) -> Self {
+        Self::Gt {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn ge(field: &str, value: impl Into<Bson>) -> Self {
+        Self::Ge {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn exists(field: &str) -> Self {
+        Self::Exists {
+            field: field.into(),
+        }
+    }
+
+    /// Field is within the **min**..=**max** range
+    pub fn between(field: &str, min: impl Into<Bson>, max: impl Into<Bson>) -> Self {
+        Self::ge(field, min).and(Self::le(field, max))
+    }
+
+    /// Both this and the **other** filters match
+    pub fn and(self, other: Filter) -> Self {
+        match self {
+            Self::And { mut filters } => {
+                filters.push(other);
+                Self::And { filters }
+            }
+            filter => Self::And {
+                filters: vec![filter, other],
+            },
+        }
+    }
+
+    /// This or the **other** filter matches
+    pub fn or(self, other: Filter) -> Self {
+        match self {
+            Self::Or { mut filters } => {
+                filters.push(other);
+                Self::Or { filters }
+            }
+            filter => Self::Or {
+                filters: vec![filter, other],
+            },
+        }
+    }
+
+    /// Check if the signal **value** matches the filter. Comparisons with missing
+    /// fields or values of different types don't match
+    pub fn matches(&self, value: &Bson) -> bool {
+        match self {
+            Self::Eq { field, value: rhs } => field_value(value, field)
+                .map(|lhs| compare(lhs, rhs) == Some(Ordering::Equal))
+                .unwrap_or_default(),
+            Self::Ne { field, value: rhs } => field_value(value, field)
+                .map(|lhs| compare(lhs, rhs) != Some(Ordering::Equal))
+                .unwrap_or_default(),
+            Self::Lt { field, value: rhs } => compare_field(value, field, rhs, Ordering::is_lt),
+            Self::Le { field, value: rhs } => compare_field(value, field, rhs, Ordering::is_le),
+            Self::Gt { field, value: rhs } => compare_field(value, field, rhs, Ordering::is_gt),
+            Self::Ge { field, value: rhs } => compare_field(value, field, rhs, Ordering::is_ge),
+            Self::Exists { field } => field_value(value, field).is_some(),
+            Self::And { filters } => filters.iter().all(|filter| filter.matches(value)),
+            Self::Or { filters } => filters.iter().any(|filter| filter.matches(value)),
+            Self::Not { filter } => !filter.matches(value),
+        }
+    }
+}
+
+/// Inverted filter
+impl Not for Filter {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::Not {
+            filter: Box::new(self),
+        }
+    }
+}
+
+/// Find the field at the dotted **path**
+fn field_value<'a>(value: &'a Bson, path: &str) -> Option<&'a Bson> {
+    if path.is_empty() {
+        return Some(value);
+    }
+
+    path.split('.')
+        .try_fold(value, |value, segment| match value {
+            Bson::Document(document) => document.get(segment),
+            Bson::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+fn compare_field(value: &Bson, path: &str, rhs: &Bson, check: fn(Ordering) -> bool) -> bool {
+    field_value(value, path)
+        .and_then(|lhs| compare(lhs, rhs))
+        .map(check)
+        .unwrap_or_default()
+}
+
+/// Compare scalar values of the same kind. Numbers are compared by value
+fn compare(lhs: &Bson, rhs: &Bson) -> Option<Ordering> {
+    if let (Some(lhs), Some(rhs)) = (as_number(lhs), as_number(rhs)) {
+        return lhs.partial_cmp(&rhs);
+    }
+
+    match (lhs, rhs) {
+        (Bson::String(lhs), Bson::String(rhs)) => Some(lhs.cmp(rhs)),
+        (Bson::Boolean(lhs), Bson::Boolean(rhs)) => Some(lhs.cmp(rhs)),
+        (Bson::DateTime(lhs), Bson::DateTime(rhs)) => Some(lhs.cmp(rhs)),
+        (Bson::Null, Bson::Null) => Some(Ordering::Equal),
+        (lhs, rhs) if lhs == rhs => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn as_number(value: &Bson) -> Option<f64> {
+    match value {
+        Bson::Int32(value) => Some(*value as f64),
+        Bson::Int64(value) => Some(*value as f64),
+        Bson::Double(value) => Some(*value),
+        _ => None,
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let field_name = |field: &String| {
+            if field.is_empty() {
+                "value".to_owned()
+            } else {
+                field.clone()
+            }
+        };
+
+        let join = |f: &mut std::fmt::Formatter<'_>, filters: &Vec<Filter>, op: &str| {
+            let filters = filters
+                .iter()
+                .map(|filter| format!("({})", filter))
+                .collect::<Vec<_>>();
+            write!(f, "{}", filters.join(op))
+        };
+
+        match self {
+            Self::Eq { field, value } => write!(f, "{} == {}", field_name(field), value),
+            Self::Ne { field, value } => write!(f, "{} != {}", field_name(field), value),
+            Self::Lt { field, value } => write!(f, "{} < {}", field_name(field), value),
+            Self::Le { field, value } => write!(f, "{} <= {}", field_name(field), value),
+            Self::Gt { field, value } => write!(f, "{} > {}", field_name(field), value),
+            Self::Ge { field, value } => write!(f, "{} >= {}", field_name(field), value),
+            Self::Exists { field } => write!(f, "exists {}", field_name(field)),
+            Self::And { filters } => join(f, filters, " && "),
+            Self::Or { filters } => join(f, filters, " || "),
+            Self::Not { filter } => write!(f, "!({})", filter),
+        }
+    }
+}
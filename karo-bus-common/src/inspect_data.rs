@@ -1,8 +1,10 @@
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display};
 
 use colored::*;
 use serde::{Deserialize, Serialize};
 
+use crate::{object_path, schema::Schema};
+
 pub const CONNECT_SERVICE_NAME: &str = "karo.bus.connect";
 
 pub const INSPECT_METHOD: &str = "inspect";
@@ -12,6 +14,36 @@ pub struct InspectData {
     pub methods: Vec<String>,
     pub signals: Vec<String>,
     pub states: Vec<String>,
+    #[serde(default)]
+    pub channels: Vec<String>,
+    /// Structured method schemas. Only described methods are present
+    #[serde(default)]
+    pub method_schemas: BTreeMap<String, MethodSchema>,
+    /// Structured signal schemas. Only described signals are present
+    #[serde(default)]
+    pub signal_schemas: BTreeMap<String, Schema>,
+    /// Structured state schemas. Only described states are present
+    #[serde(default)]
+    pub state_schemas: BTreeMap<String, Schema>,
+    /// Method metadata. Only methods registered with metadata are present
+    #[serde(default)]
+    pub method_metadata: BTreeMap<String, EndpointMetadata>,
+    /// Signal metadata. Only signals registered with metadata are present
+    #[serde(default)]
+    pub signal_metadata: BTreeMap<String, EndpointMetadata>,
+    /// State metadata. Only states registered with metadata are present
+    #[serde(default)]
+    pub state_metadata: BTreeMap<String, EndpointMetadata>,
+    /// Direct child objects of the inspected object
+    #[serde(default)]
+    pub children: Vec<String>,
+}
+
+/// Method parameters and return value schemas
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MethodSchema {
+    pub params: Schema,
+    pub returns: Schema,
 }
 
 impl InspectData {
@@ -20,27 +52,224 @@ impl InspectData {
             methods: vec![],
             signals: vec![],
             states: vec![],
+            channels: vec![],
+            method_schemas: BTreeMap::new(),
+            signal_schemas: BTreeMap::new(),
+            state_schemas: BTreeMap::new(),
+            method_metadata: BTreeMap::new(),
+            signal_metadata: BTreeMap::new(),
+            state_metadata: BTreeMap::new(),
+            children: vec![],
+        }
+    }
+
+    /// Inspection data of the object at **path**. Endpoint names are qualified
+    /// with object paths in the service data, but not in the object view.
+    /// Children are taken from the **objects** registered by the service
+    pub fn object_view<'a>(
+        &self,
+        path: &str,
+        objects: impl IntoIterator<Item = &'a String>,
+    ) -> Self {
+        fn lines(lines: &[String], path: &str) -> Vec<String> {
+            lines
+                .iter()
+                .filter_map(|line| {
+                    let (object, name) = object_path::split(endpoint_name(line));
+                    let prefix_len = endpoint_name(line).len() - name.len();
+
+                    (object == path).then(|| line[prefix_len..].to_string())
+                })
+                .collect()
+        }
+
+        fn entries<T: Clone>(entries: &BTreeMap<String, T>, path: &str) -> BTreeMap<String, T> {
+            entries
+                .iter()
+                .filter_map(|(name, entry)| match object_path::split(name) {
+                    (object, name) if object == path => Some((name.to_string(), entry.clone())),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        Self {
+            methods: lines(&self.methods, path),
+            signals: lines(&self.signals, path),
+            states: lines(&self.states, path),
+            channels: lines(&self.channels, path),
+            method_schemas: entries(&self.method_schemas, path),
+            signal_schemas: entries(&self.signal_schemas, path),
+            state_schemas: entries(&self.state_schemas, path),
+            method_metadata: entries(&self.method_metadata, path),
+            signal_metadata: entries(&self.signal_metadata, path),
+            state_metadata: entries(&self.state_metadata, path),
+            children: object_path::children(path, objects),
+        }
+    }
+
+    /// Remove the method from the inspection
+    pub fn remove_method(&mut self, method_name: &str) {
+        self.methods
+            .retain(|line| endpoint_name(line) != method_name);
+        self.method_schemas.remove(method_name);
+        self.method_metadata.remove(method_name);
+    }
+
+    /// Remove the signal from the inspection
+    pub fn remove_signal(&mut self, signal_name: &str) {
+        self.signals
+            .retain(|line| endpoint_name(line) != signal_name);
+        self.signal_schemas.remove(signal_name);
+        self.signal_metadata.remove(signal_name);
+    }
+
+    /// Remove the state from the inspection
+    pub fn remove_state(&mut self, state_name: &str) {
+        self.states.retain(|line| endpoint_name(line) != state_name);
+        self.state_schemas.remove(state_name);
+        self.state_metadata.remove(state_name);
+    }
+
+    /// Remove the channel from the inspection
+    pub fn remove_channel(&mut self, channel_name: &str) {
+        self.channels
+            .retain(|line| endpoint_name(line) != channel_name);
+    }
+}
+
+/// Endpoint description for the service users
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndpointMetadata {
+    /// Human readable description
+    pub description: Option<String>,
+    /// Version of the interface the endpoint belongs to
+    pub version: Option<String>,
+    /// Set if the endpoint is going to be removed
+    pub deprecated: Option<Deprecation>,
+}
+
+/// Endpoint deprecation notice
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    /// Interface version, which deprecated the endpoint
+    pub since: String,
+    /// Endpoint to use instead
+    pub replacement: Option<String>,
+}
+
+impl EndpointMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set endpoint description
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set interface version
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Mark the endpoint deprecated since the interface **since** version.
+    /// Callers are warned once to use the **replacement** if given
+    pub fn deprecated(mut self, since: &str, replacement: Option<&str>) -> Self {
+        self.deprecated = Some(Deprecation {
+            since: since.into(),
+            replacement: replacement.map(Into::into),
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for Deprecation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "deprecated since {}", self.since)?;
+
+        if let Some(replacement) = &self.replacement {
+            write!(f, ", use `{}`", replacement)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for EndpointMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+
+        if let Some(version) = &self.version {
+            parts.push(format!("v{}", version));
+        }
+
+        if let Some(deprecation) = &self.deprecated {
+            parts.push(deprecation.to_string().bright_red().to_string());
         }
+
+        if let Some(description) = &self.description {
+            parts.push(description.clone());
+        }
+
+        write!(f, "{}", parts.join(". "))
     }
 }
 
 impl Display for InspectData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "{}:", "methods".bright_blue())?;
-        self.methods
-            .iter()
-            .for_each(|method| writeln!(f, "\t{}", method).unwrap());
+        write_endpoints(f, &self.methods, &self.method_metadata)?;
 
         writeln!(f, "{}:", "signals".bright_yellow())?;
-        self.signals
-            .iter()
-            .for_each(|signal| writeln!(f, "\t{}", signal).unwrap());
+        write_endpoints(f, &self.signals, &self.signal_metadata)?;
 
         writeln!(f, "{}:", "states".bright_green())?;
-        self.states
+        write_endpoints(f, &self.states, &self.state_metadata)?;
+
+        writeln!(f, "{}:", "channels".bright_magenta())?;
+        self.channels
             .iter()
-            .for_each(|state| writeln!(f, "\t{}", state).unwrap());
+            .for_each(|channel| writeln!(f, "\t{}", channel).unwrap());
+
+        if !self.children.is_empty() {
+            writeln!(f, "{}:", "objects".bright_cyan())?;
+            self.children
+                .iter()
+                .for_each(|child| writeln!(f, "\t{}", child).unwrap());
+        }
 
         Ok(())
     }
 }
+
+/// Write endpoint lines followed by their metadata if present
+fn write_endpoints(
+    f: &mut std::fmt::Formatter<'_>,
+    endpoints: &[String],
+    metadata: &BTreeMap<String, EndpointMetadata>,
+) -> std::fmt::Result {
+    for endpoint in endpoints {
+        writeln!(f, "\t{}", endpoint)?;
+
+        if let Some(metadata) = metadata
+            .get(endpoint_name(endpoint))
+            .filter(|metadata| !metadata.is_empty())
+        {
+            writeln!(f, "\t\t{}", metadata)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Endpoint name of an inspection line. Lines start with the name followed by the type
+pub fn endpoint_name(line: &str) -> &str {
+    line.split(['(', ':']).next().unwrap_or_default()
+}
@@ -1,10 +1,16 @@
+pub mod admin;
 pub mod call_registry;
+pub mod compat;
 pub mod errors;
+pub mod filter;
 pub mod inspect_data;
 pub mod messages;
 pub mod monitor;
 pub mod net;
+pub mod object_path;
+pub mod schema;
 pub mod service_names;
+pub mod topology;
 
 use std::env;
 
@@ -1,11 +1,11 @@
-use std::{fmt::Display, os::unix::prelude::RawFd};
+use std::{collections::HashMap, fmt::Display, os::unix::prelude::RawFd};
 
 use bson::{self, Bson};
 use bytes::{Buf, BytesMut};
 use log::*;
 use serde::{Deserialize, Serialize};
 
-use super::errors;
+use super::{admin::AdminRequest, errors, filter::Filter};
 
 pub const PROTOCOL_VERSION: i64 = 1;
 const INVALID_SEQ: u64 = 0xDEADBEEF;
@@ -30,14 +30,77 @@ pub enum MessageBody {
         caller_name: String,
         method_name: String,
         params: Bson,
+        /// Call deadline in milliseconds since UNIX epoch if any
+        #[serde(default)]
+        deadline: Option<u64>,
+        /// Arbitrary call metadata
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// Caller assigned call sequence number. Used to cancel the call
+        #[serde(default)]
+        call_seq: u64,
+    },
+    /// Caller is not waiting for a call with *seq* anymore
+    Cancel {
+        caller_name: String,
+        seq: u64,
+    },
+    /// Streaming method call. Callee may send up to *window* items before
+    /// receiving [MessageBody::StreamCredit]
+    StreamCall {
+        caller_name: String,
+        method_name: String,
+        params: Bson,
+        call_seq: u64,
+        window: u32,
+    },
+    /// Caller consumed *credits* items of a stream call or a channel with *seq*
+    StreamCredit {
+        caller_name: String,
+        seq: u64,
+        credits: u32,
+    },
+    /// Open duplex channel. Each side may send up to *window* items before
+    /// receiving a credit
+    OpenChannel {
+        caller_name: String,
+        channel_name: String,
+        init: Bson,
+        call_seq: u64,
+        window: u32,
+    },
+    /// Channel item from the side, which opened the channel
+    ChannelData {
+        caller_name: String,
+        seq: u64,
+        data: Bson,
+    },
+    /// Side, which opened the channel won't send more items
+    ChannelClose {
+        caller_name: String,
+        seq: u64,
     },
     SignalSubscription {
         subscriber_name: String,
         signal_name: String,
+        /// Subscriber assigned subscription seq. Used to unsubscribe
+        #[serde(default)]
+        subscription_seq: u64,
+        /// Publisher sends only emissions matching the filter
+        #[serde(default)]
+        filter: Option<Filter>,
     },
     StateSubscription {
         subscriber_name: String,
         state_name: String,
+        /// Subscriber assigned subscription seq. Used to unsubscribe
+        #[serde(default)]
+        subscription_seq: u64,
+    },
+    /// Subscriber dropped signal subscription or state watch with *seq*
+    Unsubscribe {
+        subscriber_name: String,
+        seq: u64,
     },
 }
 
@@ -53,22 +116,63 @@ impl Display for MessageBody {
             Self::ServiceMessage(service_message) => write!(f, "{}", service_message),
             Self::Response(response) => write!(f, "{}", response),
             Self::MethodCall {
-                caller_name: _,
                 method_name,
                 params,
+                ..
             } => write!(
                 f,
                 "Method '{}' call. Argument value: {}",
                 method_name, params
             ),
+            Self::Cancel { caller_name, seq } => {
+                write!(f, "Call {} cancelled by '{}'", seq, caller_name)
+            }
+            Self::StreamCall {
+                method_name,
+                params,
+                window,
+                ..
+            } => write!(
+                f,
+                "Streaming method '{}' call. Window: {}. Argument value: {}",
+                method_name, window, params
+            ),
+            Self::StreamCredit { seq, credits, .. } => {
+                write!(f, "Stream {} credit: {}", seq, credits)
+            }
+            Self::OpenChannel {
+                channel_name,
+                init,
+                window,
+                ..
+            } => write!(
+                f,
+                "Channel '{}' open request. Window: {}. Init value: {}",
+                channel_name, window, init
+            ),
+            Self::ChannelData { seq, data, .. } => {
+                write!(f, "Channel {} data: {}", seq, data)
+            }
+            Self::ChannelClose { seq, .. } => write!(f, "Channel {} closed", seq),
             Self::SignalSubscription {
-                subscriber_name: _,
                 signal_name,
-            } => write!(f, "Signal '{}' subscription request", signal_name),
-            Self::StateSubscription {
-                subscriber_name: _,
-                state_name,
-            } => write!(f, "State '{}' watch request", state_name),
+                filter: Some(filter),
+                ..
+            } => write!(
+                f,
+                "Signal '{}' subscription request. Filter: {}",
+                signal_name, filter
+            ),
+            Self::SignalSubscription { signal_name, .. } => {
+                write!(f, "Signal '{}' subscription request", signal_name)
+            }
+            Self::StateSubscription { state_name, .. } => {
+                write!(f, "State '{}' watch request", state_name)
+            }
+            Self::Unsubscribe {
+                subscriber_name,
+                seq,
+            } => write!(f, "Subscription {} dropped by '{}'", seq, subscriber_name),
         }
     }
 }
@@ -78,6 +182,10 @@ impl Message {
         &self.body
     }
 
+    pub fn into_body(self) -> MessageBody {
+        self.body
+    }
+
     pub fn seq(&self) -> u64 {
         self.seq
     }
@@ -110,33 +218,69 @@ impl Message {
         }
     }
 
+    pub fn new_admin_request(request: AdminRequest) -> Self {
+        Self {
+            seq: INVALID_SEQ,
+            body: MessageBody::ServiceMessage(ServiceMessage::Admin(request)),
+        }
+    }
+
+    pub fn new_peer_disconnected(peer_service_name: String) -> Self {
+        Self {
+            seq: INVALID_SEQ,
+            body: MessageBody::ServiceMessage(ServiceMessage::PeerDisconnected {
+                peer_service_name,
+            }),
+        }
+    }
+
     pub fn new_call<T: Serialize>(caller_name: String, method_name: String, data: &T) -> Self {
+        Self::new_call_with_headers(caller_name, method_name, data, HashMap::new())
+    }
+
+    pub fn new_call_with_headers<T: Serialize>(
+        caller_name: String,
+        method_name: String,
+        data: &T,
+        headers: HashMap<String, String>,
+    ) -> Self {
         Self {
             seq: INVALID_SEQ,
             body: MessageBody::MethodCall {
                 caller_name,
                 method_name,
                 params: bson::to_bson(&data).unwrap(),
+                deadline: None,
+                headers,
+                call_seq: 0,
             },
         }
     }
 
-    pub fn new_subscription(subscriber_name: String, signal_name: String) -> Self {
+    pub fn new_subscription(
+        subscriber_name: String,
+        signal_name: String,
+        subscription_seq: u64,
+        filter: Option<Filter>,
+    ) -> Self {
         Self {
             seq: INVALID_SEQ,
             body: MessageBody::SignalSubscription {
                 subscriber_name,
                 signal_name,
+                subscription_seq,
+                filter,
             },
         }
     }
 
-    pub fn new_watch(subscriber_name: String, state_name: String) -> Self {
+    pub fn new_watch(subscriber_name: String, state_name: String, subscription_seq: u64) -> Self {
         Self {
             seq: INVALID_SEQ,
             body: MessageBody::StateSubscription {
                 subscriber_name,
                 state_name,
+                subscription_seq,
             },
         }
     }
@@ -149,6 +293,14 @@ pub enum EitherMessage {
     NeedMoreData(usize),
 }
 
+/// Peer process credentials. The hub reads them from the peer hub connection
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCredentials {
+    pub pid: Option<i32>,
+    pub uid: u32,
+    pub gid: u32,
+}
+
 /// Internal service message
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ServiceMessage {
@@ -164,10 +316,19 @@ pub enum ServiceMessage {
         await_connection: bool,
     },
     /// If one Client performs connection, other Client receives this message to make
-    /// p2p connection. Right after the messge we need to red peer UDS file descriptor
-    IncomingPeerFd { peer_service_name: String },
+    /// p2p connection. Right after the messge we need to red peer UDS file descriptor.
+    /// *peer_credentials* are the peer process credentials known to the hub
+    IncomingPeerFd {
+        peer_service_name: String,
+        #[serde(default)]
+        peer_credentials: Option<PeerCredentials>,
+    },
     /// This one is internal message to return incoming FD to a caller
     PeerFd(RawFd),
+    /// Privileged hub admin request. See [AdminRequest] for possible requests
+    Admin(AdminRequest),
+    /// Client notifies the hub that p2p connection to *peer_service_name* was shut down
+    PeerDisconnected { peer_service_name: String },
 }
 
 impl IntoMessage for ServiceMessage {
@@ -198,10 +359,16 @@ impl Display for ServiceMessage {
                 "Connection request to '{}'. Will await?: {}",
                 peer_service_name, await_connection
             ),
-            Self::IncomingPeerFd { peer_service_name } => {
+            Self::IncomingPeerFd {
+                peer_service_name, ..
+            } => {
                 write!(f, "Incoming FD for a peer '{}'", peer_service_name)
             }
             Self::PeerFd(_) => panic!("Should never be accessible outside of the lib"),
+            Self::Admin(request) => write!(f, "Hub admin request: {}", request),
+            Self::PeerDisconnected { peer_service_name } => {
+                write!(f, "Peer '{}' disconnected", peer_service_name)
+            }
         }
     }
 }
@@ -215,6 +382,20 @@ pub enum Response {
     Signal(Bson),
     StateChanged(Bson),
     Error(errors::Error),
+    /// Error returned by a method handler itself. Contains serialized user error
+    MethodError(Bson),
+    /// Streaming method call item
+    StreamItem(Bson),
+    /// Streaming method call or channel has no more items
+    StreamEnd,
+    /// Channel item from the side, which handles the channel
+    ChannelData(Bson),
+    /// Channel opener consumed a number of items
+    ChannelCredit(u32),
+    /// Side, which handles the channel won't send more items
+    ChannelClose,
+    /// Signal subscriber fell behind. Contains number of dropped emissions
+    Lagged(u64),
 }
 
 impl IntoMessage for Response {
@@ -235,6 +416,13 @@ impl Display for Response {
             Self::Signal(bson) => write!(f, "Signal emitted: {}", bson),
             Self::StateChanged(bson) => write!(f, "State changed: {}", bson),
             Self::Error(err) => write!(f, "Error: {}", err),
+            Self::MethodError(bson) => write!(f, "Method error: {}", bson),
+            Self::StreamItem(bson) => write!(f, "Stream item: {}", bson),
+            Self::StreamEnd => write!(f, "End of stream"),
+            Self::ChannelData(bson) => write!(f, "Channel data: {}", bson),
+            Self::ChannelCredit(credits) => write!(f, "Channel credit: {}", credits),
+            Self::ChannelClose => write!(f, "Channel closed"),
+            Self::Lagged(missed) => write!(f, "Subscriber lagged by {} emissions", missed),
         }
     }
 }
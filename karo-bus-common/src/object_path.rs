@@ -0,0 +1,69 @@
+use std::collections::BTreeSet;
+
+use crate::errors::Error;
+
+/// Service root object. Endpoints registered directly on the bus belong to it
+pub const ROOT_OBJECT_PATH: &str = "/";
+
+/// Check if the **path** is a valid object path: `/` or `/segment/segment..`.
+/// Segments are not empty and contain only alphanumeric characters, `_`, `-` and `.`
+pub fn validate(path: &str) -> Result<(), Error> {
+    if path == ROOT_OBJECT_PATH {
+        return Ok(());
+    }
+
+    let valid = match path.strip_prefix('/') {
+        Some(segments) => segments.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        }),
+        None => false,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidObjectPath(path.into()))
+    }
+}
+
+/// Endpoint name used on the wire and in the endpoint registers.
+/// Root object endpoints keep their names, others are prefixed with the object path
+pub fn qualify(path: &str, endpoint_name: &str) -> String {
+    if path == ROOT_OBJECT_PATH {
+        endpoint_name.into()
+    } else {
+        format!("{}/{}", path, endpoint_name)
+    }
+}
+
+/// Split qualified endpoint name into the object path and the endpoint name
+pub fn split(qualified_name: &str) -> (&str, &str) {
+    if !qualified_name.starts_with('/') {
+        return (ROOT_OBJECT_PATH, qualified_name);
+    }
+
+    match qualified_name.rsplit_once('/') {
+        Some(("", name)) => (ROOT_OBJECT_PATH, name),
+        Some((path, name)) => (path, name),
+        None => (ROOT_OBJECT_PATH, qualified_name),
+    }
+}
+
+/// Direct children of the **path** in the tree of **objects**. Intermediate
+/// nodes are listed even if there is no object registered at the node itself
+pub fn children<'a>(path: &str, objects: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let prefix = format!("{}/", path.trim_end_matches('/'));
+
+    objects
+        .into_iter()
+        .filter_map(|object| object.strip_prefix(&prefix))
+        .filter_map(|rest| rest.split('/').next())
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| format!("{}{}", prefix, segment))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
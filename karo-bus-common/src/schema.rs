@@ -0,0 +1,339 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    sync::Arc,
+};
+
+use bson::{Bson, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+
+/// Structured description of a BSON value. Used by the tooling to validate and build
+/// endpoint arguments without knowing Rust types
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Schema {
+    /// Unit, or missing value
+    Null,
+    Bool,
+    /// Any integer type
+    Int,
+    /// Floating point number. Integers are accepted as well
+    Float,
+    String,
+    /// Byte buffer
+    Binary,
+    /// File descriptor. Can be sent by bus services only
+    Fd,
+    /// Value or null
+    Optional {
+        value: Box<Schema>,
+    },
+    /// Sequence of same type items
+    Array {
+        items: Box<Schema>,
+    },
+    /// Fixed size sequence. Rust tuples are serialized as arrays
+    Tuple {
+        items: Vec<Schema>,
+    },
+    /// Document with arbitrary keys
+    Map {
+        values: Box<Schema>,
+    },
+    /// Structure
+    Object {
+        name: String,
+        fields: Vec<SchemaField>,
+    },
+    /// Enumeration of unit variants. Serialized as a variant name
+    Enum {
+        name: String,
+        variants: Vec<String>,
+    },
+    /// Value of an unknown structure
+    Any,
+}
+
+/// Structure field
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SchemaField {
+    pub name: String,
+    pub schema: Schema,
+}
+
+impl SchemaField {
+    pub fn new(name: &str, schema: Schema) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+        }
+    }
+}
+
+impl Schema {
+    /// Check if the **value** matches the schema.
+    /// **Returns** [Error::InvalidParameters] with a path to the first mismatch
+    pub fn validate(&self, value: &Bson) -> Result<(), Error> {
+        self.validate_at("value", value)
+            .map_err(Error::InvalidParameters)
+    }
+
+    fn validate_at(&self, path: &str, value: &Bson) -> Result<(), String> {
+        let matches =
+            match (self, value) {
+                (Schema::Any, _) => true,
+                (Schema::Null, Bson::Null) => true,
+                (Schema::Bool, Bson::Boolean(_)) => true,
+                (Schema::Int, Bson::Int32(_) | Bson::Int64(_)) => true,
+                (Schema::Float, Bson::Double(_) | Bson::Int32(_) | Bson::Int64(_)) => true,
+                (Schema::String, Bson::String(_)) => true,
+                (Schema::Binary, Bson::Binary(_) | Bson::Document(_)) => true,
+                (Schema::Binary, Bson::Array(array)) => {
+                    return array.iter().enumerate().try_for_each(|(i, item)| {
+                        Schema::Int.validate_at(&format!("{}[{}]", path, i), item)
+                    })
+                }
+                (Schema::Fd, Bson::Document(_)) => true,
+                (Schema::Optional { .. }, Bson::Null) => true,
+                (Schema::Optional { value: schema }, value) => {
+                    return schema.validate_at(path, value)
+                }
+                (Schema::Array { items }, Bson::Array(array)) => {
+                    return array.iter().enumerate().try_for_each(|(i, item)| {
+                        items.validate_at(&format!("{}[{}]", path, i), item)
+                    })
+                }
+                (Schema::Tuple { items }, Bson::Array(array)) => {
+                    if items.len() != array.len() {
+                        return Err(format!(
+                            "{}: expected {} items, got {}",
+                            path,
+                            items.len(),
+                            array.len()
+                        ));
+                    }
+
+                    return items.iter().zip(array).enumerate().try_for_each(
+                        |(i, (schema, item))| schema.validate_at(&format!("{}[{}]", path, i), item),
+                    );
+                }
+                (Schema::Map { values }, Bson::Document(document)) => {
+                    return document.iter().try_for_each(|(key, value)| {
+                        values.validate_at(&format!("{}.{}", path, key), value)
+                    })
+                }
+                (Schema::Object { fields, .. }, Bson::Document(document)) => {
+                    return fields.iter().try_for_each(|field| {
+                        let field_path = format!("{}.{}", path, field.name);
+
+                        match document.get(&field.name) {
+                            Some(value) => field.schema.validate_at(&field_path, value),
+                            // Missing options deserialize into None
+                            None if matches!(field.schema, Schema::Optional { .. }) => Ok(()),
+                            None => Err(format!("{}: missing field", field_path)),
+                        }
+                    });
+                }
+                (Schema::Enum { variants, .. }, Bson::String(variant)) => {
+                    if !variants.contains(variant) {
+                        return Err(format!(
+                            "{}: unknown variant `{}`, expected one of {}",
+                            path,
+                            variant,
+                            variants.join(", ")
+                        ));
+                    }
+
+                    true
+                }
+                _ => false,
+            };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(format!(
+                "{}: expected {}, got {}",
+                path,
+                self,
+                type_label(value)
+            ))
+        }
+    }
+
+    /// Build a value, which matches the schema. Used as an argument template
+    pub fn template(&self) -> Bson {
+        match self {
+            Schema::Null | Schema::Optional { .. } | Schema::Fd | Schema::Any => Bson::Null,
+            Schema::Bool => Bson::Boolean(false),
+            Schema::Int => Bson::Int64(0),
+            Schema::Float => Bson::Double(0.0),
+            Schema::String => Bson::String(String::new()),
+            Schema::Binary => Bson::Array(vec![]),
+            Schema::Array { items } => Bson::Array(vec![items.template()]),
+            Schema::Tuple { items } => Bson::Array(items.iter().map(Schema::template).collect()),
+            Schema::Map { .. } => Bson::Document(Document::new()),
+            Schema::Object { fields, .. } => Bson::Document(
+                fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.schema.template()))
+                    .collect(),
+            ),
+            Schema::Enum { variants, .. } => variants
+                .first()
+                .map(|variant| Bson::String(variant.clone()))
+                .unwrap_or(Bson::Null),
+        }
+    }
+}
+
+fn type_label(value: &Bson) -> &'static str {
+    match value {
+        Bson::Null => "null",
+        Bson::Boolean(_) => "bool",
+        Bson::Int32(_) | Bson::Int64(_) => "int",
+        Bson::Double(_) => "float",
+        Bson::String(_) => "string",
+        Bson::Binary(_) => "binary",
+        Bson::Array(_) => "array",
+        Bson::Document(_) => "object",
+        _ => "unsupported value",
+    }
+}
+
+impl Display for Schema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schema::Null => write!(f, "null"),
+            Schema::Bool => write!(f, "bool"),
+            Schema::Int => write!(f, "int"),
+            Schema::Float => write!(f, "float"),
+            Schema::String => write!(f, "string"),
+            Schema::Binary => write!(f, "binary"),
+            Schema::Fd => write!(f, "fd"),
+            Schema::Optional { value } => write!(f, "{}?", value),
+            Schema::Array { items } => write!(f, "[{}]", items),
+            Schema::Tuple { items } => {
+                let items: Vec<String> = items.iter().map(ToString::to_string).collect();
+                write!(f, "({})", items.join(", "))
+            }
+            Schema::Map { values } => write!(f, "{{string: {}}}", values),
+            Schema::Object { name, .. } | Schema::Enum { name, .. } => write!(f, "{}", name),
+            Schema::Any => write!(f, "any"),
+        }
+    }
+}
+
+/// Types, which can describe their BSON representation.
+/// Use `#[derive(BusSchema)]` from the bus library for own structures
+pub trait BusSchema {
+    fn schema() -> Schema;
+}
+
+macro_rules! impl_schema {
+    ($schema:expr => $($ty:ty),*) => {
+        $(
+            impl BusSchema for $ty {
+                fn schema() -> Schema {
+                    $schema
+                }
+            }
+        )*
+    };
+}
+
+impl_schema!(Schema::Null => ());
+impl_schema!(Schema::Bool => bool);
+impl_schema!(Schema::Int => i8, i16, i32, i64, u8, u16, u32, u64, isize, usize);
+impl_schema!(Schema::Float => f32, f64);
+impl_schema!(Schema::String => String, str, char);
+impl_schema!(Schema::Any => Bson, Document);
+
+impl<T: BusSchema + ?Sized> BusSchema for &T {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+impl<T: BusSchema + ?Sized> BusSchema for Box<T> {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+impl<T: BusSchema + ?Sized> BusSchema for Arc<T> {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+impl<T: BusSchema> BusSchema for Option<T> {
+    fn schema() -> Schema {
+        Schema::Optional {
+            value: Box::new(T::schema()),
+        }
+    }
+}
+
+macro_rules! impl_array_schema {
+    ($($ty:ty),*) => {
+        $(
+            impl<T: BusSchema> BusSchema for $ty {
+                fn schema() -> Schema {
+                    Schema::Array {
+                        items: Box::new(T::schema()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_array_schema!([T], Vec<T>, VecDeque<T>, HashSet<T>, BTreeSet<T>);
+
+impl<T: BusSchema, const N: usize> BusSchema for [T; N] {
+    fn schema() -> Schema {
+        Schema::Tuple {
+            items: (0..N).map(|_| T::schema()).collect(),
+        }
+    }
+}
+
+impl<V: BusSchema> BusSchema for HashMap<String, V> {
+    fn schema() -> Schema {
+        Schema::Map {
+            values: Box::new(V::schema()),
+        }
+    }
+}
+
+impl<V: BusSchema> BusSchema for BTreeMap<String, V> {
+    fn schema() -> Schema {
+        Schema::Map {
+            values: Box::new(V::schema()),
+        }
+    }
+}
+
+macro_rules! impl_tuple_schema {
+    ($($name:ident),+) => {
+        impl<$($name: BusSchema),+> BusSchema for ($($name,)+) {
+            fn schema() -> Schema {
+                Schema::Tuple {
+                    items: vec![$($name::schema()),+],
+                }
+            }
+        }
+    };
+}
+
+impl_tuple_schema!(A);
+impl_tuple_schema!(A, B);
+impl_tuple_schema!(A, B, C);
+impl_tuple_schema!(A, B, C, D);
+impl_tuple_schema!(A, B, C, D, E);
+impl_tuple_schema!(A, B, C, D, E, F);
+impl_tuple_schema!(A, B, C, D, E, F, G);
+impl_tuple_schema!(A, B, C, D, E, F, G, H);
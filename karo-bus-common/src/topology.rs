@@ -0,0 +1,58 @@
+use std::fmt::{Display, Write};
+
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+/// Peer connection brokered by the hub. Directed from the service, which requested
+/// the connection, to the target service
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TopologyEdge {
+    pub requester_service_name: String,
+    pub target_service_name: String,
+    /// Connection creation time. Milliseconds since UNIX epoch
+    pub created_at_ms: u64,
+}
+
+/// Bus connection graph
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TopologyData {
+    pub edges: Vec<TopologyEdge>,
+}
+
+impl TopologyData {
+    /// Export the graph in Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph karo_bus {\n");
+
+        for edge in self.edges.iter() {
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                edge.requester_service_name.replace('"', "\\\""),
+                edge.target_service_name.replace('"', "\\\""),
+                edge.created_at_ms
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl Display for TopologyData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for edge in self.edges.iter() {
+            writeln!(
+                f,
+                "{} {} {} (since {})",
+                edge.requester_service_name,
+                "-->".bright_green(),
+                edge.target_service_name,
+                edge.created_at_ms
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use karo_bus_common::{
+    compat::{check, Severity},
+    inspect_data::{EndpointMetadata, InspectData, MethodSchema},
+    schema::{BusSchema, Schema, SchemaField},
+};
+
+fn setpoint_schema(extra_field: Option<SchemaField>) -> Schema {
+    let mut fields = vec![
+        SchemaField::new("zone", String::schema()),
+        SchemaField::new("value", f64::schema()),
+    ];
+    fields.extend(extra_field);
+
+    Schema::Object {
+        name: "Setpoint".into(),
+        fields,
+    }
+}
+
+fn service_v1() -> InspectData {
+    let mut data = InspectData::new();
+
+    data.methods.push("set(Setpoint) -> bool".into());
+    data.methods.push("reset(()) -> ()".into());
+    data.signals.push("overheated: f64".into());
+    data.states.push("temperature: i32".into());
+
+    data.method_schemas.insert(
+        "set".into(),
+        MethodSchema {
+            params: setpoint_schema(None),
+            returns: bool::schema(),
+        },
+    );
+    data.state_schemas
+        .insert("temperature".into(), i32::schema());
+
+    data
+}
+
+#[test]
+fn test_same_interface() {
+    let report = check(&service_v1(), &service_v1());
+
+    assert!(report.issues.is_empty());
+    assert!(!report.is_breaking());
+}
+
+#[test]
+fn test_removed_endpoints() {
+    let mut new = service_v1();
+    new.methods.retain(|method| !method.starts_with("reset"));
+    new.signals.clear();
+    new.signals.push("cooled: f64".into());
+
+    let report = check(&service_v1(), &new);
+    assert!(report.is_breaking());
+
+    let removed: Vec<&str> = report
+        .issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Breaking)
+        .map(|issue| issue.endpoint_name.as_str())
+        .collect();
+    assert_eq!(removed, vec!["reset", "overheated"]);
+
+    // New endpoints don't break existing peers
+    let added = report
+        .issues
+        .iter()
+        .find(|issue| issue.endpoint_name == "cooled")
+        .unwrap();
+    assert_eq!(added.severity, Severity::Compatible);
+}
+
+#[test]
+fn test_type_name_changes() {
+    let mut new = service_v1();
+    new.signals = vec!["overheated: String".into()];
+
+    let report = check(&service_v1(), &new);
+    assert!(report.is_breaking());
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].endpoint_type, "signal");
+}
+
+#[test]
+fn test_parameter_changes() {
+    // Optional parameter fields can be added
+    let mut new = service_v1();
+    new.method_schemas.get_mut("set").unwrap().params =
+        setpoint_schema(Some(SchemaField::new("mode", Option::<String>::schema())));
+
+    let report = check(&service_v1(), &new);
+    assert!(!report.is_breaking(), "{}", report);
+
+    // Required ones break existing callers
+    new.method_schemas.get_mut("set").unwrap().params =
+        setpoint_schema(Some(SchemaField::new("mode", String::schema())));
+
+    let report = check(&service_v1(), &new);
+    assert!(report.is_breaking());
+    assert!(report.issues[0].message.contains("parameters.mode"));
+}
+
+#[test]
+fn test_return_and_state_changes() {
+    // Existing callers can't read an optional return value
+    let mut new = service_v1();
+    new.method_schemas.get_mut("set").unwrap().returns = Option::<bool>::schema();
+
+    let report = check(&service_v1(), &new);
+    assert!(report.is_breaking());
+
+    // Existing watchers read integers as floats, but not vice versa
+    let mut new = service_v1();
+    new.states = vec!["temperature: f64".into()];
+    new.state_schemas
+        .insert("temperature".into(), f64::schema());
+
+    assert!(check(&service_v1(), &new).is_breaking());
+
+    let mut old_float = service_v1();
+    old_float.states = vec!["temperature: f64".into()];
+    old_float
+        .state_schemas
+        .insert("temperature".into(), f64::schema());
+
+    let report = check(&old_float, &service_v1());
+    assert!(!report.is_breaking(), "{}", report);
+    assert_eq!(report.issues.len(), 1);
+}
+
+#[test]
+fn test_deprecations() {
+    let mut new = service_v1();
+    new.method_metadata.insert(
+        "reset".into(),
+        EndpointMetadata::new().deprecated("2.0", Some("set")),
+    );
+
+    let report = check(&service_v1(), &new);
+    assert!(!report.is_breaking());
+    assert_eq!(report.issues.len(), 1);
+    assert!(report.issues[0].message.contains("deprecated since 2.0"));
+
+    // Dumps are passed around as JSON
+    let json = serde_json::to_string(&new).unwrap();
+    let parsed: InspectData = serde_json::from_str(&json).unwrap();
+    assert!(check(&new, &parsed).issues.is_empty());
+}
@@ -0,0 +1,71 @@
+use bson::{bson, Bson};
+
+use karo_bus_common::filter::Filter;
+
+fn reading(zone: &str, value: f64) -> Bson {
+    bson!({
+        "zone": zone,
+        "value": value,
+        "sensor": { "id": 3, "tags": ["indoor", "floor"] },
+    })
+}
+
+#[test]
+fn test_field_matches() {
+    let kitchen = reading("kitchen", 21.5);
+
+    assert!(Filter::eq("zone", "kitchen").matches(&kitchen));
+    assert!(!Filter::eq("zone", "garage").matches(&kitchen));
+    assert!(Filter::ne("zone", "garage").matches(&kitchen));
+
+    // Nested fields and array items
+    assert!(Filter::eq("sensor.id", 3).matches(&kitchen));
+    assert!(Filter::eq("sensor.tags.1", "floor").matches(&kitchen));
+    assert!(Filter::exists("sensor.tags").matches(&kitchen));
+
+    // Missing fields don't match anything
+    assert!(!Filter::exists("sensor.name").matches(&kitchen));
+    assert!(!Filter::ne("sensor.name", "x").matches(&kitchen));
+    assert!(!Filter::eq("zone.name", "kitchen").matches(&kitchen));
+}
+
+#[test]
+fn test_ranges() {
+    let kitchen = reading("kitchen", 21.5);
+
+    // Integers and floats are compared by value
+    assert!(Filter::gt("value", 20).matches(&kitchen));
+    assert!(Filter::le("value", 21.5).matches(&kitchen));
+    assert!(!Filter::lt("value", 21.5).matches(&kitchen));
+    assert!(Filter::between("value", 18, 25).matches(&kitchen));
+    assert!(!Filter::between("value", 22, 25).matches(&kitchen));
+    assert!(Filter::eq("sensor.id", 3.0).matches(&kitchen));
+
+    // Values of different types aren't comparable
+    assert!(!Filter::gt("zone", 1).matches(&kitchen));
+    assert!(Filter::ge("zone", "k").matches(&kitchen));
+
+    // Scalar signals are addressed with an empty path
+    assert!(Filter::gt("", 10).matches(&Bson::Int32(11)));
+    assert!(!Filter::gt("", 10).matches(&Bson::Int64(10)));
+}
+
+#[test]
+fn test_combinations() {
+    let filter = Filter::eq("zone", "kitchen")
+        .or(Filter::eq("zone", "garage"))
+        .and(!Filter::gt("value", 20));
+
+    assert!(filter.matches(&reading("garage", 15.0)));
+    assert!(!filter.matches(&reading("garage", 25.0)));
+    assert!(!filter.matches(&reading("hall", 15.0)));
+
+    assert_eq!(
+        filter.to_string(),
+        "((zone == \"kitchen\") || (zone == \"garage\")) && (!(value > 20))"
+    );
+
+    // Filters are sent with subscription requests
+    let parsed: Filter = bson::from_bson(bson::to_bson(&filter).unwrap()).unwrap();
+    assert_eq!(parsed, filter);
+}
@@ -0,0 +1,74 @@
+use karo_bus_common::{
+    inspect_data::{EndpointMetadata, InspectData},
+    object_path::{children, qualify, split, validate},
+};
+
+#[test]
+fn test_object_path_validation() {
+    assert!(validate("/").is_ok());
+    assert!(validate("/devices/eth0").is_ok());
+    assert!(validate("/devices/usb-1.2_a").is_ok());
+
+    assert!(validate("").is_err());
+    assert!(validate("devices").is_err());
+    assert!(validate("/devices/").is_err());
+    assert!(validate("//devices").is_err());
+    assert!(validate("/devices/eth 0").is_err());
+    assert!(validate("/devices:eth0").is_err());
+}
+
+#[test]
+fn test_qualified_names() {
+    assert_eq!(qualify("/", "set"), "set");
+    assert_eq!(qualify("/devices/eth0", "set"), "/devices/eth0/set");
+
+    assert_eq!(split("set"), ("/", "set"));
+    assert_eq!(split("/set"), ("/", "set"));
+    assert_eq!(split("/devices/eth0/set"), ("/devices/eth0", "set"));
+}
+
+#[test]
+fn test_object_children() {
+    let objects = vec![
+        "/devices/eth0".to_string(),
+        "/devices/eth1".to_string(),
+        "/power".to_string(),
+    ];
+
+    assert_eq!(children("/", &objects), vec!["/devices", "/power"]);
+    assert_eq!(
+        children("/devices", &objects),
+        vec!["/devices/eth0", "/devices/eth1"]
+    );
+    assert!(children("/devices/eth0", &objects).is_empty());
+}
+
+#[test]
+fn test_object_inspection() {
+    let mut data = InspectData::new();
+    data.methods.push("list(()) -> i32".into());
+    data.methods.push("/devices/eth0/name(()) -> String".into());
+    data.states.push("/devices/eth0/up: bool".into());
+    data.method_metadata.insert(
+        "/devices/eth0/name".into(),
+        EndpointMetadata::new().version("1.0"),
+    );
+
+    let objects = vec!["/devices/eth0".to_string()];
+
+    let root = data.object_view("/", &objects);
+    assert_eq!(root.methods, vec!["list(()) -> i32"]);
+    assert!(root.states.is_empty());
+    assert_eq!(root.children, vec!["/devices"]);
+
+    let eth0 = data.object_view("/devices/eth0", &objects);
+    assert_eq!(eth0.methods, vec!["name(()) -> String"]);
+    assert_eq!(eth0.states, vec!["up: bool"]);
+    assert!(eth0.method_metadata.contains_key("name"));
+
+    data.remove_method("/devices/eth0/name");
+    data.remove_state("/devices/eth0/up");
+    assert_eq!(data.methods, vec!["list(()) -> i32"]);
+    assert!(data.states.is_empty());
+    assert!(data.method_metadata.is_empty());
+}
@@ -0,0 +1,79 @@
+use bson::{bson, Bson};
+
+use karo_bus_common::schema::{BusSchema, Schema, SchemaField};
+
+fn point_schema() -> Schema {
+    Schema::Object {
+        name: "Point".into(),
+        fields: vec![
+            SchemaField::new("x", f64::schema()),
+            SchemaField::new("y", f64::schema()),
+            SchemaField::new("label", Option::<String>::schema()),
+        ],
+    }
+}
+
+#[test]
+fn test_primitive_schemas() {
+    assert_eq!(<()>::schema(), Schema::Null);
+    assert_eq!(u8::schema(), Schema::Int);
+    assert_eq!(f32::schema(), Schema::Float);
+    assert_eq!(String::schema(), Schema::String);
+    assert_eq!(
+        Vec::<bool>::schema(),
+        Schema::Array {
+            items: Box::new(Schema::Bool)
+        }
+    );
+    assert_eq!(
+        <(i32, String)>::schema(),
+        Schema::Tuple {
+            items: vec![Schema::Int, Schema::String]
+        }
+    );
+}
+
+#[test]
+fn test_schema_validation() {
+    assert!(Schema::Int.validate(&Bson::Int64(42)).is_ok());
+    assert!(Schema::Float.validate(&Bson::Int32(42)).is_ok());
+    assert!(Schema::Int.validate(&Bson::Double(4.2)).is_err());
+    assert!(<(i32, String)>::schema()
+        .validate(&bson!([1, "one"]))
+        .is_ok());
+    assert!(<(i32, String)>::schema().validate(&bson!([1])).is_err());
+
+    let schema = point_schema();
+    assert!(schema.validate(&bson!({ "x": 1.5, "y": 2 })).is_ok());
+    assert!(schema
+        .validate(&bson!({ "x": 1.5, "y": 2, "label": "a" }))
+        .is_ok());
+
+    let err = schema
+        .validate(&bson!({ "x": 1.5, "y": "2" }))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("value.y"), "{}", err);
+
+    assert!(schema.validate(&bson!({ "x": 1.5 })).is_err());
+
+    let mode = Schema::Enum {
+        name: "Mode".into(),
+        variants: vec!["Heat".into(), "Cool".into()],
+    };
+    assert!(mode.validate(&bson!("Cool")).is_ok());
+    assert!(mode.validate(&bson!("Dry")).is_err());
+}
+
+#[test]
+fn test_schema_template() {
+    let schema = point_schema();
+    let template = schema.template();
+
+    assert_eq!(template, bson!({ "x": 0.0, "y": 0.0, "label": null }));
+    assert!(schema.validate(&template).is_ok());
+
+    // Schemas are sent in the inspection data
+    let serialized = bson::to_bson(&schema).unwrap();
+    assert_eq!(bson::from_bson::<Schema>(serialized).unwrap(), schema);
+}
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use bson::Bson;
 use clap::{self, Parser};
 use colored::*;
 use log::{LevelFilter, *};
@@ -7,7 +8,7 @@ use rustyline::error::ReadlineError;
 use rustyline::{ColorMode, Config, Editor, Result};
 use serde_json::Value;
 
-use karo_bus_common::inspect_data::CONNECT_SERVICE_NAME;
+use karo_bus_common::inspect_data::{InspectData, CONNECT_SERVICE_NAME, INSPECT_METHOD};
 use karo_bus_lib::{peer::Peer, Bus};
 
 /// Karo bus connect
@@ -35,7 +36,13 @@ fn print_help() {
     println!(
         "\t\twhich deserializes into the method argument type (use tracing logs and `bus-monitor`"
     );
-    println!("\t\tto find how method parameters serialize)");
+    println!(
+        "\t\tto find how method parameters serialize). Described methods validate the argument"
+    );
+    println!(
+        "\t{} {{method_name}} Print an argument template for a described method",
+        "template".bright_yellow()
+    );
 
     println!(
         "\t{} {{signal_name}} Subscribe on the signal",
@@ -58,6 +65,10 @@ fn format_response(endpoint_type: &str, endpoint_name: &str, json: &Value) {
     );
 }
 
+async fn inspect(service: &mut Peer) -> anyhow::Result<InspectData> {
+    service.call::<(), InspectData>(INSPECT_METHOD, &()).await
+}
+
 async fn handle_input_line(service: &mut Peer, line: &String) -> bool {
     let words: Vec<&str> = line.split(' ').collect();
 
@@ -87,6 +98,18 @@ async fn handle_input_line(service: &mut Peer, line: &String) -> bool {
             }
         };
 
+        // Check the argument if the service describes the method
+        if let Ok(inspect_data) = inspect(service).await {
+            if let Some(schema) = inspect_data.method_schemas.get(words[1]) {
+                let params = Bson::try_from(json.clone()).unwrap_or(Bson::Null);
+
+                if let Err(err) = schema.params.validate(&params) {
+                    println!("{}. Expected {}", err, schema.params);
+                    return false;
+                }
+            }
+        }
+
         match service.call(words[1], &json).await {
             Ok(response) => format_response("Method", words[1], &response),
             Err(err) => {
@@ -138,14 +161,29 @@ async fn handle_input_line(service: &mut Peer, line: &String) -> bool {
                 err.to_string()
             ),
         }
+    } else if words[0] == "template" {
+        if words.len() != 2 {
+            println!("Ivalid number of 'template' arguments given: no method name given. Use 'help' to see command syntax");
+            return false;
+        }
+
+        match inspect(service).await {
+            Ok(inspect_data) => match inspect_data.method_schemas.get(words[1]) {
+                Some(schema) => println!(
+                    "call {} {}",
+                    words[1],
+                    schema.params.template().into_relaxed_extjson()
+                ),
+                None => println!("Method '{}' is not described by the service", words[1]),
+            },
+            Err(err) => println!(
+                "Failed to inspect service '{}': {}",
+                service.name(),
+                err.to_string()
+            ),
+        }
     } else if words[0] == "inspect" {
-        match service
-            .call::<(), karo_bus_common::inspect_data::InspectData>(
-                karo_bus_common::inspect_data::INSPECT_METHOD,
-                &(),
-            )
-            .await
-        {
+        match inspect(service).await {
             Ok(resp) => println!("{}", resp),
             Err(err) => println!(
                 "Failed to inspect service '{}': {}",
@@ -0,0 +1,14 @@
+#!/bin/bash
+
+self_dir=$(dirname $(realpath "${0}"))
+
+echo -e "\e[32mInstalling bus ctl tool\e[0m"
+
+pushd ${self_dir} > /dev/null
+cargo build --release
+
+sudo mkdir -p /etc/karo/services/
+sudo cp -f karo.bus.ctl.service /etc/karo/services/
+
+sudo cp -f ../target/release/karo-bus-ctl /usr/bin/
+popd > /dev/null
@@ -0,0 +1,227 @@
+use std::{fmt::Display, fs, path::PathBuf};
+
+use clap::{self, Parser, Subcommand};
+use colored::*;
+use log::{LevelFilter, *};
+use serde::{de::DeserializeOwned, Serialize};
+
+use karo_bus_common::{
+    admin::{AdminRequest, HubStats, PendingConnectionInfo, ServiceInfo, CTL_SERVICE_NAME},
+    compat,
+    inspect_data::{InspectData, INSPECT_METHOD},
+    topology::TopologyData,
+};
+use karo_bus_lib::Bus;
+
+/// Karo bus hub control tool
+#[derive(Parser, Debug)]
+#[clap(version, about, long_about = None)]
+pub struct Args {
+    /// Log level: OFF, ERROR, WARN, INFO, DEBUG, TRACE
+    #[clap(short, long, value_parser, default_value_t = LevelFilter::Warn)]
+    pub log_level: log::LevelFilter,
+
+    /// Print output as JSON
+    #[clap(short, long, value_parser)]
+    pub json: bool,
+
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List registered services and their peer connections
+    Services,
+    /// List connection requests waiting for a target service to register
+    Pending,
+    /// Drop a service connection to the hub
+    Kick {
+        /// Service to disconnect
+        #[clap(value_parser)]
+        service_name: String,
+    },
+    /// Reread services policy from the service files
+    Reload,
+    /// Print hub statistics
+    Stats,
+    /// Print hub metrics in Prometheus text format
+    Metrics,
+    /// Print peer connections graph
+    Topology {
+        /// Print the graph in Graphviz DOT format
+        #[clap(long, value_parser)]
+        dot: bool,
+    },
+    /// Print service methods, signals, and states. Use with `--json` to dump
+    /// the interface for the `compat` command
+    Inspect {
+        /// Service to inspect
+        #[clap(value_parser)]
+        service_name: String,
+    },
+    /// Compare two JSON interface dumps of a service. Exits with an error
+    /// if the new interface breaks existing peers
+    Compat {
+        /// Previous interface dump
+        #[clap(value_parser)]
+        old: PathBuf,
+        /// New interface dump
+        #[clap(value_parser)]
+        new: PathBuf,
+    },
+}
+
+/// Print a list of entries either as JSON or one entry per line
+fn print_list<T: Serialize + Display>(entries: &Vec<T>, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(entries).unwrap());
+        return;
+    }
+
+    if entries.is_empty() {
+        println!("{}", "Nothing to show".bright_yellow());
+    }
+
+    for entry in entries {
+        println!("{}", entry);
+    }
+}
+
+fn print_entry<T: Serialize + Display>(entry: &T, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(entry).unwrap());
+    } else {
+        println!("{}", entry);
+    }
+}
+
+fn print_done(json: bool) {
+    if json {
+        println!("{{}}");
+    } else {
+        println!("{}", "Done".bright_green());
+    }
+}
+
+async fn admin_call<R: DeserializeOwned>(bus: &mut Bus, request: AdminRequest) -> R {
+    match bus.admin_call(request).await {
+        Ok(response) => response,
+        Err(err) => {
+            eprintln!("{} {}", "Hub request failed:".bright_red(), err.to_string());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn read_dump(path: &PathBuf) -> InspectData {
+    let dump = fs::read_to_string(path).and_then(|dump| {
+        serde_json::from_str(&dump)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    });
+
+    match dump {
+        Ok(dump) => dump,
+        Err(err) => {
+            eprintln!(
+                "{} {}: {}",
+                "Failed to read interface dump".bright_red(),
+                path.display(),
+                err.to_string()
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    debug!("Starting Karo bus ctl");
+
+    let args = Args::parse();
+
+    pretty_env_logger::formatted_builder()
+        .filter_level(args.log_level)
+        .init();
+
+    // Comparing dumps doesn't need the hub
+    if let Command::Compat { old, new } = &args.command {
+        let report = compat::check(&read_dump(old), &read_dump(new));
+        print_entry(&report, args.json);
+
+        if report.is_breaking() {
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    let mut bus = Bus::register(CTL_SERVICE_NAME)
+        .await
+        .expect("Failed to register ctl service");
+
+    debug!("Succesfully registered");
+
+    match args.command {
+        Command::Services => {
+            let services: Vec<ServiceInfo> = admin_call(&mut bus, AdminRequest::ListServices).await;
+            print_list(&services, args.json);
+        }
+        Command::Pending => {
+            let pending: Vec<PendingConnectionInfo> =
+                admin_call(&mut bus, AdminRequest::PendingConnections).await;
+            print_list(&pending, args.json);
+        }
+        Command::Kick { service_name } => {
+            admin_call::<()>(&mut bus, AdminRequest::Kick { service_name }).await;
+            print_done(args.json);
+        }
+        Command::Reload => {
+            admin_call::<()>(&mut bus, AdminRequest::ReloadPolicy).await;
+            print_done(args.json);
+        }
+        Command::Stats => {
+            let stats: HubStats = admin_call(&mut bus, AdminRequest::Stats).await;
+            print_entry(&stats, args.json);
+        }
+        Command::Metrics => {
+            let metrics: String = admin_call(&mut bus, AdminRequest::Metrics).await;
+
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&metrics).unwrap());
+            } else {
+                print!("{}", metrics);
+            }
+        }
+        Command::Topology { dot } => {
+            let topology: TopologyData = admin_call(&mut bus, AdminRequest::Topology).await;
+
+            if dot {
+                print!("{}", topology.to_dot());
+            } else {
+                print_entry(&topology, args.json);
+            }
+        }
+        Command::Inspect { service_name } => {
+            let inspect_data = match bus.connect(&service_name).await {
+                Ok(mut peer) => peer.call::<(), InspectData>(INSPECT_METHOD, &()).await,
+                Err(err) => Err(err),
+            };
+
+            match inspect_data {
+                Ok(inspect_data) => print_entry(&inspect_data, args.json),
+                Err(err) => {
+                    eprintln!(
+                        "{} {}",
+                        "Failed to inspect service:".bright_red(),
+                        err.to_string()
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Compat { .. } => unreachable!("Handled before connecting to the hub"),
+    }
+
+    Ok(())
+}
@@ -8,10 +8,56 @@ use karo_bus_common::SERVICE_FILES_DIR;
 #[clap(version, about, long_about = None)]
 pub struct Args {
     /// Log level: OFF, ERROR, WARN, INFO, DEBUG, TRACE
-    #[clap(short, long, value_parser, default_value_t = LevelFilter::Trace)]
+    #[clap(short, long, value_parser, default_value_t = LevelFilter::Info)]
     pub log_level: log::LevelFilter,
 
     /// Number of times to greet
     #[clap(short, long, value_parser, default_value_t = SERVICE_FILES_DIR.into())]
     pub service_files_dir: String,
+
+    /// Default max number of peers a service may request connection to.
+    /// Can be overriden in a service file. Unlimited if not set
+    #[clap(long, value_parser)]
+    pub max_peers: Option<usize>,
+
+    /// Default max number of pending connection requests a service may have.
+    /// Can be overriden in a service file. Unlimited if not set
+    #[clap(long, value_parser)]
+    pub max_pending_connections: Option<usize>,
+
+    /// Default max number of connection requests per second a service may send.
+    /// Can be overriden in a service file. Unlimited if not set
+    #[clap(long, value_parser)]
+    pub connection_rate: Option<usize>,
+
+    /// Localhost address to serve Prometheus metrics over HTTP at. E.g. `127.0.0.1:9090`
+    #[clap(long, value_parser)]
+    pub metrics_address: Option<String>,
+
+    /// Unix socket path to serve Prometheus metrics over HTTP at
+    #[clap(long, value_parser)]
+    pub metrics_socket: Option<String>,
+
+    /// Octal permissions of the metrics unix socket
+    #[clap(long, value_parser = parse_mode, default_value = "660")]
+    pub metrics_socket_mode: u32,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            log_level: LevelFilter::Info,
+            service_files_dir: SERVICE_FILES_DIR.into(),
+            max_peers: None,
+            max_pending_connections: None,
+            connection_rate: None,
+            metrics_address: None,
+            metrics_socket: None,
+            metrics_socket_mode: 0o660,
+        }
+    }
+}
+
+fn parse_mode(mode: &str) -> Result<u32, String> {
+    u32::from_str_radix(mode, 8).map_err(|err| format!("Invalid octal mode `{}`: {}", mode, err))
 }
@@ -15,7 +15,7 @@ use tokio::{
 use tokio_send_fd::SendFd;
 use uuid::Uuid;
 
-use crate::permissions::Permissions;
+use crate::{metrics::Metrics, permissions::Permissions};
 
 use super::hub::ClientRequest;
 use karo_bus_common::{
@@ -44,25 +44,39 @@ pub struct Client {
     uuid: Uuid,
     /// Client service name after registration
     service_name: Shared<String>,
+    /// Client process credentials. Set at registration
+    credentials: Shared<Option<UCred>>,
     /// Sender, which being pulled in the task
     task_tx: Sender<HubReponse>,
     /// Sender for clients to send requests to the hub
     hub_tx: Sender<ClientRequest>,
     /// Permissoins handle
     permissions: Arc<Permissions>,
+    /// Hub metrics
+    metrics: Arc<Metrics>,
 }
 
 impl Client {
+    pub fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
     pub fn service_name(&self) -> String {
         self.service_name.read().unwrap().clone()
     }
 
+    /// Client process credentials. None if the client is not registered yet
+    pub fn credentials(&self) -> Option<UCred> {
+        *self.credentials.read().unwrap()
+    }
+
     /// Start listening for incoming messages
     pub fn run(
         uuid: Uuid,
         hub_tx: Sender<ClientRequest>,
         mut socket: UnixStream,
         permissions: Arc<Permissions>,
+        metrics: Arc<Metrics>,
     ) -> Self {
         trace!("Starting new client with UUID {:?}", uuid);
 
@@ -71,9 +85,11 @@ impl Client {
         let client_handle = Self {
             uuid,
             service_name: Arc::new(RwLock::new(String::from(""))),
+            credentials: Arc::new(RwLock::new(None)),
             task_tx: client_tx,
             hub_tx,
             permissions,
+            metrics,
         };
         let mut this = client_handle.clone();
 
@@ -141,6 +157,32 @@ impl Client {
         }
     }
 
+    /// Request to shut down client connection. The client receives [Response::Shutdown]
+    /// with a given **reason**
+    pub async fn shutdown(&mut self, reason: &str) {
+        debug!(
+            "Shutting down service connection for `{}`: {}",
+            self.service_name(),
+            reason
+        );
+
+        let message = Response::Shutdown(reason.into()).into_message(999);
+
+        if self
+            .task_tx
+            .send(HubReponse::Shutdown(message))
+            .await
+            .is_err()
+        {
+            trace!("Client `{}` is already closed", self.service_name());
+        }
+    }
+
+    /// Wait until the client connection is closed
+    pub async fn closed(&self) {
+        self.task_tx.closed().await
+    }
+
     /// Request to send connection fd to a client
     pub async fn send_connection_fd(
         &mut self,
@@ -252,6 +294,11 @@ impl Client {
                         .await
                 }
                 ServiceMessage::Connect { .. } => self.handle_connect_message(message).await,
+                ServiceMessage::Admin(_) => self.handle_admin_message(message).await,
+                ServiceMessage::PeerDisconnected { .. } => {
+                    self.send_message_to_hub(message).await;
+                    None
+                }
                 m => {
                     warn!("Invalid message from a client: {:?}", m);
                     None
@@ -279,6 +326,7 @@ impl Client {
 
         if *protocol_version != messages::PROTOCOL_VERSION {
             warn!("Client with invalid protocol: {}", self.uuid);
+            Metrics::inc(&self.metrics.registrations_denied);
             return Some(BusError::InvalidProtocol.into_message(request.seq()));
         }
 
@@ -290,12 +338,14 @@ impl Client {
                 "Client is not allowed to register with name `{}`: {}",
                 service_name, err
             );
+            Metrics::inc(&self.metrics.registrations_denied);
             return Some(err.into_message(request.seq()));
         }
 
         // Service requested new service_name. We update our service name here.
         // In case we've failed to register service, we drop it anyway
         *(self.service_name.write().unwrap()) = service_name.clone();
+        *(self.credentials.write().unwrap()) = Some(user_credentials);
         trace!(
             "Assigned service name `{}` to a client with UUID {}",
             self.service_name.read().unwrap(),
@@ -327,6 +377,7 @@ impl Client {
                 "Client `{}` is not allowed to connect with `{}`: {}",
                 self_service_name, peer_service_name, err
             );
+            Metrics::inc(&self.metrics.connections_denied);
             return Some(err.into_message(request.seq()));
         }
 
@@ -336,6 +387,24 @@ impl Client {
         None
     }
 
+    /// Handle incoming hub admin request
+    async fn handle_admin_message(&mut self, request: Message) -> Option<Message> {
+        let self_service_name = self.service_name.read().unwrap().clone();
+
+        if let Err(err) = self.permissions.check_admin_allowed(&self_service_name) {
+            warn!(
+                "Client `{}` is not allowed to make hub admin requests: {}",
+                self_service_name, err
+            );
+            return Some(err.into_message(request.seq()));
+        }
+
+        // Notify hub about admin request
+        self.send_message_to_hub(request).await;
+
+        None
+    }
+
     // Sends a message to the hub through a channel
     async fn send_message_to_hub(&self, message: Message) {
         let service_name = self.service_name.read().unwrap().clone();
@@ -385,11 +454,12 @@ impl Drop for Client {
 
         let tx = self.task_tx.clone();
         tokio::spawn(async move {
-            tx.send(HubReponse::Shutdown(
-                Response::Shutdown(self_name).into_message(999),
-            ))
-            .await
-            .unwrap();
+            // Client task may have already exited
+            let _ = tx
+                .send(HubReponse::Shutdown(
+                    Response::Shutdown(self_name).into_message(999),
+                ))
+                .await;
         });
     }
 }
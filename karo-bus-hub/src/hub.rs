@@ -1,18 +1,40 @@
-use std::{collections::HashMap, fs, os::unix::prelude::PermissionsExt, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs,
+    os::unix::prelude::PermissionsExt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use bson::Bson;
 use karo_bus_common::{
     self as common,
+    admin::{AdminRequest, HubStats, PendingConnectionInfo, ServiceInfo},
     errors::Error as BusError,
-    messages::{IntoMessage, Message, MessageBody, Response, ServiceMessage},
+    messages::{IntoMessage, Message, MessageBody, PeerCredentials, Response, ServiceMessage},
 };
 use log::*;
+use serde::Serialize;
 use tokio::{
     net::{UnixListener, UnixStream},
     sync::mpsc::{self, Receiver, Sender},
+    time::{self, Instant as TokioInstant},
 };
 use uuid::Uuid;
 
-use crate::{args::Args, client::Client, permissions::Permissions};
+use crate::{
+    args::Args,
+    client::Client,
+    metrics::{self, Metrics},
+    permissions::Permissions,
+    quotas::{QuotaKind, QuotaTracker, Quotas},
+    topology::Topology,
+};
+
+/// Reason sent to the clients when the hub shuts down
+const SHUTDOWN_REASON: &str = "Hub is shutting down";
+/// Time to wait for clients to receive shutdown notification
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
 
 struct PendingConnectionRequest {
     requester_service_name: String,
@@ -45,11 +67,27 @@ pub struct Hub {
     permissions: Arc<Permissions>,
     /// If a client uses 'Bus::connect_await' from Karo lib, it's waiting for a peer connection in this map
     pending_connections: HashMap<String, Vec<PendingConnectionRequest>>,
+    /// Services resource usage and quotas
+    quotas: QuotaTracker,
+    /// Peer connections brokered by the hub
+    topology: Topology,
+    /// Hub start time
+    started: Instant,
+    /// Hub metrics
+    metrics: Arc<Metrics>,
+    /// Localhost TCP address to serve metrics at
+    metrics_address: Option<String>,
+    /// Unix socket path to serve metrics at
+    metrics_socket: Option<String>,
+    /// Metrics unix socket permissions
+    metrics_socket_mode: u32,
 }
 
 impl Hub {
     pub fn new(args: Args, shutdown_rx: Receiver<()>) -> Self {
         let (client_tx, hub_rx) = mpsc::channel::<ClientRequest>(32);
+        let quotas = QuotaTracker::new(Quotas::from(&args));
+        let metrics = Metrics::new(quotas.violation_counters());
 
         Self {
             client_tx,
@@ -59,6 +97,13 @@ impl Hub {
             clients: HashMap::new(),
             permissions: Arc::new(Permissions::new(&args.service_files_dir)),
             pending_connections: HashMap::new(),
+            quotas,
+            topology: Topology::new(),
+            started: Instant::now(),
+            metrics: Arc::new(metrics),
+            metrics_address: args.metrics_address.clone(),
+            metrics_socket: args.metrics_socket.clone(),
+            metrics_socket_mode: args.metrics_socket_mode,
         }
     }
 
@@ -77,6 +122,15 @@ impl Hub {
                 let socket_permissions = fs::Permissions::from_mode(0o666);
                 fs::set_permissions(socket_path.clone(), socket_permissions)?;
 
+                if let Some(ref address) = self.metrics_address {
+                    metrics::serve_tcp(address, self.metrics.clone()).await?;
+                }
+
+                if let Some(ref path) = self.metrics_socket {
+                    metrics::serve_unix(path, self.metrics_socket_mode, self.metrics.clone())
+                        .await?;
+                }
+
                 loop {
                     tokio::select! {
                         Ok((socket, address)) = listener.accept() => {
@@ -90,7 +144,10 @@ impl Hub {
                             self.handle_client_call(client_message).await
                         }
                         _ = self.shutdown_rx.recv() => {
+                            // Stop accepting new clients first
                             drop(listener);
+
+                            self.shutdown(SHUTDOWN_REASON).await;
                             return Ok(());
                         }
                     }
@@ -116,9 +173,11 @@ impl Hub {
             self.client_tx.clone(),
             socket,
             self.permissions.clone(),
+            self.metrics.clone(),
         );
 
         self.anonymous_clients.insert(uuid.clone(), client);
+        self.update_client_gauges();
     }
 
     /// Handle a message from a client
@@ -129,9 +188,43 @@ impl Hub {
                     .await
             }
             MessageBody::ServiceMessage(ServiceMessage::Connect { .. }) => {
+                if let Err(err) = self.quotas.check_connection_rate(&request.service_name) {
+                    Metrics::inc(&self.metrics.connections_denied);
+
+                    self.send_client_message(
+                        &request.service_name,
+                        err.into_message(request.message.seq()),
+                    )
+                    .await;
+                    return;
+                }
+
                 self.handle_new_connection_request(request.service_name, request.message)
                     .await
             }
+            MessageBody::ServiceMessage(ServiceMessage::PeerDisconnected { peer_service_name }) => {
+                debug!(
+                    "`{}` reported disconnection from `{}`",
+                    request.service_name, peer_service_name
+                );
+
+                // Services may report only their own connections
+                if !self.clients.contains_key(&request.service_name)
+                    || !self
+                        .topology
+                        .remove_connection(&request.service_name, peer_service_name)
+                {
+                    warn!(
+                        "Ignoring disconnection report from `{}`. Not connected to `{}`",
+                        request.service_name, peer_service_name
+                    );
+                    return;
+                }
+            }
+            MessageBody::ServiceMessage(ServiceMessage::Admin(_)) => {
+                self.handle_admin_request(request.service_name, request.message)
+                    .await
+            }
             MessageBody::Response(Response::Shutdown(_)) => {
                 self.handle_client_disconnection(&request.uuid, &request.service_name)
                     .await;
@@ -169,6 +262,8 @@ impl Hub {
                         service_name
                     );
 
+                    Metrics::inc(&self.metrics.registrations_denied);
+
                     client
                         .send_message(
                             &service_name,
@@ -183,11 +278,20 @@ impl Hub {
                         .await;
                     self.clients.insert(service_name.clone(), client);
 
+                    Metrics::inc(&self.metrics.registrations_accepted);
+
+                    let quotas = self
+                        .permissions
+                        .read_quotas(service_name, self.quotas.defaults());
+                    self.quotas.set_service_quotas(service_name, quotas);
+
                     // Check if we have pending connections to the client.
                     // If we do, we resolve all connection request by sending response
                     if let Some(pending_connection_requests) =
                         self.pending_connections.remove(service_name)
                     {
+                        self.update_pending_gauge();
+
                         for request in pending_connection_requests {
                             trace!(
                                 "Resolving connection request to {} from {}",
@@ -214,6 +318,8 @@ impl Hub {
                 e.unwrap();
             }
         }
+
+        self.update_client_gauges();
     }
 
     /// Handle peer connection message from a client
@@ -245,6 +351,7 @@ impl Hub {
                     "`{}` wants to connect to `{}`, which doesn't exist",
                     requester_service_name, target_service_name
                 );
+                Metrics::inc(&self.metrics.connections_denied);
 
                 match self.clients.get_mut(&requester_service_name) {
                     Some(client) => {
@@ -270,6 +377,7 @@ impl Hub {
                     "Service `{}` tries to connect to himself",
                     target_service_name,
                 );
+                Metrics::inc(&self.metrics.connections_denied);
 
                 match self.clients.get_mut(&requester_service_name) {
                     Some(client) => {
@@ -291,6 +399,26 @@ impl Hub {
                 return;
             }
 
+            // Reconnections to already connected peers are not accounted
+            let peers_check = if self
+                .topology
+                .is_connected(&requester_service_name, target_service_name)
+            {
+                Ok(())
+            } else {
+                let peers_count = self.topology.requested_peers_count(&requester_service_name);
+                self.quotas
+                    .check_peers(&requester_service_name, peers_count)
+            };
+
+            if let Err(err) = peers_check {
+                Metrics::inc(&self.metrics.connections_denied);
+
+                self.send_client_message(&requester_service_name, err.into_message(request.seq()))
+                    .await;
+                return;
+            }
+
             // Service to which our client wants to connect is not registered
             if !self.clients.contains_key(target_service_name) {
                 // Peer doesn't want to wait for connection
@@ -299,6 +427,7 @@ impl Hub {
                         "Failed to find a service `{}` to connect with `{}`",
                         target_service_name, requester_service_name
                     );
+                    Metrics::inc(&self.metrics.connections_denied);
 
                     match self.clients.get_mut(&requester_service_name) {
                         Some(client) => {
@@ -320,6 +449,27 @@ impl Hub {
                     return;
                 }
 
+                let pending_count = self
+                    .pending_connections
+                    .values()
+                    .flatten()
+                    .filter(|pending| pending.requester_service_name == requester_service_name)
+                    .count();
+
+                if let Err(err) = self
+                    .quotas
+                    .check_pending_connections(&requester_service_name, pending_count)
+                {
+                    Metrics::inc(&self.metrics.connections_denied);
+
+                    self.send_client_message(
+                        &requester_service_name,
+                        err.into_message(request.seq()),
+                    )
+                    .await;
+                    return;
+                }
+
                 // Peer wants to wait for a connection if service still not registered.
                 // Add it to the pending list and return. Now client is sitting and waiting for
                 // the response. See `handle_client_registration` for resolving code
@@ -335,12 +485,14 @@ impl Hub {
                         requester_service_name,
                         request,
                     });
+                self.update_pending_gauge();
                 return;
             }
 
             // Send descriptor to the requester
             let message = ServiceMessage::IncomingPeerFd {
                 peer_service_name: target_service_name.clone(),
+                peer_credentials: self.peer_credentials(target_service_name),
             }
             .into_message(request.seq());
 
@@ -364,6 +516,7 @@ impl Hub {
         // NOTE: It's duplicates, but it's possible that hub will send different message
         let message = ServiceMessage::IncomingPeerFd {
             peer_service_name: requester_service_name.clone(),
+            peer_credentials: self.peer_credentials(&requester_service_name),
         }
         .into_message(request.seq());
 
@@ -383,6 +536,10 @@ impl Hub {
             }
         }
 
+        self.topology
+            .add_connection(&requester_service_name, target_service_name);
+        Metrics::inc(&self.metrics.connections_brokered);
+
         info!(
             "Succesfully connected `{}` to `{}`",
             requester_service_name, target_service_name
@@ -392,10 +549,290 @@ impl Hub {
     /// Handle client disconnections
     async fn handle_client_disconnection(&mut self, uuid: &Uuid, service_name: &String) {
         self.anonymous_clients.remove(uuid);
+
+        // Kicked clients report disconnection after they are removed. The service
+        // may be registered by another client by then
+        if !matches!(self.clients.get(service_name), Some(client) if client.uuid() == uuid) {
+            trace!("Client {} is already removed", uuid);
+            self.update_client_gauges();
+            return;
+        }
+
         self.clients.remove(service_name);
+        // No one waits for the requests anymore. They would count towards
+        // the quota of the service registered under the same name later
+        self.remove_pending_connections(service_name);
+        self.quotas.remove_service(service_name);
+        self.topology.remove_service(service_name);
+        self.update_client_gauges();
 
         trace!("New named clients count: {}", self.clients.len());
     }
+
+    /// Gracefully shut down the hub: reject pending connection requests, notify
+    /// all clients and wait until the notifications are sent
+    async fn shutdown(&mut self, reason: &str) {
+        info!("Shutting down hub clients: {}", reason);
+
+        // Requesters are still waiting for a response. Let them know the connection won't happen
+        let pending_connections: Vec<PendingConnectionRequest> = self
+            .pending_connections
+            .drain()
+            .flat_map(|(_, requests)| requests)
+            .collect();
+
+        for pending in pending_connections {
+            self.send_client_message(
+                &pending.requester_service_name,
+                BusError::HubShutdown.into_message(pending.request.seq()),
+            )
+            .await;
+        }
+        self.update_pending_gauge();
+
+        let mut clients: Vec<Client> = self
+            .clients
+            .drain()
+            .map(|(_, client)| client)
+            .chain(self.anonymous_clients.drain().map(|(_, client)| client))
+            .collect();
+
+        for client in clients.iter_mut() {
+            client.shutdown(reason).await;
+        }
+
+        let deadline = TokioInstant::now() + SHUTDOWN_TIMEOUT;
+        for client in clients.iter() {
+            if time::timeout_at(deadline, client.closed()).await.is_err() {
+                warn!("Timed out waiting for clients to shut down");
+                break;
+            }
+        }
+
+        self.update_client_gauges();
+    }
+
+    /// Forget the connection requests the service is waiting for
+    fn remove_pending_connections(
+        &mut self,
+        requester_service_name: &str,
+    ) -> Vec<PendingConnectionRequest> {
+        let mut removed = vec![];
+
+        for requests in self.pending_connections.values_mut() {
+            let (requester_requests, other_requests): (Vec<_>, Vec<_>) = requests
+                .drain(..)
+                .partition(|pending| pending.requester_service_name == requester_service_name);
+
+            *requests = other_requests;
+            removed.extend(requester_requests);
+        }
+
+        self.pending_connections
+            .retain(|_, requests| !requests.is_empty());
+        self.update_pending_gauge();
+
+        removed
+    }
+
+    /// Reply with an **error** to the connection requests the service is waiting for
+    /// and forget them
+    async fn fail_pending_connections(&mut self, requester_service_name: &str, error: BusError) {
+        for pending in self.remove_pending_connections(requester_service_name) {
+            self.send_client_message(
+                &pending.requester_service_name,
+                error.clone().into_message(pending.request.seq()),
+            )
+            .await;
+        }
+    }
+
+    fn update_client_gauges(&self) {
+        Metrics::set(&self.metrics.registered_clients, self.clients.len());
+        Metrics::set(
+            &self.metrics.anonymous_clients,
+            self.anonymous_clients.len(),
+        );
+    }
+
+    fn update_pending_gauge(&self) {
+        Metrics::set(
+            &self.metrics.pending_connections,
+            self.pending_connections.values().map(Vec::len).sum(),
+        );
+    }
+
+    /// Handle privileged hub admin request
+    async fn handle_admin_request(&mut self, requester_service_name: String, request: Message) {
+        let admin_request = match request.body() {
+            MessageBody::ServiceMessage(ServiceMessage::Admin(admin_request)) => {
+                admin_request.clone()
+            }
+            _ => panic!("Should never happen"),
+        };
+
+        info!(
+            "Admin request from `{}`: {}",
+            requester_service_name, admin_request
+        );
+
+        let result = match admin_request {
+            AdminRequest::ListServices => Self::admin_response(&self.list_services()),
+            AdminRequest::PendingConnections => {
+                Self::admin_response(&self.list_pending_connections())
+            }
+            AdminRequest::Kick { service_name } => self
+                .kick_client(&service_name)
+                .await
+                .and_then(|_| Self::admin_response(&())),
+            AdminRequest::ReloadPolicy => {
+                self.reload_policy();
+                Self::admin_response(&())
+            }
+            AdminRequest::Stats => Self::admin_response(&self.stats()),
+            AdminRequest::Topology => Self::admin_response(&self.topology.data()),
+            AdminRequest::Metrics => Self::admin_response(&self.metrics.render()),
+        };
+
+        let response = match result {
+            Ok(bson) => Response::Return(bson),
+            Err(err) => Response::Error(err),
+        };
+
+        self.send_client_message(
+            &requester_service_name,
+            response.into_message(request.seq()),
+        )
+        .await;
+    }
+
+    fn admin_response<T: Serialize>(data: &T) -> Result<Bson, BusError> {
+        bson::to_bson(data).map_err(|err| {
+            error!("Failed to serialize admin response: {}", err);
+            BusError::Internal
+        })
+    }
+
+    /// Registered services with their peers
+    fn list_services(&self) -> Vec<ServiceInfo> {
+        let mut services: Vec<ServiceInfo> = self
+            .clients
+            .iter()
+            .map(|(service_name, client)| {
+                let credentials = client.credentials();
+
+                ServiceInfo {
+                    service_name: service_name.clone(),
+                    pid: credentials.and_then(|credentials| credentials.pid()),
+                    uid: credentials.map_or(0, |credentials| credentials.uid()),
+                    peers: self.topology.peers(service_name),
+                }
+            })
+            .collect();
+
+        services.sort_by(|left, right| left.service_name.cmp(&right.service_name));
+        services
+    }
+
+    /// Connection requests waiting for a target service to register
+    fn list_pending_connections(&self) -> Vec<PendingConnectionInfo> {
+        self.pending_connections
+            .iter()
+            .flat_map(|(target_service_name, requests)| {
+                requests.iter().map(|request| PendingConnectionInfo {
+                    requester_service_name: request.requester_service_name.clone(),
+                    target_service_name: target_service_name.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Drop connection to a registered client. Connection requests the client
+    /// is waiting for fail with [BusError::NotConnected]
+    async fn kick_client(&mut self, service_name: &String) -> Result<(), BusError> {
+        if self.clients.contains_key(service_name) {
+            self.fail_pending_connections(service_name, BusError::NotConnected)
+                .await;
+        }
+
+        match self.clients.remove(service_name) {
+            // Dropping the client handle shuts down the connection
+            Some(_) => {
+                info!("Kicked client `{}`", service_name);
+
+                self.quotas.remove_service(service_name);
+                self.topology.remove_service(service_name);
+                self.update_client_gauges();
+                Ok(())
+            }
+            _ => {
+                warn!("Failed to kick `{}`. Not registered", service_name);
+                Err(BusError::ServiceNotRegisterd)
+            }
+        }
+    }
+
+    /// Reread quotas of registered services from the service files
+    fn reload_policy(&mut self) {
+        info!("Reloading services policy");
+
+        let service_names: Vec<String> = self.clients.keys().cloned().collect();
+
+        for service_name in service_names {
+            let quotas = self
+                .permissions
+                .read_quotas(&service_name, self.quotas.defaults());
+            self.quotas.set_service_quotas(&service_name, quotas);
+        }
+
+        Metrics::inc(&self.metrics.policy_reloads);
+    }
+
+    fn stats(&self) -> HubStats {
+        HubStats {
+            uptime_secs: self.started.elapsed().as_secs(),
+            clients: self.clients.len() as u64,
+            anonymous_clients: self.anonymous_clients.len() as u64,
+            pending_connections: self
+                .pending_connections
+                .values()
+                .map(Vec::len)
+                .sum::<usize>() as u64,
+            quota_violations: [
+                QuotaKind::Peers,
+                QuotaKind::PendingConnections,
+                QuotaKind::ConnectionRate,
+            ]
+            .iter()
+            .map(|kind| (kind.name().to_string(), self.quotas.violations(*kind)))
+            .collect(),
+        }
+    }
+
+    /// Registered client process credentials to pass to its peers
+    fn peer_credentials(&self, service_name: &String) -> Option<PeerCredentials> {
+        self.clients
+            .get(service_name)
+            .and_then(|client| client.credentials())
+            .map(|credentials| PeerCredentials {
+                pid: credentials.pid(),
+                uid: credentials.uid(),
+                gid: credentials.gid(),
+            })
+    }
+
+    /// Send a message to a registered client if it's still connected
+    async fn send_client_message(&mut self, service_name: &String, message: Message) {
+        match self.clients.get_mut(service_name) {
+            Some(client) => client.send_message(service_name, message).await,
+            _ => {
+                warn!(
+                    "Failed to lookup `{}` service. Asumming disconnected",
+                    service_name
+                );
+            }
+        }
+    }
 }
 
 impl Drop for Hub {
@@ -3,4 +3,7 @@
 pub mod args;
 pub mod client;
 pub mod hub;
+pub mod metrics;
 pub mod permissions;
+pub mod quotas;
+pub mod topology;
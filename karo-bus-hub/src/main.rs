@@ -1,7 +1,10 @@
 mod args;
 mod client;
 mod hub;
+mod metrics;
 mod permissions;
+mod quotas;
+mod topology;
 
 use std::{
     io::{Error, ErrorKind},
@@ -10,7 +13,10 @@ use std::{
 
 use clap::Parser;
 use log::*;
-use tokio::sync::mpsc;
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::mpsc,
+};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -36,13 +42,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut hub = hub::Hub::new(args, shutdown_rx);
 
-    let result = tokio::select! {
-        _ = tokio::signal::ctrl_c() => {
-            let _ = shutdown_tx.send(()).await;
-            Ok(())
+    // We let the hub to notify clients and drain pending requests before exiting
+    tokio::spawn(async move {
+        let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = terminate.recv() => {},
         }
-        result = hub.run() => { result }
-    };
+
+        let _ = shutdown_tx.send(()).await;
+    });
+
+    let result = hub.run().await;
 
     debug!("Shutting down Karo hub");
 
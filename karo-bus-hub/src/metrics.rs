@@ -0,0 +1,254 @@
+use std::{
+    fmt::Write,
+    fs,
+    net::SocketAddr,
+    os::unix::prelude::PermissionsExt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use log::*;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, UnixListener},
+};
+
+use crate::quotas::{QuotaKind, QuotaViolations};
+
+const METRICS_PREFIX: &str = "karo_bus";
+
+/// Hub metrics. Shared between the hub and metrics endpoints
+pub struct Metrics {
+    /// Number of currently registered clients
+    pub registered_clients: AtomicU64,
+    /// Number of connected, but not registered clients
+    pub anonymous_clients: AtomicU64,
+    /// Number of succesfull registrations
+    pub registrations_accepted: AtomicU64,
+    /// Number of rejected registrations
+    pub registrations_denied: AtomicU64,
+    /// Number of succesfully brokered peer connections
+    pub connections_brokered: AtomicU64,
+    /// Number of rejected peer connection requests
+    pub connections_denied: AtomicU64,
+    /// Number of connection requests waiting for a target service
+    pub pending_connections: AtomicU64,
+    /// Number of policy reloads
+    pub policy_reloads: AtomicU64,
+    /// Quota violations accounted by [crate::quotas::QuotaTracker]
+    quota_violations: Arc<QuotaViolations>,
+}
+
+impl Metrics {
+    pub fn new(quota_violations: Arc<QuotaViolations>) -> Self {
+        Self {
+            registered_clients: AtomicU64::default(),
+            anonymous_clients: AtomicU64::default(),
+            registrations_accepted: AtomicU64::default(),
+            registrations_denied: AtomicU64::default(),
+            connections_brokered: AtomicU64::default(),
+            connections_denied: AtomicU64::default(),
+            pending_connections: AtomicU64::default(),
+            policy_reloads: AtomicU64::default(),
+            quota_violations,
+        }
+    }
+
+    /// Increment a counter
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Set a gauge value
+    pub fn set(gauge: &AtomicU64, value: usize) {
+        gauge.store(value as u64, Ordering::Relaxed);
+    }
+
+    /// Render metrics in Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut result = String::new();
+
+        let mut write_metric = |name: &str, kind: &str, help: &str, value: &AtomicU64| {
+            writeln!(result, "# HELP {}_{} {}", METRICS_PREFIX, name, help).unwrap();
+            writeln!(result, "# TYPE {}_{} {}", METRICS_PREFIX, name, kind).unwrap();
+            writeln!(
+                result,
+                "{}_{} {}",
+                METRICS_PREFIX,
+                name,
+                value.load(Ordering::Relaxed)
+            )
+            .unwrap();
+        };
+
+        write_metric(
+            "registered_clients",
+            "gauge",
+            "Number of registered clients",
+            &self.registered_clients,
+        );
+        write_metric(
+            "anonymous_clients",
+            "gauge",
+            "Number of connected clients, which are not registered yet",
+            &self.anonymous_clients,
+        );
+        write_metric(
+            "registrations_accepted_total",
+            "counter",
+            "Number of accepted service registrations",
+            &self.registrations_accepted,
+        );
+        write_metric(
+            "registrations_denied_total",
+            "counter",
+            "Number of denied service registrations",
+            &self.registrations_denied,
+        );
+        write_metric(
+            "connections_brokered_total",
+            "counter",
+            "Number of brokered peer connections",
+            &self.connections_brokered,
+        );
+        write_metric(
+            "connections_denied_total",
+            "counter",
+            "Number of denied peer connection requests",
+            &self.connections_denied,
+        );
+        write_metric(
+            "pending_connections",
+            "gauge",
+            "Number of connection requests waiting for a target service",
+            &self.pending_connections,
+        );
+        write_metric(
+            "policy_reloads_total",
+            "counter",
+            "Number of services policy reloads",
+            &self.policy_reloads,
+        );
+
+        let name = format!("{}_quota_violations_total", METRICS_PREFIX);
+        writeln!(result, "# HELP {} Number of service quota violations", name).unwrap();
+        writeln!(result, "# TYPE {} counter", name).unwrap();
+
+        for kind in [
+            QuotaKind::Peers,
+            QuotaKind::PendingConnections,
+            QuotaKind::ConnectionRate,
+        ] {
+            writeln!(
+                result,
+                "{}{{quota=\"{}\"}} {}",
+                name,
+                kind.name(),
+                self.quota_violations.get(kind)
+            )
+            .unwrap();
+        }
+
+        result
+    }
+}
+
+/// Start serving metrics over HTTP at a localhost TCP *address*
+pub async fn serve_tcp(address: &str, metrics: Arc<Metrics>) -> std::io::Result<()> {
+    let address: SocketAddr = address.parse().map_err(|err| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid metrics address `{}`: {}", address, err),
+        )
+    })?;
+
+    if !address.ip().is_loopback() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("Metrics address `{}` is not a localhost address", address),
+        ));
+    }
+
+    let listener = TcpListener::bind(address).await?;
+    info!("Serving metrics at http://{}", address);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_metrics_request(stream, metrics.clone()));
+                }
+                Err(err) => {
+                    error!("Failed to accept metrics connection: {}", err);
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Start serving metrics over HTTP at a unix socket *path* with permissions *mode*
+pub async fn serve_unix(path: &str, mode: u32, metrics: Arc<Metrics>) -> std::io::Result<()> {
+    // Socket file may be left from the previous run
+    let _ = fs::remove_file(path);
+
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    info!("Serving metrics at {}", path);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_metrics_request(stream, metrics.clone()));
+                }
+                Err(err) => {
+                    error!("Failed to accept metrics connection: {}", err);
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Reply to any request with a metrics page. We don't care about request details,
+/// because metrics is the only thing we serve
+async fn handle_metrics_request<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    metrics: Arc<Metrics>,
+) {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+
+    // Read request headers
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(bytes_read) => request.extend_from_slice(&buffer[..bytes_read]),
+        }
+
+        if request.len() > 16 * 1024 {
+            warn!("Metrics request is too large. Dropping connection");
+            return;
+        }
+    }
+
+    let body = metrics.render();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+
+    if let Err(err) = stream.write_all(response.as_bytes()).await {
+        debug!("Failed to write metrics response: {}", err);
+    }
+
+    let _ = stream.shutdown().await;
+}
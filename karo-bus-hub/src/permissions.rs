@@ -10,12 +10,18 @@ use log::*;
 use tokio::net::unix::UCred;
 
 use karo_bus_common::{
-    errors::Error as BusError, inspect_data::CONNECT_SERVICE_NAME, monitor::MONITOR_SERVICE_NAME,
-    service_names::NamePattern,
+    admin::CTL_SERVICE_NAME, errors::Error as BusError, inspect_data::CONNECT_SERVICE_NAME,
+    monitor::MONITOR_SERVICE_NAME, service_names::NamePattern,
 };
 
+use crate::quotas::Quotas;
+
 const ALLOWED_EXECS_KEY: &str = "exec";
 const INCOMING_CONNS_KEY: &str = "incoming_connections";
+const QUOTAS_KEY: &str = "quotas";
+const MAX_PEERS_KEY: &str = "max_peers";
+const MAX_PENDING_CONNS_KEY: &str = "max_pending_connections";
+const CONNECTION_RATE_KEY: &str = "connection_rate";
 
 /// Permissions reader.
 /// Each service file must be names as {service_name}.service,
@@ -25,11 +31,17 @@ const INCOMING_CONNS_KEY: &str = "incoming_connections";
 ///     "exec": "/usr/bin/service"
 ///     "incoming_connections": [
 ///         "com.service.name"
-///     ]
+///     ],
+///     "quotas": {
+///         "max_peers": 16,
+///         "max_pending_connections": 4,
+///         "connection_rate": 10
+///     }
 /// }
 /// ```
 /// *allowed_exec_paths* supports GLOB patterns.
-/// *allowed_connections* supports service name patterns. See [karo_bus_common::service_names] for details
+/// *allowed_connections* supports service name patterns. See [karo_bus_common::service_names] for details.
+/// *quotas* is optional. Each missing quota falls back to the hub-wide value
 pub struct Permissions {
     service_files_dir: PathBuf,
 }
@@ -178,6 +190,15 @@ impl Permissions {
         Err(BusError::NotAllowed)
     }
 
+    /// Check if a **service_name** is allowed to make hub admin requests
+    pub fn check_admin_allowed(&self, service_name: &String) -> Result<(), BusError> {
+        if service_name == CTL_SERVICE_NAME {
+            Ok(())
+        } else {
+            Err(BusError::NotAllowed)
+        }
+    }
+
     /// Read allowed incoming connections for a given service from a service file
     fn read_allowed_connections(
         &self,
@@ -231,6 +252,55 @@ impl Permissions {
         Ok(result)
     }
 
+    /// Read service quotas from a service file. Quotas missing in the service file
+    /// are taken from **defaults**
+    pub fn read_quotas(&self, service_name: &String, defaults: &Quotas) -> Quotas {
+        let mut quotas = *defaults;
+
+        let json = match self.parse_service_file_json(service_name) {
+            Ok(json) => json,
+            Err(_) => return quotas,
+        };
+
+        if !json.has_key(QUOTAS_KEY) {
+            return quotas;
+        }
+
+        if !json[QUOTAS_KEY].is_object() {
+            warn!(
+                "Invalid `{}` entry in a service file. Expected object, got `{}`",
+                QUOTAS_KEY, json[QUOTAS_KEY]
+            );
+            return quotas;
+        }
+
+        let read_quota = |key: &str, default: Option<usize>| {
+            let entry = &json[QUOTAS_KEY][key];
+
+            if entry.is_null() {
+                return default;
+            }
+
+            match entry.as_usize() {
+                Some(value) => Some(value),
+                _ => {
+                    warn!(
+                        "Invalid `{}` quota in a service file. Expected unsigned integer, got `{}`",
+                        key, entry
+                    );
+                    default
+                }
+            }
+        };
+
+        quotas.max_peers = read_quota(MAX_PEERS_KEY, quotas.max_peers);
+        quotas.max_pending_connections =
+            read_quota(MAX_PENDING_CONNS_KEY, quotas.max_pending_connections);
+        quotas.connection_rate = read_quota(CONNECTION_RATE_KEY, quotas.connection_rate);
+
+        quotas
+    }
+
     fn parse_service_file_json(&self, service_name: &String) -> Result<JsonValue, BusError> {
         let service_file_name = self
             .service_files_dir
@@ -0,0 +1,211 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use log::*;
+
+use karo_bus_common::errors::Error as BusError;
+
+use crate::args::Args;
+
+/// Connection request rate is accounted over this window
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Per-service resource quotas. *None* means the quota is unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Quotas {
+    /// Max number of peers a service may request connection to
+    pub max_peers: Option<usize>,
+    /// Max number of pending connection requests a service may have
+    pub max_pending_connections: Option<usize>,
+    /// Max number of connection requests per second
+    pub connection_rate: Option<usize>,
+}
+
+impl From<&Args> for Quotas {
+    fn from(args: &Args) -> Self {
+        Self {
+            max_peers: args.max_peers,
+            max_pending_connections: args.max_pending_connections,
+            connection_rate: args.connection_rate,
+        }
+    }
+}
+
+/// Quota kind. Used to account quota violations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuotaKind {
+    Peers,
+    PendingConnections,
+    ConnectionRate,
+}
+
+impl QuotaKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Peers => "max_peers",
+            Self::PendingConnections => "max_pending_connections",
+            Self::ConnectionRate => "connection_rate",
+        }
+    }
+}
+
+/// Number of quota violations for each quota kind.
+/// Shared with the metrics endpoints
+#[derive(Default)]
+pub struct QuotaViolations {
+    peers: AtomicU64,
+    pending_connections: AtomicU64,
+    connection_rate: AtomicU64,
+}
+
+impl QuotaViolations {
+    /// Number of quota violations of a given kind
+    pub fn get(&self, kind: QuotaKind) -> u64 {
+        self.counter(kind).load(Ordering::Relaxed)
+    }
+
+    fn inc(&self, kind: QuotaKind) -> u64 {
+        self.counter(kind).fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn counter(&self, kind: QuotaKind) -> &AtomicU64 {
+        match kind {
+            QuotaKind::Peers => &self.peers,
+            QuotaKind::PendingConnections => &self.pending_connections,
+            QuotaKind::ConnectionRate => &self.connection_rate,
+        }
+    }
+}
+
+/// Tracks service resource usage and checks it against service quotas
+pub struct QuotaTracker {
+    /// Hub-wide quotas, used if a service file doesn't override them
+    defaults: Quotas,
+    /// Registered services quotas. Read from service files at registration
+    quotas: HashMap<String, Quotas>,
+    /// Timestamps of recent connection requests for each service
+    requests: HashMap<String, VecDeque<Instant>>,
+    /// Number of quota violations for each quota kind
+    violations: Arc<QuotaViolations>,
+}
+
+impl QuotaTracker {
+    pub fn new(defaults: Quotas) -> Self {
+        Self {
+            defaults,
+            quotas: HashMap::new(),
+            requests: HashMap::new(),
+            violations: Arc::new(QuotaViolations::default()),
+        }
+    }
+
+    /// Hub-wide quotas
+    pub fn defaults(&self) -> &Quotas {
+        &self.defaults
+    }
+
+    /// Set quotas for a newly registered service
+    pub fn set_service_quotas(&mut self, service_name: &str, quotas: Quotas) {
+        trace!("Quotas for the service `{}`: {:?}", service_name, quotas);
+
+        self.quotas.insert(service_name.into(), quotas);
+    }
+
+    /// Get quotas of a service. Hub-wide quotas if the service is unknown
+    pub fn service_quotas(&self, service_name: &str) -> Quotas {
+        self.quotas
+            .get(service_name)
+            .cloned()
+            .unwrap_or(self.defaults)
+    }
+
+    /// Account new connection request and check if the service exceeds its request rate
+    pub fn check_connection_rate(&mut self, service_name: &str) -> Result<(), BusError> {
+        let now = Instant::now();
+        let limit = self.service_quotas(service_name).connection_rate;
+
+        let requests = self.requests.entry(service_name.into()).or_default();
+        while let Some(timestamp) = requests.front() {
+            if now.duration_since(*timestamp) < RATE_WINDOW {
+                break;
+            }
+
+            requests.pop_front();
+        }
+
+        match limit {
+            Some(limit) if requests.len() >= limit => {
+                self.record_violation(service_name, QuotaKind::ConnectionRate, limit)
+            }
+            _ => {
+                requests.push_back(now);
+                Ok(())
+            }
+        }
+    }
+
+    /// Check if the service is allowed to request a connection to one more peer
+    pub fn check_peers(&mut self, service_name: &str, peers_count: usize) -> Result<(), BusError> {
+        match self.service_quotas(service_name).max_peers {
+            Some(limit) if peers_count >= limit => {
+                self.record_violation(service_name, QuotaKind::Peers, limit)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check if the service is allowed to have one more pending connection request
+    pub fn check_pending_connections(
+        &mut self,
+        service_name: &str,
+        pending_count: usize,
+    ) -> Result<(), BusError> {
+        match self.service_quotas(service_name).max_pending_connections {
+            Some(limit) if pending_count >= limit => {
+                self.record_violation(service_name, QuotaKind::PendingConnections, limit)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Forget all resources of a disconnected service
+    pub fn remove_service(&mut self, service_name: &str) {
+        self.quotas.remove(service_name);
+        self.requests.remove(service_name);
+    }
+
+    /// Number of quota violations of a given kind
+    pub fn violations(&self, kind: QuotaKind) -> u64 {
+        self.violations.get(kind)
+    }
+
+    /// Quota violation counters. Used to export violations as metrics
+    pub fn violation_counters(&self) -> Arc<QuotaViolations> {
+        self.violations.clone()
+    }
+
+    fn record_violation(
+        &mut self,
+        service_name: &str,
+        kind: QuotaKind,
+        limit: usize,
+    ) -> Result<(), BusError> {
+        let violations = self.violations.inc(kind);
+
+        warn!(
+            "Service `{}` exceeded `{}` quota of {}. Total violations: {}",
+            service_name,
+            kind.name(),
+            limit,
+            violations
+        );
+
+        Err(BusError::QuotaExceeded(kind.name().into()))
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::*;
+
+use karo_bus_common::topology::{TopologyData, TopologyEdge};
+
+/// Keeps track of the peer connections brokered by the hub
+pub struct Topology {
+    /// Connection creation times. Keyed by (requester, target) service names
+    edges: HashMap<(String, String), SystemTime>,
+}
+
+impl Topology {
+    pub fn new() -> Self {
+        Self {
+            edges: HashMap::new(),
+        }
+    }
+
+    /// Account new connection from a *requester* to a *target*.
+    /// Reconnection resets connection creation time
+    pub fn add_connection(&mut self, requester: &str, target: &str) {
+        trace!("New topology edge `{}` -> `{}`", requester, target);
+
+        self.edges
+            .insert((requester.into(), target.into()), SystemTime::now());
+    }
+
+    /// Remove connection between two services regardless of the initiator.
+    /// Returns false if the services are not connected
+    pub fn remove_connection(&mut self, service_name: &str, peer_name: &str) -> bool {
+        trace!(
+            "Removing topology edges between `{}` and `{}`",
+            service_name,
+            peer_name
+        );
+
+        let requested = self
+            .edges
+            .remove(&(service_name.to_string(), peer_name.to_string()))
+            .is_some();
+        let accepted = self
+            .edges
+            .remove(&(peer_name.to_string(), service_name.to_string()))
+            .is_some();
+
+        requested || accepted
+    }
+
+    /// Remove all connections of a disconnected service
+    pub fn remove_service(&mut self, service_name: &str) {
+        self.edges
+            .retain(|(requester, target), _| requester != service_name && target != service_name);
+    }
+
+    /// If *requester* has a connection to *target*
+    pub fn is_connected(&self, requester: &str, target: &str) -> bool {
+        self.edges
+            .contains_key(&(requester.to_string(), target.to_string()))
+    }
+
+    /// Number of peers a service requested connection to
+    pub fn requested_peers_count(&self, service_name: &str) -> usize {
+        self.edges
+            .keys()
+            .filter(|(requester, _)| requester == service_name)
+            .count()
+    }
+
+    /// Services connected to a service, regardless of the connection initiator
+    pub fn peers(&self, service_name: &str) -> Vec<String> {
+        let mut result: Vec<String> = self
+            .edges
+            .keys()
+            .filter_map(|(requester, target)| {
+                if requester == service_name {
+                    Some(target.clone())
+                } else if target == service_name {
+                    Some(requester.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// Export connection graph
+    pub fn data(&self) -> TopologyData {
+        let mut edges: Vec<TopologyEdge> = self
+            .edges
+            .iter()
+            .map(|((requester, target), created_at)| TopologyEdge {
+                requester_service_name: requester.clone(),
+                target_service_name: target.clone(),
+                created_at_ms: created_at
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_millis() as u64),
+            })
+            .collect();
+
+        edges.sort_by(|left, right| {
+            (&left.requester_service_name, &left.target_service_name)
+                .cmp(&(&right.requester_service_name, &right.target_service_name))
+        });
+
+        TopologyData { edges }
+    }
+}
@@ -0,0 +1,334 @@
+use std::{env, path::Path, time::Duration};
+
+use json::JsonValue;
+use karo_bus_common::{
+    admin::{AdminRequest, HubStats, PendingConnectionInfo, ServiceInfo, CTL_SERVICE_NAME},
+    HUB_SOCKET_PATH_ENV,
+};
+use log::LevelFilter;
+use tempdir::TempDir;
+use tokio::{
+    fs::OpenOptions,
+    io::AsyncWriteExt,
+    sync::mpsc::{self, Sender},
+    time,
+};
+
+use karo_bus_hub::{args::Args, hub::Hub};
+use karo_bus_lib::Bus;
+
+async fn start_hub(socket_path: &str, service_files_dir: &str) -> Sender<()> {
+    env::set_var(HUB_SOCKET_PATH_ENV, socket_path);
+
+    let args = Args {
+        log_level: LevelFilter::Debug,
+        service_files_dir: service_files_dir.into(),
+        ..Default::default()
+    };
+
+    // let _ = pretty_env_logger::formatted_builder()
+    //     .filter_level(args.log_level)
+    //     .try_init();
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>(1);
+
+    tokio::spawn(async move {
+        let mut hub = Hub::new(args, shutdown_rx);
+        hub.run().await.expect("Failed to run hub");
+
+        println!("Shutting hub down");
+    });
+
+    println!("Succesfully started hub socket");
+    shutdown_tx
+}
+
+async fn write_service_file(service_dir: &Path, service_name: &str, content: JsonValue) {
+    let service_file_path = service_dir.join(format!("{}.service", service_name));
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .open(service_file_path.as_path())
+        .await
+        .expect("Failed to create service file");
+
+    file.write_all(json::stringify(content).as_bytes())
+        .await
+        .expect("Failed to write service file content");
+    file.flush().await.expect("Failed to flush service file");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_admin_requests() {
+    let socket_dir = TempDir::new("karo_hub_socket_dir").expect("Failed to create socket tempdir");
+    let socket_path: String = socket_dir
+        .path()
+        .join("karo_hub.socket")
+        .as_os_str()
+        .to_str()
+        .unwrap()
+        .into();
+
+    let service_dir = TempDir::new("test_admin_requests").expect("Failed to create tempdir");
+
+    let shutdown_tx = start_hub(
+        &socket_path,
+        service_dir.path().as_os_str().to_str().unwrap(),
+    )
+    .await;
+    // Lets wait until hub starts
+    time::sleep(Duration::from_millis(10)).await;
+
+    let service_file_json = json::parse(
+        r#"
+        {
+            "exec": "/**/*",
+            "incoming_connections": []
+        }
+        "#,
+    )
+    .unwrap();
+
+    write_service_file(
+        service_dir.path(),
+        CTL_SERVICE_NAME,
+        service_file_json.clone(),
+    )
+    .await;
+
+    let service_name = "admin.requests.service";
+    write_service_file(service_dir.path(), service_name, service_file_json).await;
+
+    let mut ctl_bus = Bus::register(CTL_SERVICE_NAME)
+        .await
+        .expect("Failed to register ctl service");
+
+    let mut service_bus = Bus::register(service_name)
+        .await
+        .expect("Failed to register service");
+
+    // Only ctl service is allowed to make admin requests
+    service_bus
+        .admin_call::<HubStats>(AdminRequest::Stats)
+        .await
+        .expect_err("Non-privileged admin request succeeded");
+
+    let stats: HubStats = ctl_bus
+        .admin_call(AdminRequest::Stats)
+        .await
+        .expect("Failed to get hub stats");
+    assert_eq!(stats.clients, 2);
+
+    let services: Vec<ServiceInfo> = ctl_bus
+        .admin_call(AdminRequest::ListServices)
+        .await
+        .expect("Failed to list services");
+    assert!(services
+        .iter()
+        .any(|service| service.service_name == service_name));
+
+    // Connection requests of the kicked service fail
+    let target_service_name = "admin.requests.target";
+    let target_service_file_json = json::parse(
+        r#"
+        {
+            "exec": "/**/*",
+            "incoming_connections": ["admin.requests.service"]
+        }
+        "#,
+    )
+    .unwrap();
+    write_service_file(
+        service_dir.path(),
+        target_service_name,
+        target_service_file_json,
+    )
+    .await;
+
+    let mut waiting_bus = service_bus.clone();
+    let waiting_connection =
+        tokio::spawn(async move { waiting_bus.connect_await(target_service_name).await });
+
+    let mut pending = vec![];
+    for _ in 0..100 {
+        pending = ctl_bus
+            .admin_call::<Vec<PendingConnectionInfo>>(AdminRequest::PendingConnections)
+            .await
+            .expect("Failed to list pending connections");
+
+        if !pending.is_empty() {
+            break;
+        }
+        time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].requester_service_name, service_name);
+
+    ctl_bus
+        .admin_call::<()>(AdminRequest::Kick {
+            service_name: service_name.into(),
+        })
+        .await
+        .expect("Failed to kick service");
+
+    let pending: Vec<PendingConnectionInfo> = ctl_bus
+        .admin_call(AdminRequest::PendingConnections)
+        .await
+        .expect("Failed to list pending connections");
+    assert!(pending.is_empty());
+
+    time::timeout(Duration::from_secs(1), waiting_connection)
+        .await
+        .expect("Pending connection request timed out")
+        .expect("Failed to join connection request")
+        .expect_err("Kicked service connected");
+
+    // Kicked bus registers again. Late disconnection of the kicked client doesn't affect it
+    let mut registered = false;
+    for _ in 0..100 {
+        let services: Vec<ServiceInfo> = ctl_bus
+            .admin_call(AdminRequest::ListServices)
+            .await
+            .expect("Failed to list services");
+
+        registered = services
+            .iter()
+            .any(|service| service.service_name == service_name);
+        if registered {
+            break;
+        }
+        time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(registered);
+
+    time::sleep(Duration::from_millis(100)).await;
+
+    let services: Vec<ServiceInfo> = ctl_bus
+        .admin_call(AdminRequest::ListServices)
+        .await
+        .expect("Failed to list services");
+    assert!(services
+        .iter()
+        .any(|service| service.service_name == service_name));
+
+    ctl_bus
+        .admin_call::<()>(AdminRequest::Kick {
+            service_name: "admin.requests.unknown".into(),
+        })
+        .await
+        .expect_err("Kicked unknown service");
+
+    shutdown_tx
+        .send(())
+        .await
+        .expect("Failed to send shutdown request to the hub");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_disconnected_requester() {
+    let socket_dir = TempDir::new("karo_hub_socket_dir").expect("Failed to create socket tempdir");
+    let socket_path: String = socket_dir
+        .path()
+        .join("karo_hub.socket")
+        .as_os_str()
+        .to_str()
+        .unwrap()
+        .into();
+
+    let service_dir =
+        TempDir::new("test_disconnected_requester").expect("Failed to create tempdir");
+
+    let shutdown_tx = start_hub(
+        &socket_path,
+        service_dir.path().as_os_str().to_str().unwrap(),
+    )
+    .await;
+    // Lets wait until hub starts
+    time::sleep(Duration::from_millis(10)).await;
+
+    let service_file_json = json::parse(
+        r#"
+        {
+            "exec": "/**/*",
+            "incoming_connections": []
+        }
+        "#,
+    )
+    .unwrap();
+
+    write_service_file(
+        service_dir.path(),
+        CTL_SERVICE_NAME,
+        service_file_json.clone(),
+    )
+    .await;
+
+    let service_name = "admin.disconnected.service";
+    write_service_file(service_dir.path(), service_name, service_file_json).await;
+
+    let target_service_file_json = json::parse(
+        r#"
+        {
+            "exec": "/**/*",
+            "incoming_connections": ["admin.disconnected.service"]
+        }
+        "#,
+    )
+    .unwrap();
+    write_service_file(
+        service_dir.path(),
+        "admin.disconnected.target",
+        target_service_file_json,
+    )
+    .await;
+
+    let mut ctl_bus = Bus::register(CTL_SERVICE_NAME)
+        .await
+        .expect("Failed to register ctl service");
+
+    let service_bus = Bus::register(service_name)
+        .await
+        .expect("Failed to register service");
+
+    let mut waiting_bus = service_bus.clone();
+    let waiting_connection = tokio::spawn(async move {
+        let _ = waiting_bus.connect_await("admin.disconnected.target").await;
+    });
+
+    let mut pending = vec![];
+    for _ in 0..100 {
+        pending = ctl_bus
+            .admin_call::<Vec<PendingConnectionInfo>>(AdminRequest::PendingConnections)
+            .await
+            .expect("Failed to list pending connections");
+
+        if !pending.is_empty() {
+            break;
+        }
+        time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(pending.len(), 1);
+
+    // Requests of a disconnected service are forgotten
+    waiting_connection.abort();
+    drop(service_bus);
+
+    for _ in 0..100 {
+        pending = ctl_bus
+            .admin_call::<Vec<PendingConnectionInfo>>(AdminRequest::PendingConnections)
+            .await
+            .expect("Failed to list pending connections");
+
+        if pending.is_empty() {
+            break;
+        }
+        time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(pending.is_empty());
+
+    shutdown_tx
+        .send(())
+        .await
+        .expect("Failed to send shutdown request to the hub");
+}
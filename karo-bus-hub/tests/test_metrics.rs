@@ -0,0 +1,32 @@
+use karo_bus_hub::{
+    metrics::Metrics,
+    quotas::{QuotaTracker, Quotas},
+};
+
+#[test]
+fn test_metrics_rendering() {
+    let mut tracker = QuotaTracker::new(Quotas {
+        connection_rate: Some(0),
+        ..Default::default()
+    });
+    let metrics = Metrics::new(tracker.violation_counters());
+
+    Metrics::set(&metrics.registered_clients, 3);
+    Metrics::inc(&metrics.registrations_accepted);
+    Metrics::inc(&metrics.registrations_accepted);
+    Metrics::inc(&metrics.connections_denied);
+
+    // Violations are read from the quota tracker
+    assert!(tracker.check_connection_rate("com.metrics").is_err());
+
+    let text = metrics.render();
+
+    assert!(text.contains("# TYPE karo_bus_registered_clients gauge\n"));
+    assert!(text.contains("karo_bus_registered_clients 3\n"));
+    assert!(text.contains("# TYPE karo_bus_registrations_accepted_total counter\n"));
+    assert!(text.contains("karo_bus_registrations_accepted_total 2\n"));
+    assert!(text.contains("karo_bus_connections_denied_total 1\n"));
+    assert!(text.contains("karo_bus_connections_brokered_total 0\n"));
+    assert!(text.contains("karo_bus_quota_violations_total{quota=\"connection_rate\"} 1\n"));
+    assert!(text.contains("karo_bus_quota_violations_total{quota=\"max_peers\"} 0\n"));
+}
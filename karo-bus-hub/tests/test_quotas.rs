@@ -0,0 +1,152 @@
+use std::{fs, path::Path};
+
+use json::JsonValue;
+use tempdir::TempDir;
+
+use karo_bus_common::errors::Error as BusError;
+use karo_bus_hub::{
+    permissions::Permissions,
+    quotas::{QuotaKind, QuotaTracker, Quotas},
+    topology::Topology,
+};
+
+fn write_service_file(service_dir: &Path, service_name: &str, content: JsonValue) {
+    let service_file_path = service_dir.join(format!("{}.service", service_name));
+
+    fs::write(service_file_path, json::stringify(content)).expect("Failed to write service file");
+}
+
+#[test]
+fn test_service_file_quotas() {
+    let service_dir = TempDir::new("test_service_file_quotas").expect("Failed to create tempdir");
+    let service_dir_path: String = service_dir.path().to_str().unwrap().into();
+
+    let defaults = Quotas {
+        max_peers: Some(8),
+        max_pending_connections: Some(2),
+        connection_rate: None,
+    };
+
+    write_service_file(
+        service_dir.path(),
+        "com.quotas.partial",
+        json::parse(
+            r#"
+            {
+                "exec": "/**/*",
+                "incoming_connections": [],
+                "quotas": {
+                    "max_peers": 1,
+                    "connection_rate": 5
+                }
+            }
+            "#,
+        )
+        .unwrap(),
+    );
+
+    write_service_file(
+        service_dir.path(),
+        "com.quotas.none",
+        json::parse(
+            r#"
+            {
+                "exec": "/**/*",
+                "incoming_connections": []
+            }
+            "#,
+        )
+        .unwrap(),
+    );
+
+    let permissions = Permissions::new(&service_dir_path);
+
+    assert_eq!(
+        permissions.read_quotas(&"com.quotas.partial".into(), &defaults),
+        Quotas {
+            max_peers: Some(1),
+            max_pending_connections: Some(2),
+            connection_rate: Some(5),
+        }
+    );
+
+    assert_eq!(
+        permissions.read_quotas(&"com.quotas.none".into(), &defaults),
+        defaults
+    );
+}
+
+#[test]
+fn test_peers_quota() {
+    let mut tracker = QuotaTracker::new(Quotas::default());
+    tracker.set_service_quotas(
+        "com.quotas.peers",
+        Quotas {
+            max_peers: Some(1),
+            ..Default::default()
+        },
+    );
+
+    // Peers are counted from the connection topology
+    let mut topology = Topology::new();
+    let check_peers = |tracker: &mut QuotaTracker, topology: &Topology| {
+        tracker.check_peers(
+            "com.quotas.peers",
+            topology.requested_peers_count("com.quotas.peers"),
+        )
+    };
+
+    assert!(check_peers(&mut tracker, &topology).is_ok());
+    topology.add_connection("com.quotas.peers", "com.first");
+
+    // Connections requested by other services are not accounted
+    topology.add_connection("com.second", "com.quotas.peers");
+    assert!(matches!(
+        check_peers(&mut tracker, &topology),
+        Err(BusError::QuotaExceeded(_))
+    ));
+    assert_eq!(tracker.violations(QuotaKind::Peers), 1);
+
+    // Disconnected peers free the quota
+    topology.remove_service("com.first");
+    assert!(check_peers(&mut tracker, &topology).is_ok());
+
+    // Closed connections free the quota as well
+    topology.add_connection("com.quotas.peers", "com.second");
+    assert!(check_peers(&mut tracker, &topology).is_err());
+    assert!(topology.remove_connection("com.second", "com.quotas.peers"));
+    assert!(check_peers(&mut tracker, &topology).is_ok());
+}
+
+#[test]
+fn test_pending_connections_quota() {
+    let mut tracker = QuotaTracker::new(Quotas {
+        max_pending_connections: Some(2),
+        ..Default::default()
+    });
+
+    assert!(tracker
+        .check_pending_connections("com.quotas.pending", 1)
+        .is_ok());
+    assert!(tracker
+        .check_pending_connections("com.quotas.pending", 2)
+        .is_err());
+    assert_eq!(tracker.violations(QuotaKind::PendingConnections), 1);
+}
+
+#[test]
+fn test_connection_rate_quota() {
+    let mut tracker = QuotaTracker::new(Quotas {
+        connection_rate: Some(3),
+        ..Default::default()
+    });
+
+    for _ in 0..3 {
+        assert!(tracker.check_connection_rate("com.quotas.rate").is_ok());
+    }
+
+    assert!(tracker.check_connection_rate("com.quotas.rate").is_err());
+    // Other services have their own budget
+    assert!(tracker.check_connection_rate("com.quotas.other").is_ok());
+    assert_eq!(tracker.violations(QuotaKind::ConnectionRate), 1);
+}
@@ -21,6 +21,7 @@ async fn start_hub(socket_path: &str, service_files_dir: &str) -> Sender<()> {
     let args = Args {
         log_level: LevelFilter::Debug,
         service_files_dir: service_files_dir.into(),
+        ..Default::default()
     };
 
     // let _ = pretty_env_logger::formatted_builder()
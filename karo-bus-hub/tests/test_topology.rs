@@ -0,0 +1,45 @@
+use karo_bus_hub::topology::Topology;
+
+#[test]
+fn test_topology_connections() {
+    let mut topology = Topology::new();
+
+    topology.add_connection("com.first", "com.second");
+    topology.add_connection("com.first", "com.third");
+    topology.add_connection("com.third", "com.second");
+
+    assert!(topology.is_connected("com.first", "com.second"));
+    assert!(!topology.is_connected("com.second", "com.first"));
+    assert_eq!(topology.requested_peers_count("com.first"), 2);
+    assert_eq!(topology.peers("com.second"), vec!["com.first", "com.third"]);
+
+    // Either side can report disconnection
+    assert!(topology.remove_connection("com.second", "com.first"));
+    assert!(!topology.is_connected("com.first", "com.second"));
+    assert_eq!(topology.requested_peers_count("com.first"), 1);
+
+    // Reports about connections of other services don't change the topology
+    assert!(!topology.remove_connection("com.second", "com.first"));
+    assert!(!topology.remove_connection("com.fourth", "com.third"));
+    assert!(topology.is_connected("com.first", "com.third"));
+    assert_eq!(topology.requested_peers_count("com.third"), 1);
+
+    topology.remove_service("com.third");
+    assert!(topology.data().edges.is_empty());
+}
+
+#[test]
+fn test_topology_export() {
+    let mut topology = Topology::new();
+
+    topology.add_connection("com.first", "com.second");
+
+    let data = topology.data();
+    assert_eq!(data.edges.len(), 1);
+    assert_eq!(data.edges[0].requester_service_name, "com.first");
+    assert_eq!(data.edges[0].target_service_name, "com.second");
+
+    let dot = data.to_dot();
+    assert!(dot.starts_with("digraph karo_bus {"));
+    assert!(dot.contains("\"com.first\" -> \"com.second\""));
+}
@@ -1,8 +1,17 @@
+use bson::RawDocumentBuf;
 use log::LevelFilter;
+use serde::{Deserialize, Serialize};
 use tokio;
 
 use karo_bus_lib::Bus;
 
+#[derive(Serialize, Deserialize)]
+struct Sample {
+    id: u64,
+    name: String,
+    values: Vec<f64>,
+}
+
 #[tokio::main]
 async fn main() {
     pretty_env_logger::formatted_builder()
@@ -11,7 +20,16 @@ async fn main() {
 
     let mut bus = Bus::register("com.examples.register_method").await.unwrap();
 
-    bus.register_method("method", |val: i32| async move { val })
+    let _method = bus
+        .register_method("method", |val: i32| async move { val })
+        .unwrap();
+
+    let _sample = bus
+        .register_method("sample", |sample: Sample| async move { sample })
+        .unwrap();
+
+    let _raw_sample = bus
+        .register_raw_method("raw_sample", |sample: RawDocumentBuf| async move { sample })
         .unwrap();
 
     let _ = tokio::signal::ctrl_c().await;
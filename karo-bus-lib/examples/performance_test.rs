@@ -1,8 +1,27 @@
+use bson::RawDocumentBuf;
 use log::LevelFilter;
+use serde::{Deserialize, Serialize};
 use tokio;
 
 use karo_bus_lib::Bus;
 
+#[derive(Serialize, Deserialize)]
+struct Sample {
+    id: u64,
+    name: String,
+    values: Vec<f64>,
+}
+
+fn report(name: &str, num_calls: usize, start: std::time::Instant) {
+    let duration = std::time::Instant::now() - start;
+    println!(
+        "{}: {} calls made in {} milliseconds",
+        name,
+        num_calls,
+        duration.as_millis()
+    );
+}
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     pretty_env_logger::formatted_builder()
@@ -11,7 +30,7 @@ async fn main() {
 
     let mut bus = Bus::register("com.examples.call_method").await.unwrap();
 
-    let peer_connection = bus
+    let mut peer_connection = bus
         .connect("com.examples.register_method".into())
         .await
         .unwrap();
@@ -24,10 +43,33 @@ async fn main() {
         let _: i32 = peer_connection.call("method", &42).await.unwrap();
     }
 
-    let duration = std::time::Instant::now() - start;
-    println!(
-        "{} calls made in {} milliseconds",
-        NUM_CALLS,
-        duration.as_millis()
-    );
+    report("method", NUM_CALLS, start);
+
+    let sample = Sample {
+        id: 42,
+        name: "sensor".into(),
+        values: (0..256).map(|value| value as f64).collect(),
+    };
+
+    // Typed calls serialize the sample into a Bson tree and back on both sides
+    let start = std::time::Instant::now();
+
+    for _ in 0..NUM_CALLS {
+        let _: Sample = peer_connection.call("sample", &sample).await.unwrap();
+    }
+
+    report("sample", NUM_CALLS, start);
+
+    // Raw calls pass already encoded document as is
+    let raw_sample = RawDocumentBuf::from_document(&bson::to_document(&sample).unwrap()).unwrap();
+    let start = std::time::Instant::now();
+
+    for _ in 0..NUM_CALLS {
+        let _ = peer_connection
+            .call_raw("raw_sample", &raw_sample)
+            .await
+            .unwrap();
+    }
+
+    report("raw_sample", NUM_CALLS, start);
 }
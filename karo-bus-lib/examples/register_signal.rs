@@ -20,9 +20,9 @@ async fn main() {
         tokio::select! {
             _ = tokio::signal::ctrl_c() => { return },
             _ = tokio::time::sleep(Duration::from_secs(3)) => {
-                signal.emit(42 + increment);
-                signal.emit(11 + increment);
-                signal.emit(64 + increment);
+                signal.emit(42 + increment).unwrap();
+                signal.emit(11 + increment).unwrap();
+                signal.emit(64 + increment).unwrap();
                 increment += 1
             }
         }
@@ -21,7 +21,7 @@ async fn main() {
         tokio::select! {
             _ = tokio::signal::ctrl_c() => { return },
             _ = tokio::time::sleep(Duration::from_secs(1)) => {
-                state.set(iter.next().unwrap());
+                state.set(iter.next().unwrap()).unwrap();
             }
         }
     }
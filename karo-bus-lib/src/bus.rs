@@ -1,39 +1,58 @@
 use std::{
     collections::HashMap,
-    sync::{Arc, RwLock},
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, UNIX_EPOCH},
 };
 
 use anyhow::Result;
-use bson::Bson;
+use bson::{Bson, RawDocumentBuf};
 use karo_common_connection::{connection::Connection, one_time_connector::OneTimeConnector};
 use log::*;
+use serde::{de::DeserializeOwned, Serialize};
 use tokio::{
     net::UnixStream,
     sync::{
+        broadcast::{self, Receiver as BroadcastReceiver, Sender as BroadcastSender},
         mpsc::{self, Receiver, Sender},
-        oneshot::Sender as OneSender,
         RwLock as TokioRwLock,
     },
 };
+use tokio_stream::Stream;
 
 use karo_common_rpc::{
     rpc_connection::RpcConnection, rpc_sender::RpcSender, Message as MessageHandle,
 };
 
 use crate::{
-    connections::{hub::Hub, peer::Peer},
-    endpoints::Endpoints,
+    channel::{ChannelReceiver, ChannelSender},
+    connections::{fd_channel::FdChannel, hub::Hub, peer::Peer},
+    endpoints::{
+        context::{CallContext, CancellationToken},
+        method::{MethodOptions, MethodPanic},
+        object::Object,
+        registration::Registration,
+        signal::{Signal, SignalOptions},
+        state::{State, StateOptions},
+        Endpoints,
+    },
+    events::BusEvent,
     monitor::Monitor,
 };
 
 use karo_bus_common::{
+    admin::AdminRequest,
     errors::Error as BusError,
-    messages::{IntoMessage, Message, MessageBody, Response, ServiceMessage},
+    filter::Filter,
+    messages::{IntoMessage, Message, MessageBody, PeerCredentials, Response, ServiceMessage},
     monitor::MONITOR_SERVICE_NAME,
+    schema::BusSchema,
 };
 
 type Shared<T> = Arc<RwLock<T>>;
-type MethodCall = (Bson, OneSender<Response>);
 
 /// Bus connection handle. Associated with a service name at the hub.
 /// Use to connect to other services,
@@ -46,14 +65,19 @@ pub struct Bus {
     peers: Arc<TokioRwLock<HashMap<String, Peer>>>,
     /// User service endpoints
     endpoints: Endpoints,
-    /// Sender to make calls into the task
-    endpoints_tx: Sender<MessageHandle>,
+    /// Sender to pass incoming peer messages into the task along with the peer name
+    endpoints_tx: Sender<(String, MessageHandle)>,
     /// Sender to shutdown bus connection
     shutdown_tx: Sender<()>,
     /// Monitor connection if connected
     monitor: Monitor,
     /// Hub writer to perform outgoing connections
     hub_sender: RpcSender,
+    /// Sender to notify users about bus connection events
+    events_tx: BroadcastSender<BusEvent>,
+    /// If the service is registered at the hub now. Used to report every
+    /// disconnection once, whether the hub shuts down or the connection is lost
+    hub_connected: Arc<AtomicBool>,
 }
 
 impl Bus {
@@ -69,8 +93,12 @@ impl Bus {
 
         let (task_tx, rx) = mpsc::channel(32);
         let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
+        let (events_tx, _events_rx) = broadcast::channel(16);
 
-        let hub_connection = Hub::new(service_name).await?;
+        let hub_connected = Arc::new(AtomicBool::new(false));
+
+        let hub_connection =
+            Hub::new(service_name, events_tx.clone(), hub_connected.clone()).await?;
         let mut this = Self {
             service_name: service_name.into(),
             peers: Arc::new(TokioRwLock::new(HashMap::new())),
@@ -79,6 +107,8 @@ impl Bus {
             shutdown_tx,
             monitor: Monitor::new(service_name),
             hub_sender: hub_connection.sender(),
+            events_tx,
+            hub_connected,
         };
 
         // Start tokio task to handle incoming messages
@@ -91,7 +121,7 @@ impl Bus {
     fn start(
         &mut self,
         mut hub_connection: RpcConnection,
-        mut interfaces_rx: Receiver<MessageHandle>,
+        mut interfaces_rx: Receiver<(String, MessageHandle)>,
         mut shutdown_rx: Receiver<()>,
     ) {
         let mut this = self.clone();
@@ -105,10 +135,10 @@ impl Bus {
 
                         this.handle_bus_message(message, &mut hub_connection).await;
                     },
-                    Some(message_handle) = interfaces_rx.recv() => {
-                        trace!("Service task message: {:?}", message_handle.body::<Bson>());
+                    Some((peer_name, message_handle)) = interfaces_rx.recv() => {
+                        trace!("Service task message from `{}`: {:?}", peer_name, message_handle.body::<Bson>());
 
-                        this.handle_task_message(message_handle).await;
+                        this.handle_task_message(peer_name, message_handle).await;
                     },
                     Some(_) = shutdown_rx.recv() => {
                         drop(hub_connection);
@@ -119,6 +149,301 @@ impl Bus {
         });
     }
 
+    /// Register service method. Calls are handled concurrently with default [MethodOptions].\
+    /// **P** is paramtere type. Should be a deserializable structure\
+    /// **R** is method return type. Should be a serializable structure\
+    /// **Returns** [Registration] handle. Dropping the handle unregisters the method
+    pub fn register_method<P, R, Ret>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        Ret: Future<Output = R> + Send + 'static,
+    {
+        self.endpoints.register_method(method_name, callback)
+    }
+
+    /// Register service method with given execution **options**. Use
+    /// [MethodOptions::serialized] to handle calls one by one.
+    /// Calls exceeding the queue size are rejected with [BusError::Busy]
+    pub fn register_method_with_options<P, R, Ret>(
+        &mut self,
+        method_name: &str,
+        options: MethodOptions,
+        callback: impl Fn(P) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        Ret: Future<Output = R> + Send + 'static,
+    {
+        self.endpoints
+            .register_method_with_options(method_name, options, callback)
+    }
+
+    /// Register service method, which receives [CallContext] along with the parameters.
+    /// The context contains caller identity and call metadata. Use [MethodOptions::default]
+    /// for default execution options
+    pub fn register_method_with_context<P, R, Ret>(
+        &mut self,
+        method_name: &str,
+        options: MethodOptions,
+        callback: impl Fn(P, CallContext) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        Ret: Future<Output = R> + Send + 'static,
+    {
+        self.endpoints
+            .register_method_with_context(method_name, options, callback)
+    }
+
+    /// Register service method, which may fail. Error **E** is returned to the caller
+    /// as [crate::errors::MethodError], which can be deserialized back into **E**.\
+    /// **P** is paramtere type. Should be a deserializable structure\
+    /// **R** is method return type. Should be a serializable structure\
+    /// **E** is method error type. Should be a serializable structure
+    pub fn register_fallible_method<P, R, E, Ret>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        E: Serialize + Send + 'static,
+        Ret: Future<Output = std::result::Result<R, E>> + Send + 'static,
+    {
+        self.endpoints
+            .register_fallible_method(method_name, callback)
+    }
+
+    /// Register service method, which may fail and receives [CallContext] along
+    /// with the parameters. See [Bus::register_fallible_method]
+    pub fn register_fallible_method_with_context<P, R, E, Ret>(
+        &mut self,
+        method_name: &str,
+        options: MethodOptions,
+        callback: impl Fn(P, CallContext) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        E: Serialize + Send + 'static,
+        Ret: Future<Output = std::result::Result<R, E>> + Send + 'static,
+    {
+        self.endpoints
+            .register_fallible_method_with_context(method_name, options, callback)
+    }
+
+    /// Register streaming method. The handler returns a stream of items, which are sent to
+    /// the caller with flow control: the handler stream is polled only if the caller is
+    /// ready to receive more items.\
+    /// **P** is paramtere type. Should be a deserializable structure\
+    /// **R** is stream item type. Should be a serializable structure\
+    /// **Returns** [Registration] handle. Dropping the handle unregisters the method
+    pub fn register_streaming_method<P, R, S>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P) -> S + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        S: Stream<Item = R> + Send + 'static,
+    {
+        self.endpoints
+            .register_streaming_method(method_name, callback)
+    }
+
+    /// Register streaming method, which receives [CallContext] along with the parameters.
+    /// The context is cancelled if the caller drops the stream.
+    /// See [Bus::register_streaming_method]
+    pub fn register_streaming_method_with_context<P, R, S>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P, CallContext) -> S + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        S: Stream<Item = R> + Send + 'static,
+    {
+        self.endpoints
+            .register_streaming_method_with_context(method_name, callback)
+    }
+
+    /// Register method, which works on raw BSON documents. Proxies and bridges may forward
+    /// payloads without knowing their types. Parameters and return values are ordinary
+    /// call documents, so typed callers may call the method as well. Call it with [Peer::call_raw]
+    pub fn register_raw_method<Ret>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(RawDocumentBuf) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        Ret: Future<Output = RawDocumentBuf> + Send + 'static,
+    {
+        self.endpoints.register_raw_method(method_name, callback)
+    }
+
+    /// Register duplex channel. Handler receives opener's init value and a pair of
+    /// channel halves. Each side may send a limited number of items, until the other
+    /// side consumes them. The channel is closed when the handler finishes.\
+    /// **I** is init value type. Should be a deserializable structure\
+    /// **In** is incoming item type. Should be a deserializable structure\
+    /// **Out** is outgoing item type. Should be a serializable structure\
+    /// **Returns** [Registration] handle. Dropping the handle unregisters the channel
+    pub fn register_channel<I, In, Out, Ret>(
+        &mut self,
+        channel_name: &str,
+        callback: impl Fn(I, ChannelSender<Out>, ChannelReceiver<In>) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        I: DeserializeOwned + Send + 'static,
+        In: DeserializeOwned + Send + 'static,
+        Out: Serialize + Send + 'static,
+        Ret: Future<Output = ()> + Send + 'static,
+    {
+        self.endpoints.register_channel(channel_name, callback)
+    }
+
+    /// Register duplex channel, which handler receives [CallContext] along with the init value.
+    /// The context is cancelled if the opener drops the channel.
+    /// See [Bus::register_channel]
+    pub fn register_channel_with_context<I, In, Out, Ret>(
+        &mut self,
+        channel_name: &str,
+        callback: impl Fn(I, CallContext, ChannelSender<Out>, ChannelReceiver<In>) -> Ret
+            + Send
+            + Sync
+            + 'static,
+    ) -> Result<Registration>
+    where
+        I: DeserializeOwned + Send + 'static,
+        In: DeserializeOwned + Send + 'static,
+        Out: Serialize + Send + 'static,
+        Ret: Future<Output = ()> + Send + 'static,
+    {
+        self.endpoints
+            .register_channel_with_context(channel_name, callback)
+    }
+
+    /// Register service signal.\
+    /// **T** is a signal type. Should be a serializable structure.\
+    /// **Returns** [Signal] handle which can be used to emit signal
+    pub fn register_signal<T>(&mut self, signal_name: &str) -> Result<Signal<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.endpoints.register_signal(signal_name)
+    }
+
+    /// Register service signal with [SignalOptions]. See [Bus::register_signal]
+    pub fn register_signal_with_options<T>(
+        &mut self,
+        signal_name: &str,
+        options: SignalOptions,
+    ) -> Result<Signal<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.endpoints
+            .register_signal_with_options(signal_name, options)
+    }
+
+    /// Register service state.\
+    /// **T** is a state type. Should be a serializable structure.\
+    /// **Returns** [State] handle which can be used to change state
+    pub fn register_state<T>(&mut self, state_name: &str, initial_value: T) -> Result<State<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.endpoints.register_state(state_name, initial_value)
+    }
+
+    /// Register service state with [StateOptions]. See [Bus::register_state]
+    pub fn register_state_with_options<T>(
+        &mut self,
+        state_name: &str,
+        initial_value: T,
+        options: StateOptions,
+    ) -> Result<State<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.endpoints
+            .register_state_with_options(state_name, initial_value, options)
+    }
+
+    /// Describe registered method parameters and return value in a structured way.
+    /// Tools use the schema from the service inspection to validate and build arguments.\
+    /// **P** is parameter type\
+    /// **R** is return type
+    pub fn describe_method<P: BusSchema, R: BusSchema>(&mut self, method_name: &str) -> Result<()> {
+        self.endpoints.describe_method::<P, R>(method_name)
+    }
+
+    /// Describe registered signal value in a structured way. See [Bus::describe_method]
+    pub fn describe_signal<T: BusSchema>(&mut self, signal_name: &str) -> Result<()> {
+        self.endpoints.describe_signal::<T>(signal_name)
+    }
+
+    /// Describe registered state value in a structured way. See [Bus::describe_method]
+    pub fn describe_state<T: BusSchema>(&mut self, state_name: &str) -> Result<()> {
+        self.endpoints.describe_state::<T>(state_name)
+    }
+
+    /// Unregister method or streaming method. Calls being handled are finished, next calls
+    /// fail with [BusError::NotRegistered]. The method is removed from the service inspection
+    pub fn unregister_method(&mut self, method_name: &str) -> Result<()> {
+        self.endpoints.unregister_method(method_name)
+    }
+
+    /// Unregister duplex channel. Already open channels are not closed
+    pub fn unregister_channel(&mut self, channel_name: &str) -> Result<()> {
+        self.endpoints.unregister_channel(channel_name)
+    }
+
+    /// Unregister signal. Subscribers get end of their subscription streams.
+    /// Dropping the [Signal] handle unregisters the signal as well
+    pub fn unregister_signal(&mut self, signal_name: &str) -> Result<()> {
+        self.endpoints.unregister_signal(signal_name)
+    }
+
+    /// Unregister state. Watchers get end of their watch streams.
+    /// Dropping the [State] handle unregisters the state as well
+    pub fn unregister_state(&mut self, state_name: &str) -> Result<()> {
+        self.endpoints.unregister_state(state_name)
+    }
+
+    /// Register an object at the **path**, e.g. `/devices/eth0`. Objects have their own
+    /// endpoints, so a service may expose a number of objects with the same interface.
+    /// Peers address objects with [Peer::object]. Inspecting the service or an object
+    /// lists child objects. Dropping the [Object] handle unregisters the object
+    pub fn object(&mut self, path: &str) -> Result<Object> {
+        self.endpoints.register_object(path)?;
+
+        Ok(Object::new(path, self.endpoints.clone()))
+    }
+
+    /// Set a hook to report method handler panics. A panicked call is replied with
+    /// [BusError::Internal] and the method keeps handling next calls
+    pub fn set_method_panic_hook(&mut self, hook: impl Fn(&MethodPanic) + Send + Sync + 'static) {
+        self.endpoints.set_method_panic_hook(hook)
+    }
+
+    /// Subscribe to the bus connection events. The bus reconnects to the hub
+    /// automatically if the connection is lost. Existing [Peer] connections are p2p
+    /// and keep working while the hub restarts
+    pub fn events(&self) -> BroadcastReceiver<BusEvent> {
+        self.events_tx.subscribe()
+    }
+
     /// Perform connection to an another service.
     /// The method may fail if:
     /// 1. The service is not allowed to connect to a target service
@@ -170,7 +495,7 @@ impl Bus {
                         return Err(BusError::Internal.into());
                     }
 
-                    self.register_peer_fd(peer_service_name, stream.unwrap(), true)
+                    self.register_peer_fd(peer_service_name, stream.unwrap(), true, None)
                         .await;
                 }
                 // Hub doesn't allow connection
@@ -198,44 +523,177 @@ impl Bus {
             .unwrap())
     }
 
-    /// Handle messages incoming form an existent peer connection
-    async fn handle_task_message(&mut self, mut message_handle: MessageHandle) {
+    /// Make a privileged hub admin request. Only [karo_bus_common::admin::CTL_SERVICE_NAME]
+    /// service is allowed to make admin requests.\
+    /// **R** is the response type. See [AdminRequest] for response types
+    pub async fn admin_call<R: DeserializeOwned>(&mut self, request: AdminRequest) -> Result<R> {
+        debug!("Making hub admin request: {}", request);
+
+        let response = self
+            .hub_sender
+            .call(&Message::new_admin_request(request))
+            .await?;
+
+        match response.body() {
+            MessageBody::Response(Response::Return(data)) => match bson::from_bson::<R>(data) {
+                Ok(data) => Ok(data),
+                Err(err) => {
+                    error!("Can't deserialize admin response: {}", err.to_string());
+                    Err(BusError::InvalidResponse.into())
+                }
+            },
+            // Hub refused the request
+            MessageBody::Response(Response::Error(err)) => {
+                warn!("Hub admin request failed: {}", err);
+                Err(err.into())
+            }
+            // Invalid protocol here
+            m => {
+                error!("Invalid response from the hub: {:?}", m);
+                Err(BusError::InvalidMessage.into())
+            }
+        }
+    }
+
+    /// Handle messages incoming form an existent peer connection to the **caller_name** service.
+    /// Caller and subscriber names written into the messages are ignored, because a peer
+    /// can claim any name. Calls, streams, channels, and subscriptions are keyed by the connection
+    async fn handle_task_message(
+        &mut self,
+        caller_name: String,
+        mut message_handle: MessageHandle,
+    ) {
         match message_handle.body() {
             MessageBody::MethodCall {
-                caller_name,
+                caller_name: _,
                 method_name,
                 params,
+                deadline,
+                headers,
+                call_seq,
+            } => {
+                let context = CallContext {
+                    credentials: self.peer_credentials(&caller_name).await,
+                    fd_channel: self.peer_fd_channel(&caller_name).await,
+                    caller_name,
+                    seq: message_handle.id(),
+                    deadline: deadline.map(|deadline| UNIX_EPOCH + Duration::from_millis(deadline)),
+                    headers,
+                    cancellation: CancellationToken::new(),
+                    call_seq,
+                };
+
+                self.handle_method_call(&method_name, params, context, message_handle)
+                    .await;
+            }
+            MessageBody::StreamCall {
+                caller_name: _,
+                method_name,
+                params,
+                call_seq,
+                window,
+            } => {
+                let context = CallContext {
+                    credentials: self.peer_credentials(&caller_name).await,
+                    fd_channel: self.peer_fd_channel(&caller_name).await,
+                    caller_name,
+                    seq: message_handle.id(),
+                    deadline: None,
+                    headers: HashMap::new(),
+                    cancellation: CancellationToken::new(),
+                    call_seq,
+                };
+
+                self.handle_stream_call(&method_name, params, window, context, &mut message_handle)
+                    .await;
+            }
+            MessageBody::StreamCredit {
+                caller_name: _,
+                seq,
+                credits,
+            } => {
+                self.endpoints
+                    .handle_stream_credit(&caller_name, seq, credits);
+            }
+            MessageBody::OpenChannel {
+                caller_name: _,
+                channel_name,
+                init,
+                call_seq,
+                window,
             } => {
-                self.handle_method_call(&caller_name, &method_name, &params, &mut message_handle)
+                let context = CallContext {
+                    credentials: self.peer_credentials(&caller_name).await,
+                    fd_channel: self.peer_fd_channel(&caller_name).await,
+                    caller_name,
+                    seq: message_handle.id(),
+                    deadline: None,
+                    headers: HashMap::new(),
+                    cancellation: CancellationToken::new(),
+                    call_seq,
+                };
+
+                self.handle_open_channel(&channel_name, init, window, context, &mut message_handle)
                     .await;
             }
+            MessageBody::ChannelData {
+                caller_name: _,
+                seq,
+                data,
+            } => {
+                self.endpoints.handle_channel_data(&caller_name, seq, data);
+            }
+            MessageBody::ChannelClose { seq, .. } => {
+                self.endpoints.handle_channel_close(&caller_name, seq);
+            }
+            // Caller gave up waiting for a call
+            MessageBody::Cancel { seq, .. } => {
+                self.endpoints.handle_cancel(&caller_name, seq);
+            }
             MessageBody::SignalSubscription {
-                subscriber_name,
+                subscriber_name: _,
                 signal_name,
+                subscription_seq,
+                filter,
             } => {
                 let response = self
                     .handle_incoming_signal_subscription(
-                        &subscriber_name,
+                        &caller_name,
                         &signal_name,
+                        subscription_seq,
+                        filter,
                         &mut message_handle,
                     )
                     .await;
             }
             MessageBody::StateSubscription {
-                subscriber_name,
+                subscriber_name: _,
                 state_name,
+                subscription_seq,
             } => {
                 let response = self
-                    .handle_incoming_state_watch(&subscriber_name, &state_name, &mut message_handle)
+                    .handle_incoming_state_watch(
+                        &caller_name,
+                        &state_name,
+                        subscription_seq,
+                        &mut message_handle,
+                    )
                     .await;
             }
+            // Subscriber dropped a subscription stream
+            MessageBody::Unsubscribe {
+                subscriber_name: _,
+                seq,
+            } => {
+                self.endpoints.handle_unsubscribe(&caller_name, seq);
+            }
             // Peer connection wants us to shut it down
-            MessageBody::Response(Response::Shutdown(peer_name)) => {
+            MessageBody::Response(Response::Shutdown(_)) => {
                 info!(
                     "Service connection received shutdown request from {}",
-                    peer_name
+                    caller_name
                 );
-                self.remove_peer(peer_name.clone()).await;
+                self.remove_peer(caller_name).await;
             }
             m => {
                 error!("Invalid client message: {:?}", m)
@@ -246,18 +704,85 @@ impl Bus {
     /// Handle incoming method call
     async fn handle_method_call(
         &self,
-        caller_name: &str,
         method_name: &str,
-        params: &Bson,
+        params: Bson,
+        context: CallContext,
+        handle: MessageHandle,
+    ) {
+        self.endpoints
+            .handle_method_call(method_name, params, context, handle)
+            .await;
+    }
+
+    /// Handle incoming streaming method call
+    async fn handle_stream_call(
+        &self,
+        method_name: &str,
+        params: Bson,
+        window: u32,
+        context: CallContext,
         handle: &mut MessageHandle,
     ) {
+        let seq = handle.id();
+
+        let peer_sender = match self.peers.read().await.get(&context.caller_name) {
+            Some(caller) => caller.sender(),
+            None => {
+                handle.reply(&BusError::Internal.into_message(seq)).await;
+                return;
+            }
+        };
+
         self.endpoints
-            .handle_method_call(caller_name, method_name, params, handle);
+            .handle_stream_call(method_name, params, window, context, handle, peer_sender)
+            .await;
     }
 
-    /// Handle incoming method call
-    fn handle_inspect_call(&self, seq: u64) -> Message {
-        self.endpoints.handle_inspect_call(seq)
+    /// Handle incoming channel open request
+    async fn handle_open_channel(
+        &self,
+        channel_name: &str,
+        init: Bson,
+        window: u32,
+        context: CallContext,
+        handle: &mut MessageHandle,
+    ) {
+        let seq = handle.id();
+
+        let peer_sender = match self.peers.read().await.get(&context.caller_name) {
+            Some(caller) => caller.sender(),
+            None => {
+                handle.reply(&BusError::Internal.into_message(seq)).await;
+                return;
+            }
+        };
+
+        self.endpoints
+            .handle_open_channel(channel_name, init, window, context, handle, peer_sender)
+            .await;
+    }
+
+    /// Descriptor side channel of the peer connection
+    async fn peer_fd_channel(&self, peer_service_name: &str) -> Option<FdChannel> {
+        self.peers
+            .read()
+            .await
+            .get(peer_service_name)
+            .map(Peer::fd_channel)
+    }
+
+    /// Peer process credentials provided by the hub
+    async fn peer_credentials(&self, peer_service_name: &str) -> Option<PeerCredentials> {
+        self.peers
+            .read()
+            .await
+            .get(peer_service_name)
+            .and_then(Peer::credentials)
+    }
+
+    /// Handle incoming inspection call of the object at the **path**
+    fn handle_inspect_call(&self, seq: u64, path: &str) -> Message {
+        self.endpoints.handle_inspect_call(seq, path)
     }
 
     /// Handle incoming signal subscription
@@ -265,18 +790,24 @@ impl Bus {
         &self,
         subscriber_name: &str,
         signal_name: &str,
+        subscription_seq: u64,
+        filter: Option<Filter>,
         handle: &mut MessageHandle,
     ) {
         let seq = handle.id();
 
         match self.peers.read().await.get(subscriber_name) {
             Some(caller) => {
-                self.endpoints.handle_incoming_signal_subscription(
-                    subscriber_name,
-                    signal_name,
-                    handle,
-                    caller,
-                );
+                self.endpoints
+                    .handle_incoming_signal_subscription(
+                        subscriber_name,
+                        signal_name,
+                        subscription_seq,
+                        filter,
+                        handle,
+                        caller,
+                    )
+                    .await;
             }
             None => {
                 handle.reply(&BusError::Internal.into_message(seq));
@@ -289,18 +820,22 @@ impl Bus {
         &self,
         subscriber_name: &str,
         state_name: &str,
+        subscription_seq: u64,
         handle: &mut MessageHandle,
     ) {
         let seq = handle.id();
 
         match self.peers.read().await.get(subscriber_name) {
             Some(caller) => {
-                self.endpoints.handle_incoming_state_watch(
-                    subscriber_name,
-                    state_name,
-                    handle,
-                    caller,
-                );
+                self.endpoints
+                    .handle_incoming_state_watch(
+                        subscriber_name,
+                        state_name,
+                        subscription_seq,
+                        handle,
+                        caller,
+                    )
+                    .await;
             }
             None => {
                 handle.reply(&BusError::Internal.into_message(seq));
@@ -324,7 +859,10 @@ impl Bus {
 
         match message_handle.body() {
             // Incoming connection request. Connection socket FD will be coming next
-            MessageBody::ServiceMessage(ServiceMessage::IncomingPeerFd { peer_service_name }) => {
+            MessageBody::ServiceMessage(ServiceMessage::IncomingPeerFd {
+                peer_service_name,
+                peer_credentials,
+            }) => {
                 trace!("Incoming file descriptor for a peer: {}", peer_service_name);
 
                 let stream = message_handle.take_fd();
@@ -339,9 +877,19 @@ impl Bus {
                     return;
                 }
 
-                self.register_peer_fd(&peer_service_name, stream.unwrap(), false)
+                self.register_peer_fd(&peer_service_name, stream.unwrap(), false, peer_credentials)
                     .await;
             }
+            // Hub is shutting down or the connection is lost. The connection will reconnect
+            // by itself, but we keep peers untouched, because they don't depend on the hub
+            MessageBody::Response(Response::Shutdown(reason)) => {
+                warn!("Hub connection closed: {}", reason);
+
+                if self.hub_connected.swap(false, Ordering::SeqCst) {
+                    // No one may listen for the events
+                    let _ = self.events_tx.send(BusEvent::HubDisconnected(reason));
+                }
+            }
             // If got a response to a call, handle it by call_registry. Otherwise it's
             // an incoming call. Use [handle_bus_message]
             m => error!("Invalid message from the hub: {:?}", m),
@@ -349,7 +897,13 @@ impl Bus {
     }
 
     /// Register new [Peer] with a given unix socket file descriptor
-    async fn register_peer_fd(&self, peer_service_name: &str, stream: UnixStream, outgoing: bool) {
+    async fn register_peer_fd(
+        &self,
+        peer_service_name: &str,
+        stream: UnixStream,
+        outgoing: bool,
+        peer_credentials: Option<PeerCredentials>,
+    ) {
         // Create new service connection handle. Can be used to handle own
         // connection requests by just returning already existing handle
         let mut new_service_connection = Peer::new(
@@ -358,6 +912,8 @@ impl Bus {
             Some(stream),
             self.endpoints_tx.clone(),
             self.hub_sender.clone(),
+            peer_credentials,
+            outgoing,
         )
         .await
         .unwrap();
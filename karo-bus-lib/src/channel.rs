@@ -0,0 +1,381 @@
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use anyhow::Result;
+use bson::Bson;
+use log::*;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::{
+    mpsc::{Receiver, Sender},
+    Semaphore,
+};
+use tokio_stream::{Stream, StreamExt};
+
+use karo_bus_common::{
+    errors::Error,
+    messages::{IntoMessage, MessageBody, Response},
+};
+use karo_common_rpc::rpc_sender::RpcSender;
+
+use crate::{
+    connections::call_stream::MessageStream,
+    endpoints::context::{CallContext, CancellationToken},
+};
+
+/// Default number of channel items a peer may send before receiving a credit
+pub const DEFAULT_CHANNEL_WINDOW: u32 = 16;
+
+/// Future running a channel handler
+pub(crate) type ChannelFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+/// Type erased channel handler. Fails if init value can't be deserialized
+pub(crate) type ChannelHandler =
+    Arc<dyn Fn(Bson, CallContext, ChannelParts) -> Result<ChannelFuture, Error> + Send + Sync>;
+
+/// Channel end. Channel messages look different depending on which
+/// side has opened the channel
+#[derive(Debug, Clone)]
+pub(crate) enum ChannelSide {
+    /// Side, which opened the channel using the caller assigned *call_seq*
+    Caller { caller_name: String, call_seq: u64 },
+    /// Side, which handles the channel. Replies using the open request *seq*
+    Callee { seq: u64 },
+}
+
+impl ChannelSide {
+    fn data_message(&self, data: Bson) -> Bson {
+        match self {
+            Self::Caller {
+                caller_name,
+                call_seq,
+            } => bson::to_bson(&MessageBody::ChannelData {
+                caller_name: caller_name.clone(),
+                seq: *call_seq,
+                data,
+            }),
+            Self::Callee { seq } => bson::to_bson(&Response::ChannelData(data).into_message(*seq)),
+        }
+        .unwrap()
+    }
+
+    fn credit_message(&self, credits: u32) -> Bson {
+        match self {
+            Self::Caller {
+                caller_name,
+                call_seq,
+            } => bson::to_bson(&MessageBody::StreamCredit {
+                caller_name: caller_name.clone(),
+                seq: *call_seq,
+                credits,
+            }),
+            Self::Callee { seq } => {
+                bson::to_bson(&Response::ChannelCredit(credits).into_message(*seq))
+            }
+        }
+        .unwrap()
+    }
+
+    fn close_message(&self) -> Bson {
+        match self {
+            Self::Caller {
+                caller_name,
+                call_seq,
+            } => bson::to_bson(&MessageBody::ChannelClose {
+                caller_name: caller_name.clone(),
+                seq: *call_seq,
+            }),
+            Self::Callee { seq } => bson::to_bson(&Response::ChannelClose.into_message(*seq)),
+        }
+        .unwrap()
+    }
+}
+
+/// Untyped channel end parts. Used to create typed [ChannelSender] and [ChannelReceiver]
+pub(crate) struct ChannelParts {
+    pub side: ChannelSide,
+    pub peer_sender: RpcSender,
+    /// Number of items we're allowed to send to the other side
+    pub send_credits: Arc<Semaphore>,
+    /// Incoming items
+    pub data_rx: Receiver<Result<Bson, Error>>,
+    pub window: u32,
+    /// Opener side guard, which is shared between the channel halves
+    pub guard: Option<Arc<ChannelGuard>>,
+}
+
+impl ChannelParts {
+    pub fn split<S, R>(self) -> (ChannelSender<S>, ChannelReceiver<R>) {
+        (
+            ChannelSender {
+                side: self.side.clone(),
+                peer_sender: self.peer_sender.clone(),
+                credits: self.send_credits,
+                closed: false,
+                _guard: self.guard.clone(),
+                _phantom: PhantomData,
+            },
+            ChannelReceiver {
+                data_rx: self.data_rx,
+                side: self.side,
+                peer_sender: self.peer_sender,
+                credit_batch: (self.window / 2).max(1),
+                consumed: 0,
+                _guard: self.guard,
+                _phantom: PhantomData,
+            },
+        )
+    }
+}
+
+/// Sending half of a peer channel.\
+/// **T** is item type. Should be a serializable structure
+pub struct ChannelSender<T> {
+    side: ChannelSide,
+    peer_sender: RpcSender,
+    /// Number of items the other side is ready to receive
+    credits: Arc<Semaphore>,
+    closed: bool,
+    _guard: Option<Arc<ChannelGuard>>,
+    _phantom: PhantomData<fn(T)>,
+}
+
+impl<T: Serialize> ChannelSender<T> {
+    /// Send an item. Waits if the other side is not ready to receive more items.
+    /// Fails if the channel is closed by the other side
+    pub async fn send(&mut self, item: &T) -> Result<()> {
+        if self.closed {
+            return Err(Error::NotConnected.into());
+        }
+
+        match self.credits.acquire().await {
+            Ok(permit) => permit.forget(),
+            Err(_) => {
+                self.closed = true;
+                return Err(Error::NotConnected.into());
+            }
+        }
+
+        let data = bson::to_bson(item)?;
+
+        if self
+            .peer_sender
+            .send(self.side.data_message(data))
+            .await
+            .is_err()
+        {
+            warn!("Failed to send channel item. Peer is probably closed");
+            self.closed = true;
+            return Err(Error::NotConnected.into());
+        }
+
+        Ok(())
+    }
+
+    /// Tell the other side we won't send anything else. The receiving half keeps working
+    pub async fn close(mut self) {
+        self.closed = true;
+
+        let _ = self.peer_sender.send(self.side.close_message()).await;
+    }
+}
+
+impl<T> Drop for ChannelSender<T> {
+    fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
+
+        let peer_sender = self.peer_sender.clone();
+        let message = self.side.close_message();
+
+        tokio::spawn(async move {
+            let _ = peer_sender.send(message).await;
+        });
+    }
+}
+
+/// Receiving half of a peer channel. Ends when the other side closes its sending half.\
+/// **T** is item type. Should be a deserializable structure
+pub struct ChannelReceiver<T> {
+    /// Incoming items
+    data_rx: Receiver<Result<Bson, Error>>,
+    side: ChannelSide,
+    peer_sender: RpcSender,
+    /// Number of items we return as a credit at once
+    credit_batch: u32,
+    /// Number of consumed items not yet returned as a credit
+    consumed: u32,
+    _guard: Option<Arc<ChannelGuard>>,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> ChannelReceiver<T> {
+    /// Account consumed item and return credits to the other side if consumed enough
+    fn consume(&mut self) {
+        self.consumed += 1;
+
+        if self.consumed >= self.credit_batch {
+            let peer_sender = self.peer_sender.clone();
+            let message = self.side.credit_message(self.consumed);
+
+            tokio::spawn(async move {
+                let _ = peer_sender.send(message).await;
+            });
+
+            self.consumed = 0;
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Stream for ChannelReceiver<T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match this.data_rx.poll_recv(cx) {
+            Poll::Ready(Some(Ok(data))) => {
+                this.consume();
+
+                Poll::Ready(Some(bson::from_bson::<T>(data).map_err(|err| {
+                    error!("Can't deserialize channel item: {}", err.to_string());
+                    Error::InvalidResponse.into()
+                })))
+            }
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err.into()))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Channel state on the handling side
+pub(crate) struct ChannelState {
+    /// Sender of incoming items. None if the opener closed its sending half
+    pub data_tx: Option<Sender<Result<Bson, Error>>>,
+    /// Number of items the handler is allowed to send
+    pub send_credits: Arc<Semaphore>,
+    /// Cancelled if the opener dropped the channel
+    pub cancellation: CancellationToken,
+}
+
+/// Opener side channel guard. Cancels the channel when both channel halves are dropped
+pub(crate) struct ChannelGuard {
+    peer_sender: RpcSender,
+    caller_name: String,
+    call_seq: u64,
+    /// Channel is finished by the handler. Nothing to cancel
+    pub finished: Arc<AtomicBool>,
+    /// Stops the opener channel task
+    pub cancellation: CancellationToken,
+}
+
+impl ChannelGuard {
+    pub fn new(peer_sender: RpcSender, caller_name: String, call_seq: u64) -> Self {
+        Self {
+            peer_sender,
+            caller_name,
+            call_seq,
+            finished: Arc::new(AtomicBool::new(false)),
+            cancellation: CancellationToken::new(),
+        }
+    }
+}
+
+impl Drop for ChannelGuard {
+    fn drop(&mut self) {
+        self.cancellation.cancel();
+
+        if self.finished.load(Ordering::Acquire) {
+            return;
+        }
+
+        trace!("Channel {} dropped. Cancelling", self.call_seq);
+
+        let message = MessageBody::Cancel {
+            caller_name: self.caller_name.clone(),
+            seq: self.call_seq,
+        };
+        let peer_sender = self.peer_sender.clone();
+
+        tokio::spawn(async move {
+            let _ = peer_sender.send(bson::to_bson(&message).unwrap()).await;
+        });
+    }
+}
+
+/// Start opener side task, which dispatches incoming channel messages
+/// into *data_tx* and *send_credits*
+pub(crate) fn start_channel_task(
+    mut messages: MessageStream,
+    side: ChannelSide,
+    peer_sender: RpcSender,
+    data_tx: Sender<Result<Bson, Error>>,
+    send_credits: Arc<Semaphore>,
+    finished: Arc<AtomicBool>,
+    cancellation: CancellationToken,
+) {
+    tokio::spawn(async move {
+        // None if the handler closed its sending half
+        let mut data_tx = Some(data_tx);
+
+        loop {
+            let message = tokio::select! {
+                _ = cancellation.cancelled() => break,
+                message = messages.next() => match message {
+                    Some(message) => message,
+                    None => {
+                        warn!("Channel connection closed");
+                        break;
+                    }
+                },
+            };
+
+            match message.body::<MessageBody>() {
+                MessageBody::Response(Response::ChannelData(data)) => {
+                    let delivered = match &data_tx {
+                        Some(data_tx) => data_tx.send(Ok(data)).await.is_ok(),
+                        None => false,
+                    };
+
+                    // Receiving half is dropped. Return the credit right away to not stall the handler
+                    if !delivered {
+                        let _ = peer_sender.send(side.credit_message(1)).await;
+                    }
+                }
+                MessageBody::Response(Response::ChannelCredit(credits)) => {
+                    send_credits.add_permits(credits as usize)
+                }
+                MessageBody::Response(Response::ChannelClose) => data_tx = None,
+                MessageBody::Response(Response::StreamEnd) => {
+                    finished.store(true, Ordering::Release);
+                    break;
+                }
+                MessageBody::Response(Response::Error(err)) => {
+                    finished.store(true, Ordering::Release);
+
+                    if let Some(data_tx) = &data_tx {
+                        let _ = data_tx.send(Err(err)).await;
+                    }
+                    break;
+                }
+                m => {
+                    error!("Invalid channel message: {:?}", m);
+                    break;
+                }
+            }
+        }
+
+        trace!("Channel {:?} finished", side);
+
+        // Fail pending sends
+        send_credits.close();
+    });
+}
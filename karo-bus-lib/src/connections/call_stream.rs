@@ -0,0 +1,157 @@
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use anyhow::Result;
+use log::*;
+use serde::de::DeserializeOwned;
+use tokio_stream::Stream;
+
+use karo_bus_common::{
+    errors::Error,
+    messages::{MessageBody, Response},
+};
+use karo_common_rpc::{rpc_sender::RpcSender, Message as MessageHandle};
+
+use crate::errors::MethodError;
+
+/// Stream of incoming messages for a call
+pub(crate) type MessageStream = Pin<Box<dyn Stream<Item = MessageHandle> + Send>>;
+
+/// Streaming method call results. Yields items until the callee ends the stream or fails.
+/// Dropping the stream cancels the call.\
+/// **R** is stream item type
+pub struct CallStream<R> {
+    /// Incoming stream messages
+    messages: MessageStream,
+    /// Sender to return credits or cancel the call
+    peer_sender: RpcSender,
+    /// Own service name
+    caller_name: String,
+    /// Caller assigned call seq
+    call_seq: u64,
+    /// Number of items we return as a credit at once
+    credit_batch: u32,
+    /// Number of consumed items not yet returned as a credit
+    consumed: u32,
+    /// Callee ended the stream
+    finished: bool,
+    _phantom: PhantomData<fn() -> R>,
+}
+
+impl<R> CallStream<R> {
+    pub(crate) fn new(
+        messages: MessageStream,
+        peer_sender: RpcSender,
+        caller_name: String,
+        call_seq: u64,
+        window: u32,
+    ) -> Self {
+        Self {
+            messages,
+            peer_sender,
+            caller_name,
+            call_seq,
+            credit_batch: (window / 2).max(1),
+            consumed: 0,
+            finished: false,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Send a message to the callee without waiting
+    fn send_message(&self, message: MessageBody) {
+        let peer_sender = self.peer_sender.clone();
+
+        tokio::spawn(async move {
+            let _ = peer_sender.send(bson::to_bson(&message).unwrap()).await;
+        });
+    }
+
+    /// Account consumed item and return credits to the callee if consumed enough
+    fn consume(&mut self) {
+        self.consumed += 1;
+
+        if self.consumed >= self.credit_batch {
+            self.send_message(MessageBody::StreamCredit {
+                caller_name: self.caller_name.clone(),
+                seq: self.call_seq,
+                credits: self.consumed,
+            });
+
+            self.consumed = 0;
+        }
+    }
+}
+
+impl<R: DeserializeOwned> Stream for CallStream<R> {
+    type Item = Result<R>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.finished {
+            return Poll::Ready(None);
+        }
+
+        let message = match this.messages.as_mut().poll_next(cx) {
+            Poll::Ready(Some(message)) => message,
+            Poll::Ready(None) => {
+                warn!("Stream call {} connection closed", this.call_seq);
+                this.finished = true;
+                return Poll::Ready(None);
+            }
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let item = match message.body::<MessageBody>() {
+            MessageBody::Response(Response::StreamItem(data)) => {
+                this.consume();
+
+                match bson::from_bson::<R>(data) {
+                    Ok(item) => Ok(item),
+                    Err(err) => {
+                        error!("Can't deserialize stream item: {}", err.to_string());
+                        Err(Error::InvalidResponse.into())
+                    }
+                }
+            }
+            MessageBody::Response(Response::StreamEnd) => {
+                this.finished = true;
+                return Poll::Ready(None);
+            }
+            MessageBody::Response(Response::MethodError(data)) => {
+                this.finished = true;
+                Err(MethodError::new(data).into())
+            }
+            MessageBody::Response(Response::Error(err)) => {
+                this.finished = true;
+                Err(err.into())
+            }
+            m => {
+                error!("Invalid stream call message: {:?}", m);
+                this.finished = true;
+                Err(Error::InvalidMessage.into())
+            }
+        };
+
+        Poll::Ready(Some(item))
+    }
+}
+
+impl<R> Drop for CallStream<R> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        trace!("Stream call {} dropped. Cancelling", self.call_seq);
+
+        self.send_message(MessageBody::Cancel {
+            caller_name: self.caller_name.clone(),
+            seq: self.call_seq,
+        });
+    }
+}
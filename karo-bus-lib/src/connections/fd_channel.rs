@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    os::unix::{
+        io::{AsRawFd, FromRawFd, OwnedFd},
+        net::UnixStream as StdUnixStream,
+    },
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use bson::Bson;
+use log::*;
+use tokio::{
+    net::UnixStream,
+    sync::{Mutex as TokioMutex, Notify},
+    time,
+};
+use tokio_send_fd::SendFd;
+
+use karo_bus_common::errors::Error;
+
+use crate::fd::{self, MessageFds};
+
+/// Time to wait for descriptors referred by an incoming message
+const FD_RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sending half state. Reset on every peer (re)connection
+struct FdSender {
+    socket: Arc<UnixStream>,
+    /// Id of the next descriptor sent. The peer counts received descriptors the same way
+    next_id: i64,
+}
+
+struct FdChannelInner {
+    sender: TokioMutex<Option<FdSender>>,
+    /// Received descriptors, which are not yet claimed by incoming messages
+    incoming: Mutex<HashMap<i64, OwnedFd>>,
+    /// Notifies about newly received descriptors
+    incoming_notify: Notify,
+}
+
+/// Peer side channel to pass [crate::BusFd] descriptors using SCM_RIGHTS.
+/// The peer connection socket itself is owned by the RPC connection, so the side which
+/// requested the connection creates a socket pair and passes one end to the peer right
+/// after connecting
+#[derive(Clone)]
+pub(crate) struct FdChannel {
+    inner: Arc<FdChannelInner>,
+}
+
+impl FdChannel {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(FdChannelInner {
+                sender: TokioMutex::new(None),
+                incoming: Mutex::new(HashMap::new()),
+                incoming_notify: Notify::new(),
+            }),
+        }
+    }
+
+    /// Create side channel and pass one end to the peer over the peer *stream*
+    pub async fn initiate(&self, stream: &UnixStream) -> Result<()> {
+        let (local, remote) = StdUnixStream::pair()?;
+        let remote = OwnedFd::from(remote);
+
+        stream
+            .send_fd(remote.as_raw_fd())
+            .await
+            .context("Failed to send descriptor channel to the peer")?;
+
+        local.set_nonblocking(true)?;
+        self.set_socket(UnixStream::from_std(local)?).await;
+
+        Ok(())
+    }
+
+    /// Receive side channel end from the peer over the peer *stream*
+    pub async fn accept(&self, stream: &UnixStream) -> Result<()> {
+        let fd = stream
+            .recv_fd()
+            .await
+            .context("Failed to receive descriptor channel from the peer")?;
+
+        // Safe, because we own just received descriptor
+        let socket = unsafe { StdUnixStream::from_raw_fd(fd) };
+        socket.set_nonblocking(true)?;
+        self.set_socket(UnixStream::from_std(socket)?).await;
+
+        Ok(())
+    }
+
+    /// Replace side channel socket and start receiving descriptors
+    async fn set_socket(&self, socket: UnixStream) {
+        let socket = Arc::new(socket);
+
+        self.inner.incoming.lock().unwrap().clear();
+        *self.inner.sender.lock().await = Some(FdSender {
+            socket: socket.clone(),
+            next_id: 0,
+        });
+
+        let inner = self.inner.clone();
+
+        tokio::spawn(async move {
+            let mut next_id = 0;
+
+            // Exits when the peer closes its end
+            while let Ok(fd) = socket.recv_fd().await {
+                // Safe, because we own just received descriptor
+                let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+                inner.incoming.lock().unwrap().insert(next_id, fd);
+                inner.incoming_notify.notify_waiters();
+
+                next_id += 1;
+            }
+
+            trace!("Descriptor channel closed");
+        });
+    }
+
+    /// Send descriptors collected while serializing outgoing *data*. Replaces
+    /// descriptor placeholders with the side channel ids. The peer receives duplicates,
+    /// so the caller still owns and closes the *fds*
+    pub async fn send(&self, mut data: Bson, fds: &MessageFds) -> Result<Bson> {
+        if fds.is_empty() {
+            return Ok(data);
+        }
+
+        let mut sender = self.inner.sender.lock().await;
+        let sender = sender.as_mut().ok_or(Error::NotConnected)?;
+
+        let mut ids = HashMap::new();
+        for key in fd::placeholders(&data) {
+            if ids.contains_key(&key) {
+                continue;
+            }
+
+            let fd = fds.get(key).ok_or(Error::Internal)?;
+
+            sender
+                .socket
+                .send_fd(fd.as_raw_fd())
+                .await
+                .context("Failed to send descriptor to the peer")?;
+
+            ids.insert(key, sender.next_id);
+            sender.next_id += 1;
+        }
+
+        fd::replace_placeholders(&mut data, &|key| ids.get(&key).copied());
+        Ok(data)
+    }
+
+    /// Wait for descriptors referred by incoming *data*. Replaces descriptor placeholders
+    /// with the keys of the returned message descriptors. Placeholders can refer only
+    /// descriptors passed with this message
+    pub async fn receive(&self, mut data: Bson) -> Result<(Bson, MessageFds)> {
+        let mut fds = MessageFds::default();
+
+        let ids = fd::placeholders(&data);
+        if ids.is_empty() {
+            return Ok((data, fds));
+        }
+
+        let mut keys = HashMap::new();
+        for id in ids {
+            if keys.contains_key(&id) {
+                continue;
+            }
+
+            let fd = time::timeout(FD_RECEIVE_TIMEOUT, self.wait_fd(id))
+                .await
+                .map_err(|_| {
+                    error!("Peer didn't send descriptor {}", id);
+                    Error::Timeout
+                })?;
+
+            keys.insert(id, fds.push(fd));
+        }
+
+        fd::replace_placeholders(&mut data, &|id| keys.get(&id).copied());
+        Ok((data, fds))
+    }
+
+    /// Claim and close descriptors referred by incoming *data*, which is rejected
+    /// without being handled. Descriptors still in flight are closed once they arrive
+    pub fn discard(&self, data: &Bson) {
+        let mut ids = fd::placeholders(data);
+        if ids.is_empty() {
+            return;
+        }
+
+        ids.sort_unstable();
+        ids.dedup();
+
+        let this = self.clone();
+        tokio::spawn(async move {
+            for id in ids {
+                if time::timeout(FD_RECEIVE_TIMEOUT, this.wait_fd(id))
+                    .await
+                    .is_err()
+                {
+                    warn!("Peer didn't send discarded descriptor {}", id);
+                }
+            }
+        });
+    }
+
+    async fn wait_fd(&self, id: i64) -> OwnedFd {
+        loop {
+            // Subscribe before checking to not miss a notification
+            let notified = self.inner.incoming_notify.notified();
+
+            if let Some(fd) = self.inner.incoming.lock().unwrap().remove(&id) {
+                return fd;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+impl Debug for FdChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FdChannel").finish_non_exhaustive()
+    }
+}
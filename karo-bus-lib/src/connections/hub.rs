@@ -1,7 +1,12 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
 use anyhow::Result;
+use tokio::sync::broadcast::Sender as BroadcastSender;
 
 use karo_common_rpc::{rpc_connection::RpcConnection, rpc_sender::RpcSender};
 
+use crate::events::BusEvent;
+
 use super::hub_connector::HubConnector;
 
 pub(crate) struct Hub {
@@ -15,9 +20,15 @@ pub(crate) struct Hub {
 
 /// Hub connection, which handles all network requests and responses
 impl Hub {
-    pub async fn new(service_name: &str) -> Result<RpcConnection> {
+    /// *events_tx* is used to notify the bus about hub disconnections and reconnections.
+    /// *connected* is set while the service is registered at the hub
+    pub async fn new(
+        service_name: &str,
+        events_tx: BroadcastSender<BusEvent>,
+        connected: Arc<AtomicBool>,
+    ) -> Result<RpcConnection> {
         // Peer connector, which will connect to the peer if this is and outgoing connection
-        let connector = Box::new(HubConnector::new(service_name.into()));
+        let connector = Box::new(HubConnector::new(service_name.into(), events_tx, connected));
 
         // Rpc connection
         RpcConnection::new(connector).await
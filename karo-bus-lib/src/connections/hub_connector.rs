@@ -1,22 +1,46 @@
-use std::time::Duration;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use anyhow::Result;
 use async_trait::async_trait;
 use karo_common_rpc::{rpc_connector::RpcConnector, rpc_sender::RpcSender};
 use log::*;
-use tokio::{net::UnixStream, time::sleep};
+use tokio::{net::UnixStream, sync::broadcast::Sender as BroadcastSender, time::sleep};
 
 use karo_common_messages::{Message, Response};
 
+use crate::events::BusEvent;
+
 /// Peer connector to request peer connection from the hub
 pub struct HubConnector {
     /// Peer name
     service_name: String,
+    /// Sender to notify the bus about disconnections and reconnections
+    events_tx: BroadcastSender<BusEvent>,
+    /// If the service has already registered at the hub at least once
+    registered: AtomicBool,
+    /// If the service is registered at the hub now. Shared with the bus, which
+    /// reports hub shutdowns
+    connected: Arc<AtomicBool>,
 }
 
 impl HubConnector {
-    pub fn new(service_name: String) -> Self {
-        Self { service_name }
+    pub fn new(
+        service_name: String,
+        events_tx: BroadcastSender<BusEvent>,
+        connected: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            service_name,
+            events_tx,
+            registered: false.into(),
+            connected,
+        }
     }
 }
 
@@ -25,6 +49,16 @@ impl RpcConnector for HubConnector {
     async fn connect(&self) -> Result<UnixStream> {
         info!("Connecting to a hub socket");
 
+        // Reconnecting without a shutdown message from the hub means the connection is lost
+        if self.connected.swap(false, Ordering::SeqCst) {
+            warn!("Hub connection lost");
+
+            // No one may listen for the events
+            let _ = self
+                .events_tx
+                .send(BusEvent::HubDisconnected("Connection lost".into()));
+        }
+
         loop {
             match UnixStream::connect(karo_bus_common::get_hub_socket_path()).await {
                 Ok(socket) => return Ok(socket),
@@ -53,7 +87,19 @@ impl RpcConnector for HubConnector {
         match sender.call(&message).await?.body() {
             // Failed to register the service
             Message::Response(Response::Error(error)) => Err(error.into()),
-            _ => Ok(()),
+            _ => {
+                self.connected.store(true, Ordering::SeqCst);
+
+                // Every registration after the first one is a reconnection
+                if self.registered.swap(true, Ordering::SeqCst) {
+                    info!("Service `{}` reconnected to the hub", self_name);
+
+                    // No one may listen for the events
+                    let _ = self.events_tx.send(BusEvent::HubReconnected);
+                }
+
+                Ok(())
+            }
         }
     }
 }
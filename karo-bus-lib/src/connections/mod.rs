@@ -1,4 +1,8 @@
+pub mod call_stream;
+pub mod fd_channel;
 pub mod hub;
 mod hub_connector;
 pub mod peer;
 mod peer_connector;
+pub mod peer_object;
+pub mod subscription;
@@ -1,30 +1,65 @@
 use std::{
-    fmt::Debug,
-    sync::{atomic::AtomicBool, Arc},
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{Context, Result};
-use bson::Bson;
-use karo_bus_common::messages::Message;
+use bson::{Bson, RawDocument, RawDocumentBuf};
 use log::*;
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::{
     net::UnixStream,
     sync::{
-        broadcast::Receiver as BroadcastReceiver,
+        broadcast::error::RecvError,
         mpsc::{self, Receiver, Sender},
+        watch::{self, Receiver as WatchReceiver},
+        OnceCell, Semaphore,
     },
+    time,
 };
-use tokio_stream::{Stream, StreamExt};
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
 
-use karo_common_messages::{Error, Message as MessageBody, Response};
+use karo_bus_common::{
+    errors::Error,
+    filter::Filter,
+    inspect_data::{InspectData, INSPECT_METHOD},
+    messages::{IntoMessage, Message, MessageBody, PeerCredentials, Response},
+    object_path,
+};
 use karo_common_rpc::{
     rpc_connection::RpcConnection, rpc_sender::RpcSender, Message as MessageHandle,
 };
 
-use crate::monitor::Monitor;
+use crate::{
+    channel::{
+        start_channel_task, ChannelGuard, ChannelParts, ChannelReceiver, ChannelSender,
+        ChannelSide, DEFAULT_CHANNEL_WINDOW,
+    },
+    endpoints::{
+        context::CancellationToken,
+        signal::{Emission, LagPolicy, SignalReceiver},
+        stream::DEFAULT_STREAM_WINDOW,
+    },
+    errors::MethodError,
+    fd,
+    monitor::Monitor,
+};
+
+use super::{
+    call_stream::{CallStream, MessageStream},
+    fd_channel::FdChannel,
+    peer_connector::PeerConnector,
+    peer_object::PeerObject,
+    subscription::{Subscription, SubscriptionEvent},
+};
 
-use super::peer_connector::PeerConnector;
+/// Number of subscription messages buffered for a slow subscriber
+const SUBSCRIPTION_BUFFER: usize = 16;
 
 /// A command from outside into the loop
 enum CommandType {
@@ -47,30 +82,88 @@ pub struct Peer {
     peer_sender: RpcSender,
     /// Sender to shutdown peer connection or set monitor
     command_tx: Sender<CommandType>,
+    /// Peer process credentials provided by the hub if available
+    peer_credentials: Option<PeerCredentials>,
+    /// Next outgoing call seq. Used to cancel calls
+    call_seq: Arc<AtomicU64>,
+    /// Side channel to pass descriptors embedded into messages
+    fd_channel: FdChannel,
+    /// Peer inspection data by object path. Fetched once per object to check
+    /// if used endpoints are deprecated
+    inspections: Arc<Mutex<HashMap<String, Arc<OnceCell<Option<InspectData>>>>>>,
+    /// Endpoints, which were already checked for deprecation
+    checked_endpoints: Arc<Mutex<HashSet<(EndpointKind, String)>>>,
+    /// Changes every time the connection to the peer is re-established
+    reconnects: WatchReceiver<()>,
+}
+
+/// Kind of a peer endpoint used by this side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum EndpointKind {
+    Method,
+    Signal,
+    State,
+}
+
+impl Display for EndpointKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Method => write!(f, "Method"),
+            Self::Signal => write!(f, "Signal"),
+            Self::State => write!(f, "State"),
+        }
+    }
+}
+
+/// Sends cancellation request to the peer if a call is dropped before receiving a response
+struct CancelGuard {
+    peer_sender: RpcSender,
+    caller_name: String,
+    call_seq: u64,
+    done: bool,
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+
+        let message = MessageBody::Cancel {
+            caller_name: self.caller_name.clone(),
+            seq: self.call_seq,
+        };
+        let peer_sender = self.peer_sender.clone();
+
+        tokio::spawn(async move {
+            let _ = peer_sender.send(bson::to_bson(&message).unwrap()).await;
+        });
+    }
 }
 
 impl Peer {
     /// Create new service handle and start tokio task to handle incoming messages from the peer
-    /// *incoming_stream* If passed, this is an incoming connection, if None, peer handle should
+    /// *incoming_stream* Connection stream received from the hub. If None, peer handle should
     ///     connect itself
     /// *hub_writer* Sends a message directrly to the hub. Used for reconnection
+    /// *peer_credentials* Peer process credentials provided by the hub
+    /// *requested* If we requested the connection. Decides which side sets up descriptor passing.
+    ///     Only connections we requested are re-established if lost
     pub(crate) async fn new(
         service_name: String,
         peer_service_name: String,
         incoming_stream: Option<UnixStream>,
-        endpoints_tx: Sender<MessageHandle>,
+        endpoints_tx: Sender<(String, MessageHandle)>,
         hub_writer: RpcSender,
+        peer_credentials: Option<PeerCredentials>,
+        requested: bool,
     ) -> Result<Self> {
-        // If we have an incoming stream, we don't reconnect
-        let outgoing: Arc<AtomicBool> = Arc::new(if incoming_stream.is_none() {
-            true.into()
-        } else {
-            false.into()
-        });
+        // If the peer requested the connection, it's up to the peer to reconnect
+        let outgoing = Arc::new(AtomicBool::new(requested || incoming_stream.is_none()));
 
         info!(
             "Registered new {} connection from {}",
-            if incoming_stream.is_none() {
+            if outgoing.load(Ordering::Acquire) {
                 "outgoing"
             } else {
                 "incoming"
@@ -79,13 +172,18 @@ impl Peer {
         );
 
         let (command_tx, command_rx) = mpsc::channel(1);
+        let fd_channel = FdChannel::new();
+        let (reconnects_tx, reconnects) = watch::channel(());
 
         // Peer connector, which will connect to the peer if this is and outgoing connection
         let connector = Box::new(PeerConnector::new(
             peer_service_name.clone(),
-            hub_writer,
+            hub_writer.clone(),
             incoming_stream,
             outgoing.clone(),
+            fd_channel.clone(),
+            requested,
+            reconnects_tx,
         ));
 
         // Rpc connection
@@ -97,8 +195,10 @@ impl Peer {
             rpc_connection,
             command_rx,
             endpoints_tx,
+            hub_writer,
             service_name.clone(),
             peer_service_name.clone(),
+            outgoing.clone(),
         );
 
         Ok(Self {
@@ -107,15 +207,23 @@ impl Peer {
             outgoing,
             peer_sender,
             command_tx,
+            peer_credentials,
+            call_seq: Arc::new(AtomicU64::new(1)),
+            fd_channel,
+            inspections: Arc::new(Mutex::new(HashMap::new())),
+            checked_endpoints: Arc::new(Mutex::new(HashSet::new())),
+            reconnects,
         })
     }
 
     async fn start_task(
         mut rpc_connection: RpcConnection,
         mut shutdown_rx: Receiver<CommandType>,
-        endpoints_tx: Sender<MessageHandle>,
+        endpoints_tx: Sender<(String, MessageHandle)>,
+        hub_writer: RpcSender,
         service_name: String,
         peer_service_name: String,
+        outgoing: Arc<AtomicBool>,
     ) {
         tokio::spawn(async move {
             loop {
@@ -129,18 +237,29 @@ impl Peer {
                         match incoming_message {
                             Ok(message) => {
                                 if matches!(message.body(), MessageBody::Response(Response::Shutdown(_))) {
+                                    Self::notify_hub_disconnection(&hub_writer, &peer_service_name).await;
+
+                                    // Connections we requested are re-established by the connector
+                                    if outgoing.load(Ordering::Acquire) {
+                                        warn!("Peer connection closed. Reconnecting");
+                                        continue;
+                                    }
+
                                     warn!("Peer connection closed. Shutting him down");
                                     return;
                                 } else {
-                                    // This is an incoming message. Send it to the interfaces
-                                    if endpoints_tx.send(message).await.is_err() {
+                                    // This is an incoming message. Send it to the interfaces.
+                                    // The caller is the connection peer, whatever name the message claims
+                                    if endpoints_tx.send((peer_service_name.clone(), message)).await.is_err() {
                                         warn!("Peer connection closed. Shutting down");
+                                        Self::notify_hub_disconnection(&hub_writer, &peer_service_name).await;
                                         return;
                                     }
                                 }
                             },
                             Err(err) => {
                                 warn!("Peer connection closed. Shutting down");
+                                Self::notify_hub_disconnection(&hub_writer, &peer_service_name).await;
                                 return;
                             }
                         }
@@ -149,8 +268,9 @@ impl Peer {
                         match message {
                             CommandType::Monitor(monitor) => rpc_connection.set_monitor(Box::new(monitor)),
                             CommandType::Shutdown => {
-                                let message: MessageBody = Response::Shutdown("Shutdown".into()).into();
-                                rpc_connection.sender().send(message.into());
+                                let message = MessageBody::Response(Response::Shutdown("Shutdown".into()));
+                                let _ = rpc_connection.sender().send(bson::to_bson(&message).unwrap()).await;
+                                Self::notify_hub_disconnection(&hub_writer, &peer_service_name).await;
                                 return;
                             }
                         }
@@ -160,28 +280,229 @@ impl Peer {
         });
     }
 
+    /// Notify the hub the peer connection is closed, so it can update connection topology
+    async fn notify_hub_disconnection(hub_writer: &RpcSender, peer_service_name: &str) {
+        let message = Message::new_peer_disconnected(peer_service_name.into());
+
+        if hub_writer
+            .send(bson::to_bson(&message).unwrap())
+            .await
+            .is_err()
+        {
+            debug!(
+                "Failed to notify the hub about `{}` disconnection",
+                peer_service_name
+            );
+        }
+    }
+
     pub fn name(&self) -> &String {
         &self.service_name
     }
 
+    /// Peer process credentials provided by the hub if available
+    pub fn credentials(&self) -> Option<PeerCredentials> {
+        self.peer_credentials
+    }
+
+    /// Remote object at the **path**, e.g. `/devices/eth0`. Calls to unknown objects
+    /// fail with [Error::NotRegistered]. Use [PeerObject::inspect] to list child objects
+    pub fn object(&mut self, path: &str) -> PeerObject<'_> {
+        PeerObject::new(self, path)
+    }
+
+    /// Sender into the peer connection
+    pub(crate) fn sender(&self) -> RpcSender {
+        self.peer_sender.clone()
+    }
+
+    /// Descriptor side channel of the peer connection
+    pub(crate) fn fd_channel(&self) -> FdChannel {
+        self.fd_channel.clone()
+    }
+
     /// Remote method call\
     /// **P** is an argument type. Should be a serializable structure.\
-    /// **R** is return type. Should be a deserializable structure
+    /// **R** is return type. Should be a deserializable structure\
+    /// If the method handler fails, returns [MethodError]
     pub async fn call<P: Serialize, R: DeserializeOwned>(
         &mut self,
         method_name: &str,
         params: &P,
     ) -> Result<R> {
-        let message =
-            MessageBody::new_call(self.peer_service_name.clone(), method_name.into(), params);
+        self.call_with_headers(method_name, params, HashMap::new())
+            .await
+    }
+
+    /// Remote method call with call metadata. The peer method handler receives
+    /// **headers** in its [crate::CallContext]. See [Peer::call]
+    pub async fn call_with_headers<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        params: &P,
+        headers: HashMap<String, String>,
+    ) -> Result<R> {
+        self.perform_call(method_name, params, headers, None).await
+    }
+
+    /// Remote method call, which fails with [Error::Timeout] if the peer doesn't reply
+    /// within the **timeout**. The deadline is passed to the peer, so the method
+    /// handler may stop early. See [Peer::call]
+    pub async fn call_with_timeout<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        params: &P,
+        timeout: Duration,
+    ) -> Result<R> {
+        self.perform_call(method_name, params, HashMap::new(), Some(timeout))
+            .await
+    }
+
+    /// Remote method call with a raw BSON document. The document is sent as the call
+    /// parameters, so the peer may handle it with a typed method or with
+    /// [crate::Bus::register_raw_method]. See [Peer::call]
+    pub async fn call_raw(
+        &mut self,
+        method_name: &str,
+        params: &RawDocument,
+    ) -> Result<RawDocumentBuf> {
+        self.perform_call(method_name, &params, HashMap::new(), None)
+            .await
+    }
+
+    /// Warn once if the peer marks the endpoint deprecated. The peer inspection is fetched
+    /// in background on the first endpoint use, so the calls are not delayed
+    fn check_deprecation(&self, kind: EndpointKind, endpoint_name: &str) {
+        let (path, local_name) = object_path::split(endpoint_name);
+
+        if local_name == INSPECT_METHOD
+            || !self
+                .checked_endpoints
+                .lock()
+                .unwrap()
+                .insert((kind, endpoint_name.into()))
+        {
+            return;
+        }
+
+        let inspection = self
+            .inspections
+            .lock()
+            .unwrap()
+            .entry(path.into())
+            .or_default()
+            .clone();
+
+        // Not using a peer clone here. Dropping the clone would shut the connection down
+        let peer_sender = self.peer_sender.clone();
+        let inspect_call = MessageBody::MethodCall {
+            caller_name: self.service_name.clone(),
+            method_name: object_path::qualify(path, INSPECT_METHOD),
+            params: Bson::Null,
+            deadline: None,
+            headers: HashMap::new(),
+            call_seq: self.call_seq.fetch_add(1, Ordering::Relaxed),
+        };
+
+        let peer_service_name = self.peer_service_name.clone();
+        let endpoint_name = endpoint_name.to_owned();
+        let local_name = local_name.to_owned();
+
+        tokio::spawn(async move {
+            let inspect_data = inspection
+                .get_or_init(|| async {
+                    match peer_sender
+                        .call(&inspect_call)
+                        .await
+                        .ok()?
+                        .body::<MessageBody>()
+                    {
+                        MessageBody::Response(Response::Return(data)) => {
+                            bson::from_bson::<InspectData>(data).ok()
+                        }
+                        _ => None,
+                    }
+                })
+                .await;
+
+            let metadata = inspect_data.as_ref().and_then(|inspect_data| match kind {
+                EndpointKind::Method => inspect_data.method_metadata.get(&local_name),
+                EndpointKind::Signal => inspect_data.signal_metadata.get(&local_name),
+                EndpointKind::State => inspect_data.state_metadata.get(&local_name),
+            });
+
+            if let Some(deprecation) = metadata.and_then(|metadata| metadata.deprecated.as_ref()) {
+                warn!(
+                    "{} `{}::{}` is {}",
+                    kind, peer_service_name, endpoint_name, deprecation
+                );
+            }
+        });
+    }
+
+    /// Perform remote method call. If the call is dropped or timed out before
+    /// receiving a response, the peer gets a cancellation request
+    async fn perform_call<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        params: &P,
+        headers: HashMap<String, String>,
+        timeout: Option<Duration>,
+    ) -> Result<R> {
+        self.check_deprecation(EndpointKind::Method, method_name);
+
+        let call_seq = self.call_seq.fetch_add(1, Ordering::Relaxed);
+
+        let deadline = timeout.map(|timeout| {
+            (SystemTime::now() + timeout)
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64
+        });
+
+        // Descriptors embedded into the parameters are closed when the call is sent
+        let (params, fds) = fd::to_bson(params)?;
+
+        let message = MessageBody::MethodCall {
+            caller_name: self.service_name.clone(),
+            method_name: method_name.into(),
+            params: self.fd_channel.send(params, &fds).await?,
+            deadline,
+            headers,
+            call_seq,
+        };
+
+        let mut cancel_guard = CancelGuard {
+            peer_sender: self.peer_sender.clone(),
+            caller_name: self.service_name.clone(),
+            call_seq,
+            done: false,
+        };
 
         // Send method call request
-        let response: MessageBody = self.peer_sender.call(&message).await?.body();
+        let response = match timeout {
+            Some(timeout) => match time::timeout(timeout, self.peer_sender.call(&message)).await {
+                Ok(response) => response?,
+                Err(_) => {
+                    warn!(
+                        "Call to `{}::{}` timed out",
+                        self.peer_service_name, method_name
+                    );
+                    return Err(Error::Timeout.into());
+                }
+            },
+            None => self.peer_sender.call(&message).await?,
+        };
+
+        cancel_guard.done = true;
+        let response: MessageBody = response.body();
 
         match response {
             // Succesfully performed remote method call
             MessageBody::Response(Response::Return(data)) => {
-                match bson::from_bson::<R>(data.clone()) {
+                let (data, fds) = self.fd_channel.receive(data).await?;
+
+                match fd::from_bson::<R>(data, fds) {
                     Ok(data) => Ok(data),
                     Err(err) => {
                         error!("Can't deserialize method response: {}", err.to_string());
@@ -189,6 +510,14 @@ impl Peer {
                     }
                 }
             }
+            // Method handler itself returned an error
+            MessageBody::Response(Response::MethodError(data)) => {
+                debug!(
+                    "Call to `{}::{}` returned an error: {}",
+                    self.peer_service_name, method_name, data
+                );
+                Err(MethodError::new(data).into())
+            }
             // Got an error from the peer
             MessageBody::Response(Response::Error(err)) => {
                 warn!(
@@ -207,33 +536,242 @@ impl Peer {
         }
     }
 
-    /// Remote signal subscription\
+    /// Remote streaming method call. The peer sends up to [DEFAULT_STREAM_WINDOW]
+    /// items ahead of the consumer. Dropping the stream cancels the call.\
+    /// **P** is an argument type. Should be a serializable structure.\
+    /// **R** is stream item type. Should be a deserializable structure
+    pub async fn call_stream<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        params: &P,
+    ) -> Result<CallStream<R>> {
+        let call_seq = self.call_seq.fetch_add(1, Ordering::Relaxed);
+        let window = DEFAULT_STREAM_WINDOW;
+
+        let message = MessageBody::StreamCall {
+            caller_name: self.service_name.clone(),
+            method_name: method_name.into(),
+            params: bson::to_bson(params)?,
+            call_seq,
+            window,
+        };
+
+        let mut messages = self.peer_sender.subscribe(&message).await?;
+
+        match messages
+            .next()
+            .await
+            .context("Stream call unexpectedly closed")?
+            .body()
+        {
+            // Callee accepted the call. Items will follow
+            MessageBody::Response(Response::Ok) => {
+                debug!(
+                    "Succesfully started streaming call `{}::{}`",
+                    self.peer_service_name, method_name
+                );
+
+                Ok(CallStream::new(
+                    Box::pin(messages),
+                    self.peer_sender.clone(),
+                    self.service_name.clone(),
+                    call_seq,
+                    window,
+                ))
+            }
+            // Got an error from the peer
+            MessageBody::Response(Response::Error(err)) => {
+                warn!(
+                    "Failed to perform a streaming call to `{}::{}`: {}",
+                    self.peer_service_name,
+                    method_name,
+                    err.to_string()
+                );
+                Err(err.into())
+            }
+            // Invalid protocol
+            r => {
+                error!("Invalid Ok response for a streaming call: {:?}", r);
+                Err(Error::InvalidMessage.into())
+            }
+        }
+    }
+
+    /// Open duplex channel to the peer. Each side sends up to [DEFAULT_CHANNEL_WINDOW]
+    /// items ahead of the consumer. Dropping both halves cancels the channel.\
+    /// **I** is init value type. Should be a serializable structure.\
+    /// **S** is outgoing item type. Should be a serializable structure.\
+    /// **R** is incoming item type. Should be a deserializable structure
+    pub async fn open_channel<I: Serialize, S: Serialize, R: DeserializeOwned>(
+        &mut self,
+        channel_name: &str,
+        init: &I,
+    ) -> Result<(ChannelSender<S>, ChannelReceiver<R>)> {
+        let call_seq = self.call_seq.fetch_add(1, Ordering::Relaxed);
+        let window = DEFAULT_CHANNEL_WINDOW;
+
+        let message = MessageBody::OpenChannel {
+            caller_name: self.service_name.clone(),
+            channel_name: channel_name.into(),
+            init: bson::to_bson(init)?,
+            call_seq,
+            window,
+        };
+
+        let mut messages = self.peer_sender.subscribe(&message).await?;
+
+        match messages
+            .next()
+            .await
+            .context("Channel unexpectedly closed")?
+            .body()
+        {
+            // Handler accepted the channel
+            MessageBody::Response(Response::Ok) => {
+                debug!(
+                    "Succesfully opened channel `{}::{}`",
+                    self.peer_service_name, channel_name
+                );
+
+                let side = ChannelSide::Caller {
+                    caller_name: self.service_name.clone(),
+                    call_seq,
+                };
+                let (data_tx, data_rx) = mpsc::channel(window as usize);
+                let send_credits = Arc::new(Semaphore::new(window as usize));
+                let guard = ChannelGuard::new(
+                    self.peer_sender.clone(),
+                    self.service_name.clone(),
+                    call_seq,
+                );
+
+                start_channel_task(
+                    Box::pin(messages),
+                    side.clone(),
+                    self.peer_sender.clone(),
+                    data_tx,
+                    send_credits.clone(),
+                    guard.finished.clone(),
+                    guard.cancellation.clone(),
+                );
+
+                Ok(ChannelParts {
+                    side,
+                    peer_sender: self.peer_sender.clone(),
+                    send_credits,
+                    data_rx,
+                    window,
+                    guard: Some(Arc::new(guard)),
+                }
+                .split())
+            }
+            // Got an error from the peer
+            MessageBody::Response(Response::Error(err)) => {
+                warn!(
+                    "Failed to open channel `{}::{}`: {}",
+                    self.peer_service_name,
+                    channel_name,
+                    err.to_string()
+                );
+                Err(err.into())
+            }
+            // Invalid protocol
+            r => {
+                error!("Invalid Ok response for a channel: {:?}", r);
+                Err(Error::InvalidMessage.into())
+            }
+        }
+    }
+
+    /// Remote signal subscription. The subscription is renewed if the peer
+    /// connection is re-established. Use [Peer::subscribe_events] to know when
+    /// emissions could have been missed\
     /// **T** is the signal type. Should be a deserializable structure
     pub async fn subscribe<T>(&mut self, signal_name: &str) -> Result<impl Stream<Item = T>>
     where
         T: DeserializeOwned + Send,
     {
-        let message = MessageBody::new_subscription(self.service_name.clone(), signal_name.into());
+        Ok(self
+            .subscribe_events(signal_name)
+            .await?
+            .filter_map(SubscriptionEvent::into_value))
+    }
 
-        let mut subscription_stream = self.peer_sender.subscribe(&message).await?;
+    /// Remote signal subscription, which reports peer reconnections with
+    /// [SubscriptionEvent::Resubscribed]\
+    /// **T** is the signal type. Should be a deserializable structure
+    pub async fn subscribe_events<T>(
+        &mut self,
+        signal_name: &str,
+    ) -> Result<impl Stream<Item = SubscriptionEvent<T>>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        self.subscription_events(signal_name, None).await
+    }
 
-        let subscription_response: MessageBody = subscription_stream
-            .next()
-            .await
-            .context("Subscription stream unexpectedly closed")?
-            .body();
+    /// Remote signal subscription, which receives only emissions matching the **filter**.
+    /// The peer evaluates the filter before sending. See [Peer::subscribe]\
+    /// **T** is the signal type. Should be a deserializable structure
+    pub async fn subscribe_filtered<T>(
+        &mut self,
+        signal_name: &str,
+        filter: Filter,
+    ) -> Result<impl Stream<Item = T>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        Ok(self
+            .subscription_events(signal_name, Some(filter))
+            .await?
+            .filter_map(SubscriptionEvent::into_value))
+    }
 
-        // Match first message
-        match subscription_response {
-            // Succesfully performed remote method call
-            MessageBody::Response(Response::Ok) => {
-                debug!("Succesfully subscribed to the signal `{}`", signal_name);
+    /// Subscribe to the signal emissions matching the **filter** if any
+    async fn subscription_events<T>(
+        &mut self,
+        signal_name: &str,
+        filter: Option<Filter>,
+    ) -> Result<impl Stream<Item = SubscriptionEvent<T>>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        self.check_deprecation(EndpointKind::Signal, signal_name);
 
-                Ok(subscription_stream.map_while(|message| {
-                    let bson_body = message.body::<Bson>();
+        let subscription_seq = self.call_seq.fetch_add(1, Ordering::Relaxed);
+        let message = Message::new_subscription(
+            self.service_name.clone(),
+            signal_name.into(),
+            subscription_seq,
+            filter,
+        )
+        .into_body();
 
-                    if let Ok(body) = bson::from_bson(bson_body.clone()) {
-                        Some(body)
+        let events = self.start_subscription(message, signal_name).await?;
+        debug!("Succesfully subscribed to the signal `{}`", signal_name);
+
+        let fd_channel = self.fd_channel.clone();
+
+        // Signals may carry descriptors, which we have to wait for
+        let stream = ReceiverStream::new(events)
+            .then(move |event| {
+                let fd_channel = fd_channel.clone();
+
+                async move {
+                    let message = match event {
+                        SubscriptionEvent::Value(message) => message,
+                        SubscriptionEvent::Resubscribed => {
+                            return Some(SubscriptionEvent::Resubscribed)
+                        }
+                        SubscriptionEvent::Lagged(missed) => {
+                            return Some(SubscriptionEvent::Lagged(missed))
+                        }
+                    };
+
+                    let (bson_body, fds) = fd_channel.receive(message.body::<Bson>()).await.ok()?;
+
+                    if let Ok(body) = fd::from_bson(bson_body.clone(), fds) {
+                        Some(SubscriptionEvent::Value(body))
                     } else {
                         warn!(
                             "Failed to deserialize subscription body from {:?}",
@@ -241,88 +779,299 @@ impl Peer {
                         );
                         None
                     }
-                }))
-            }
-            // Invalid protocol
-            r => {
-                error!("Invalid Ok response for a signal subscription: {:?}", r);
-                Err(Error::InvalidMessage.into())
-            }
-        }
+                }
+            })
+            .map_while(|event| event);
+
+        Ok(Subscription::new(
+            Box::pin(stream),
+            self.peer_sender.clone(),
+            self.service_name.clone(),
+            subscription_seq,
+        ))
     }
 
-    /// Start watching remote state changes\
+    /// Start watching remote state changes. The watch is renewed if the peer
+    /// connection is re-established. Use [Peer::watch_events] to know when
+    /// changes could have been missed\
     /// **T** is the signal type. Should be a deserializable structure\
     /// **Returns** current state value
     pub async fn watch<T>(&mut self, state_name: &str) -> Result<impl Stream<Item = T>>
     where
         T: DeserializeOwned + Send,
     {
-        let message = MessageBody::new_watch(self.service_name.clone(), state_name.into());
+        Ok(self
+            .watch_events(state_name)
+            .await?
+            .filter_map(SubscriptionEvent::into_value))
+    }
+
+    /// Start watching remote state changes, reporting peer reconnections with
+    /// [SubscriptionEvent::Resubscribed]. The current value follows each of them\
+    /// **T** is the signal type. Should be a deserializable structure\
+    /// **Returns** current state value
+    pub async fn watch_events<T>(
+        &mut self,
+        state_name: &str,
+    ) -> Result<impl Stream<Item = SubscriptionEvent<T>>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        self.check_deprecation(EndpointKind::State, state_name);
 
-        let mut subscription_stream = self.peer_sender.subscribe(&message).await?;
+        let subscription_seq = self.call_seq.fetch_add(1, Ordering::Relaxed);
+        let message = Message::new_watch(
+            self.service_name.clone(),
+            state_name.into(),
+            subscription_seq,
+        )
+        .into_body();
 
-        // Match first message
-        match subscription_stream
-            .next()
-            .await
-            .context("Watch stream unexpectedly closed")?
-            .body()
-        {
-            // Succesfully performed remote method call
-            MessageBody::Response(Response::Ok) => {
-                debug!("Succesfully started watching the state `{}`", state_name);
+        let events = self.start_subscription(message, state_name).await?;
+        debug!("Succesfully started watching the state `{}`", state_name);
 
-                Ok(subscription_stream.map_while(|message| {
-                    let bson_body = message.body::<Bson>();
+        let stream = ReceiverStream::new(events).map_while(|event| match event {
+            SubscriptionEvent::Value(message) => {
+                let bson_body = message.body::<Bson>();
 
-                    if let Ok(body) = bson::from_bson(bson_body.clone()) {
-                        Some(body)
-                    } else {
-                        warn!("Failed to deserialize watch body from {:?}", bson_body);
-                        None
+                if let Ok(body) = bson::from_bson(bson_body.clone()) {
+                    Some(SubscriptionEvent::Value(body))
+                } else {
+                    warn!("Failed to deserialize watch body from {:?}", bson_body);
+                    None
+                }
+            }
+            SubscriptionEvent::Resubscribed => Some(SubscriptionEvent::Resubscribed),
+            SubscriptionEvent::Lagged(missed) => Some(SubscriptionEvent::Lagged(missed)),
+        });
+
+        Ok(Subscription::new(
+            Box::pin(stream),
+            self.peer_sender.clone(),
+            self.service_name.clone(),
+            subscription_seq,
+        ))
+    }
+
+    /// Send subscription or watch **message** and start a task forwarding subscription
+    /// messages. The task sends the **message** again every time the peer connection
+    /// is re-established. The subscription seq stays the same, so the publisher replaces
+    /// the old subscription
+    async fn start_subscription(
+        &self,
+        message: MessageBody,
+        endpoint_name: &str,
+    ) -> Result<Receiver<SubscriptionEvent<MessageHandle>>> {
+        let mut reconnects = self.reconnects.clone();
+        reconnects.borrow_and_update();
+
+        let (current_value, mut messages) =
+            Self::request_subscription(&self.peer_sender, &message).await?;
+
+        let (events_tx, events_rx) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        if let Some(current_value) = current_value {
+            let _ = events_tx
+                .send(SubscriptionEvent::Value(current_value))
+                .await;
+        }
+
+        let peer_sender = self.peer_sender.clone();
+        let outgoing = self.outgoing.clone();
+        let endpoint_name = endpoint_name.to_owned();
+
+        tokio::spawn(async move {
+            loop {
+                // Forward messages until the connection is re-established
+                loop {
+                    tokio::select! {
+                        message = messages.next() => match message {
+                            // The publisher ended the subscription
+                            Some(message) if is_stream_end(&message) => return,
+                            Some(message) => {
+                                let event = match lagged_count(&message) {
+                                    Some(missed) => SubscriptionEvent::Lagged(missed),
+                                    None => SubscriptionEvent::Value(message),
+                                };
+
+                                if events_tx.send(event).await.is_err() {
+                                    return;
+                                }
+                            }
+                            // The old connection is gone. Only connections we requested
+                            // are re-established, otherwise end the subscription
+                            None if !outgoing.load(Ordering::Acquire) => return,
+                            // Wait for a new connection
+                            None => messages = Box::pin(tokio_stream::pending::<MessageHandle>()),
+                        },
+                        reconnected = reconnects.changed() => {
+                            // Connector dropped. The peer won't reconnect anymore
+                            if reconnected.is_err() {
+                                return;
+                            }
+                            break;
+                        },
+                        _ = events_tx.closed() => return,
+                    }
+                }
+
+                debug!(
+                    "Peer reconnected. Renewing subscription to `{}`",
+                    endpoint_name
+                );
+
+                match Self::request_subscription(&peer_sender, &message).await {
+                    Ok((current_value, renewed)) => {
+                        messages = renewed;
+
+                        if events_tx
+                            .send(SubscriptionEvent::Resubscribed)
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+
+                        if let Some(current_value) = current_value {
+                            if events_tx
+                                .send(SubscriptionEvent::Value(current_value))
+                                .await
+                                .is_err()
+                            {
+                                return;
+                            }
+                        }
                     }
-                }))
+                    Err(err) => {
+                        warn!(
+                            "Failed to renew subscription to `{}`: {}",
+                            endpoint_name, err
+                        );
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(events_rx)
+    }
+
+    /// Send subscription or watch **message**.\
+    /// **Returns** current state value if the publisher sent one, and subscription messages
+    async fn request_subscription(
+        peer_sender: &RpcSender,
+        message: &MessageBody,
+    ) -> Result<(Option<MessageHandle>, MessageStream)> {
+        let mut messages = peer_sender.subscribe(message).await?;
+
+        let response = messages
+            .next()
+            .await
+            .context("Subscription stream unexpectedly closed")?;
+
+        // Match first message
+        match response.body() {
+            // Succesfully subscribed to a signal
+            MessageBody::Response(Response::Ok) => Ok((None, Box::pin(messages))),
+            // State watches start with the current value
+            MessageBody::Response(Response::StateChanged(_)) => {
+                Ok((Some(response), Box::pin(messages)))
             }
             // Invalid protocol
             r => {
-                error!("Invalid Ok response for a signal subscription: {:?}", r);
+                error!("Invalid Ok response for a subscription: {:?}", r);
                 Err(Error::InvalidMessage.into())
             }
         }
     }
 
     /// Start subscription task, which polls signal Receiver and sends peer message
-    /// if emited
+    /// if emited. The task stops if the **cancellation** token is cancelled, and
+    /// cancels the token itself when finished
     pub(crate) fn start_signal_sending_task(
         &self,
-        mut signal_receiver: BroadcastReceiver<Message>,
+        mut signal_receiver: SignalReceiver,
         seq: u64,
+        cancellation: CancellationToken,
     ) {
+        let peer_sender = self.peer_sender.clone();
+        let fd_channel = self.fd_channel.clone();
+
         tokio::spawn(async move {
-            loop {
-                // Wait for signal emission
-                match signal_receiver.recv().await {
-                    Ok(mut message) => {
-                        // Replace seq with subscription seq
-                        message.update_seq(seq);
-
-                        // Call self task to send signal message
-                        if let Err(_) = self
-                            .peer_sender
-                            .send(bson::to_bson(&message).unwrap())
-                            .await
-                        {
-                            warn!("Failed to send signal to a subscriber. Probably closed. Removing subscriber");
+            let sending = async {
+                loop {
+                    // Wait for signal emission
+                    match signal_receiver.recv().await {
+                        Ok(Emission { mut message, fds }) => {
+                            // Replace seq with subscription seq
+                            message.update_seq(seq);
+
+                            // The signal or state is unregistered. Forward the end and stop
+                            let finished = matches!(
+                                message.body(),
+                                MessageBody::Response(Response::StreamEnd)
+                            );
+
+                            // Pass descriptors embedded into the signal
+                            let message = match fd_channel
+                                .send(bson::to_bson(&message).unwrap(), &fds)
+                                .await
+                            {
+                                Ok(message) => message,
+                                Err(err) => {
+                                    warn!(
+                                        "Failed to send signal descriptors to a subscriber: {}",
+                                        err
+                                    );
+                                    continue;
+                                }
+                            };
+
+                            // Call self task to send signal message
+                            if let Err(_) = peer_sender.send(message).await {
+                                warn!("Failed to send signal to a subscriber. Probably closed. Removing subscriber");
+                                return;
+                            }
+
+                            if finished {
+                                return;
+                            }
+                        }
+                        // The subscriber fell behind. Report the lag and apply the policy
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!("Subscriber {} lagged by {} emissions", seq, missed);
+
+                            let message = Response::Lagged(missed).into_message(seq);
+                            if peer_sender
+                                .send(bson::to_bson(&message).unwrap())
+                                .await
+                                .is_err()
+                            {
+                                return;
+                            }
+
+                            if signal_receiver.lag_policy == LagPolicy::Disconnect {
+                                let message = Response::StreamEnd.into_message(seq);
+                                let _ = peer_sender.send(bson::to_bson(&message).unwrap()).await;
+                                return;
+                            }
+                        }
+                        Err(err) => {
+                            error!("Signal receiver error: {:?}", err);
                             return;
                         }
                     }
-                    Err(err) => {
-                        error!("Signal receiver error: {:?}", err);
-                        return;
-                    }
+                }
+            };
+
+            tokio::select! {
+                _ = sending => {},
+                _ = cancellation.cancelled() => {
+                    debug!("Subscriber unsubscribed from {}", seq);
                 }
             }
+
+            // Mark the subscription finished
+            cancellation.cancel();
         });
     }
 
@@ -350,6 +1099,23 @@ impl Peer {
     }
 }
 
+/// Check if the publisher ended the subscription, because the signal or state
+/// is unregistered
+fn is_stream_end(message: &MessageHandle) -> bool {
+    matches!(
+        message.body::<MessageBody>(),
+        MessageBody::Response(Response::StreamEnd)
+    )
+}
+
+/// Number of emissions the publisher dropped, because we fell behind
+fn lagged_count(message: &MessageHandle) -> Option<u64> {
+    match message.body::<MessageBody>() {
+        MessageBody::Response(Response::Lagged(missed)) => Some(missed),
+        _ => None,
+    }
+}
+
 impl Drop for Peer {
     fn drop(&mut self) {
         trace!("Peer `{}` connection dropped", self.peer_service_name);
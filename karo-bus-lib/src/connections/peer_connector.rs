@@ -5,12 +5,14 @@ use std::sync::{
 
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
-use karo_common_messages::{Message, Response};
 use log::*;
-use tokio::net::UnixStream;
+use tokio::{net::UnixStream, sync::watch::Sender as WatchSender};
 
+use karo_bus_common::messages::{Message, MessageBody, ServiceMessage};
 use karo_common_rpc::{rpc_connector::RpcConnector, rpc_sender::RpcSender};
 
+use super::fd_channel::FdChannel;
+
 /// Peer connector to request peer connection from the hub
 pub struct PeerConnector {
     /// Peer name
@@ -24,6 +26,15 @@ pub struct PeerConnector {
     /// This vaue can change, because we can start subscribing to a service, which
     /// previously initiated connection.
     reconnect: Arc<AtomicBool>,
+    /// Descriptor side channel, which is set up on every connection
+    fd_channel: FdChannel,
+    /// If we requested the incoming stream. The requester creates descriptor side channel
+    requested: bool,
+    /// Notified every time we reconnect to the peer, after the new connection is set up.
+    /// Subscriptions renew themselves on reconnection
+    reconnects: WatchSender<()>,
+    /// If we've already connected to the peer at least once
+    connected: AtomicBool,
 }
 
 impl PeerConnector {
@@ -32,12 +43,19 @@ impl PeerConnector {
         hub_writer: RpcSender,
         incoming_stream: Option<UnixStream>,
         reconnect: Arc<AtomicBool>,
+        fd_channel: FdChannel,
+        requested: bool,
+        reconnects: WatchSender<()>,
     ) -> Self {
         Self {
             peer_name,
             hub_writer,
             incoming_stream,
             reconnect,
+            fd_channel,
+            requested,
+            reconnects,
+            connected: false.into(),
         }
     }
 }
@@ -51,10 +69,17 @@ impl RpcConnector for PeerConnector {
                 "Creating RPC conenction from an incoming peer stream `{}`",
                 self.peer_name
             );
+
+            if self.requested {
+                self.fd_channel.initiate(&incoming_stream).await?;
+            } else {
+                self.fd_channel.accept(&incoming_stream).await?;
+            }
+
             return Ok(incoming_stream);
         }
 
-        if !self.reconnect.load(Ordering::Release) {
+        if !self.reconnect.load(Ordering::Acquire) {
             return Err(anyhow!("Refused to reconnect incoming connections"));
         }
 
@@ -74,14 +99,15 @@ impl RpcConnector for PeerConnector {
                 self.peer_name
             ))?;
 
-        let message_body: Message = message.body();
-
-        match message_body {
+        match message.body() {
             // This is the message we should receive if succesfully reconnected
-            Message::Response(Response::Ok) => {
+            MessageBody::ServiceMessage(ServiceMessage::IncomingPeerFd { .. }) => {
                 // Check if we've receive peer fd
                 if let Some(stream) = message.take_fd() {
                     info!("Succesfully reconnected to `{}`", self.peer_name);
+
+                    self.fd_channel.initiate(&stream).await?;
+
                     return Ok(stream);
                 } else {
                     error!("Hub didn't send us a descriptor after Ok response");
@@ -91,8 +117,19 @@ impl RpcConnector for PeerConnector {
             m => {
                 // If failed to resubscribe, try to reconnect again
                 error!("Invalid reconnection response: {:?}", m);
-                return Err(anyhow!("Invalid message from the hub: {}", m));
+                return Err(anyhow!("Invalid message from the hub: {:?}", m));
             }
         }
     }
+
+    /// Called when the connection stream is set up. Subscriptions renew themselves
+    /// over the new connection
+    async fn on_connected(&self, _sender: &mut RpcSender) -> Result<()> {
+        // Every connection after the first one is a reconnection
+        if self.connected.swap(true, Ordering::SeqCst) {
+            let _ = self.reconnects.send(());
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,156 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio_stream::Stream;
+
+use karo_bus_common::{
+    filter::Filter,
+    inspect_data::{InspectData, INSPECT_METHOD},
+    object_path,
+};
+
+use crate::channel::{ChannelReceiver, ChannelSender};
+
+use super::{call_stream::CallStream, peer::Peer, subscription::SubscriptionEvent};
+
+/// Remote object handle. Addresses endpoints of the peer object at an object path.
+/// Create with [Peer::object]
+pub struct PeerObject<'a> {
+    peer: &'a mut Peer,
+    /// Object path
+    path: String,
+}
+
+impl<'a> PeerObject<'a> {
+    pub(crate) fn new(peer: &'a mut Peer, path: &str) -> Self {
+        Self {
+            peer,
+            path: path.into(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Remote object method call. See [Peer::call]
+    pub async fn call<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        params: &P,
+    ) -> Result<R> {
+        let method_name = self.qualify(method_name);
+        self.peer.call(&method_name, params).await
+    }
+
+    /// Remote object method call with call metadata. See [Peer::call_with_headers]
+    pub async fn call_with_headers<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        params: &P,
+        headers: HashMap<String, String>,
+    ) -> Result<R> {
+        let method_name = self.qualify(method_name);
+        self.peer
+            .call_with_headers(&method_name, params, headers)
+            .await
+    }
+
+    /// Remote object method call with a deadline. See [Peer::call_with_timeout]
+    pub async fn call_with_timeout<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        params: &P,
+        timeout: Duration,
+    ) -> Result<R> {
+        let method_name = self.qualify(method_name);
+        self.peer
+            .call_with_timeout(&method_name, params, timeout)
+            .await
+    }
+
+    /// Remote object streaming method call. See [Peer::call_stream]
+    pub async fn call_stream<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        params: &P,
+    ) -> Result<CallStream<R>> {
+        let method_name = self.qualify(method_name);
+        self.peer.call_stream(&method_name, params).await
+    }
+
+    /// Open duplex channel to the object. See [Peer::open_channel]
+    pub async fn open_channel<I: Serialize, S: Serialize, R: DeserializeOwned>(
+        &mut self,
+        channel_name: &str,
+        init: &I,
+    ) -> Result<(ChannelSender<S>, ChannelReceiver<R>)> {
+        let channel_name = self.qualify(channel_name);
+        self.peer.open_channel(&channel_name, init).await
+    }
+
+    /// Remote object signal subscription. See [Peer::subscribe]
+    pub async fn subscribe<T>(&mut self, signal_name: &str) -> Result<impl Stream<Item = T>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let signal_name = self.qualify(signal_name);
+        self.peer.subscribe(&signal_name).await
+    }
+
+    /// Remote object signal subscription with a filter. See [Peer::subscribe_filtered]
+    pub async fn subscribe_filtered<T>(
+        &mut self,
+        signal_name: &str,
+        filter: Filter,
+    ) -> Result<impl Stream<Item = T>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let signal_name = self.qualify(signal_name);
+        self.peer.subscribe_filtered(&signal_name, filter).await
+    }
+
+    /// Start watching remote object state changes. See [Peer::watch]
+    pub async fn watch<T>(&mut self, state_name: &str) -> Result<impl Stream<Item = T>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let state_name = self.qualify(state_name);
+        self.peer.watch(&state_name).await
+    }
+
+    /// Remote object signal subscription reporting reconnections. See [Peer::subscribe_events]
+    pub async fn subscribe_events<T>(
+        &mut self,
+        signal_name: &str,
+    ) -> Result<impl Stream<Item = SubscriptionEvent<T>>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let signal_name = self.qualify(signal_name);
+        self.peer.subscribe_events(&signal_name).await
+    }
+
+    /// Remote object state watch reporting reconnections. See [Peer::watch_events]
+    pub async fn watch_events<T>(
+        &mut self,
+        state_name: &str,
+    ) -> Result<impl Stream<Item = SubscriptionEvent<T>>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let state_name = self.qualify(state_name);
+        self.peer.watch_events(&state_name).await
+    }
+
+    /// Inspect object endpoints and child objects
+    pub async fn inspect(&mut self) -> Result<InspectData> {
+        self.call(INSPECT_METHOD, &()).await
+    }
+
+    fn qualify(&self, endpoint_name: &str) -> String {
+        object_path::qualify(&self.path, endpoint_name)
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use log::*;
+use tokio_stream::Stream;
+
+use karo_bus_common::messages::MessageBody;
+use karo_common_rpc::rpc_sender::RpcSender;
+
+/// Signal subscription or state watch stream item. See [crate::Peer::subscribe_events]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionEvent<T> {
+    /// Signal emission or state value
+    Value(T),
+    /// The peer connection was re-established and the subscription renewed.
+    /// Emissions sent while disconnected are lost. State watches receive
+    /// the current value right after this event
+    Resubscribed,
+    /// The subscriber fell behind and the publisher dropped this number of emissions.
+    /// See [crate::LagPolicy]
+    Lagged(u64),
+}
+
+impl<T> SubscriptionEvent<T> {
+    /// Value of the event if any
+    pub fn into_value(self) -> Option<T> {
+        match self {
+            Self::Value(value) => Some(value),
+            Self::Resubscribed | Self::Lagged(_) => None,
+        }
+    }
+}
+
+/// Signal subscription or state watch stream. Dropping the stream
+/// tells the publisher to stop sending updates.\
+/// **S** is the stream of decoded values
+pub(crate) struct Subscription<S> {
+    /// Decoded values
+    values: S,
+    /// Sender to unsubscribe
+    peer_sender: RpcSender,
+    /// Own service name
+    subscriber_name: String,
+    /// Subscriber assigned subscription seq
+    subscription_seq: u64,
+}
+
+impl<S> Subscription<S> {
+    pub(crate) fn new(
+        values: S,
+        peer_sender: RpcSender,
+        subscriber_name: String,
+        subscription_seq: u64,
+    ) -> Self {
+        Self {
+            values,
+            peer_sender,
+            subscriber_name,
+            subscription_seq,
+        }
+    }
+}
+
+impl<S: Stream + Unpin> Stream for Subscription<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().values).poll_next(cx)
+    }
+}
+
+impl<S> Drop for Subscription<S> {
+    fn drop(&mut self) {
+        trace!("Subscription {} dropped", self.subscription_seq);
+
+        let message = MessageBody::Unsubscribe {
+            subscriber_name: self.subscriber_name.clone(),
+            seq: self.subscription_seq,
+        };
+        let peer_sender = self.peer_sender.clone();
+
+        tokio::spawn(async move {
+            let _ = peer_sender.send(bson::to_bson(&message).unwrap()).await;
+        });
+    }
+}
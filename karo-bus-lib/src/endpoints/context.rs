@@ -0,0 +1,77 @@
+use std::{collections::HashMap, sync::Arc, time::SystemTime};
+
+use tokio::sync::watch::{self, Sender as WatchSender};
+
+use karo_bus_common::messages::PeerCredentials;
+
+use crate::connections::fd_channel::FdChannel;
+
+/// Method call context. Passed into handlers registered with
+/// [crate::Bus::register_method_with_context]
+#[derive(Debug, Clone)]
+pub struct CallContext {
+    /// Caller service name
+    pub caller_name: String,
+    /// Caller process credentials provided by the hub if available
+    pub credentials: Option<PeerCredentials>,
+    /// Call sequence number
+    pub seq: u64,
+    /// Call deadline if the caller set one
+    pub deadline: Option<SystemTime>,
+    /// Call metadata set by the caller
+    pub headers: HashMap<String, String>,
+    /// Cancelled if the caller is not waiting for the response anymore,
+    /// or the call deadline is exceeded
+    pub cancellation: CancellationToken,
+    /// Caller assigned call seq. Used to match cancellation requests
+    pub(crate) call_seq: u64,
+    /// Caller connection descriptor channel. Used to pass descriptors embedded into
+    /// parameters and return values
+    pub(crate) fd_channel: Option<FdChannel>,
+}
+
+impl CallContext {
+    /// Get a request header value
+    pub fn header(&self, name: &str) -> Option<&String> {
+        self.headers.get(name)
+    }
+}
+
+/// Call cancellation token. Long running handlers can use it to stop early
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    tx: Arc<WatchSender<bool>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        let (tx, _rx) = watch::channel(false);
+
+        Self { tx: Arc::new(tx) }
+    }
+
+    /// Cancel the token. Wakes up everyone waiting in [CancellationToken::cancelled]
+    pub fn cancel(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Wait until the token is cancelled
+    pub async fn cancelled(&self) {
+        let mut rx = self.tx.subscribe();
+
+        while !*rx.borrow_and_update() {
+            // We own the sender, so the channel can't be closed
+            let _ = rx.changed().await;
+        }
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,95 @@
+use std::{any::Any, sync::Arc};
+
+use karo_bus_common::inspect_data::EndpointMetadata;
+
+/// Default max number of concurrently running calls of a single method
+pub const DEFAULT_CONCURRENCY: usize = 16;
+/// Default max number of calls waiting for a free handler slot
+pub const DEFAULT_QUEUE_SIZE: usize = 32;
+
+/// Method handler execution options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodOptions {
+    /// Max number of concurrently running handler calls. Unlimited if None
+    pub concurrency: Option<usize>,
+    /// Max number of calls waiting for a free handler slot.
+    /// Calls above the limit are rejected with [karo_bus_common::errors::Error::Busy]
+    pub queue_size: usize,
+    /// Description, version and deprecation returned through the inspection
+    pub metadata: EndpointMetadata,
+}
+
+impl MethodOptions {
+    /// Handle calls one by one in the incoming order
+    pub fn serialized() -> Self {
+        Self {
+            concurrency: Some(1),
+            ..Default::default()
+        }
+    }
+
+    /// Set max number of concurrently running handler calls
+    pub fn concurrency(mut self, concurrency: Option<usize>) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Set max number of calls waiting for a free handler slot
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size;
+        self
+    }
+
+    /// Set method metadata
+    pub fn metadata(mut self, metadata: EndpointMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+impl Default for MethodOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: Some(DEFAULT_CONCURRENCY),
+            queue_size: DEFAULT_QUEUE_SIZE,
+            metadata: EndpointMetadata::default(),
+        }
+    }
+}
+
+/// Method handler panic report. See [crate::Bus::set_method_panic_hook]
+#[derive(Debug, Clone)]
+pub struct MethodPanic {
+    /// Panicked method name
+    pub method_name: String,
+    /// Service, which made the call
+    pub caller_name: String,
+    /// Panic message if available
+    pub message: String,
+}
+
+impl MethodPanic {
+    pub(crate) fn new(
+        method_name: String,
+        caller_name: String,
+        panic: Box<dyn Any + Send>,
+    ) -> Self {
+        // Panic payload is either a static string or a formatted one
+        let message = match panic.downcast::<String>() {
+            Ok(message) => *message,
+            Err(panic) => match panic.downcast::<&str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "Unknown panic".into(),
+            },
+        };
+
+        Self {
+            method_name,
+            caller_name,
+            message,
+        }
+    }
+}
+
+/// User hook to report method handler panics
+pub(crate) type PanicHook = Arc<dyn Fn(&MethodPanic) + Send + Sync>;
@@ -1,40 +1,68 @@
 use std::{
     any::type_name,
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     future::Future,
     sync::{Arc, RwLock},
+    time::SystemTime,
 };
 
 use anyhow::Result;
-use bson::Bson;
+use bson::{Bson, RawDocumentBuf};
 use log::*;
 use serde::{de::DeserializeOwned, Serialize};
+use tokio_stream::{Stream, StreamExt};
 
-use tokio::sync::{
-    broadcast::{self, Sender as BroadcastSender},
-    mpsc::{self, Receiver, Sender},
-    oneshot::{self, Sender as OneSender},
-    watch::{self, Receiver as WatchReceiver},
+use tokio::{
+    sync::{
+        broadcast::Sender as BroadcastSender,
+        mpsc::{self, error::TrySendError, Receiver, Sender},
+        oneshot::{self, Sender as OneSender},
+        watch::{self, Receiver as WatchReceiver},
+        Semaphore,
+    },
+    time,
 };
 
 use karo_bus_common::{
     errors::Error as BusError,
-    inspect_data::InspectData,
+    filter::Filter,
+    inspect_data::{InspectData, MethodSchema},
     messages::{IntoMessage, Message, Response},
+    object_path::{self, ROOT_OBJECT_PATH},
+    schema::BusSchema,
 };
 
-use karo_common_rpc::Message as MessageHandle;
+use karo_common_rpc::{rpc_sender::RpcSender, Message as MessageHandle};
 
 use crate::{
+    channel::{
+        ChannelHandler, ChannelParts, ChannelReceiver, ChannelSender, ChannelSide, ChannelState,
+    },
     connections::peer::Peer,
-    endpoints::{signal::Signal, state::State},
+    endpoints::{
+        context::{CallContext, CancellationToken},
+        method::{MethodOptions, MethodPanic, PanicHook},
+        registration::{Registration, RegistrationKind},
+        signal::{Emission, Signal, SignalChannel, SignalOptions},
+        state::{State, StateOptions},
+        stream::{start_stream_task, StreamControl, StreamHandler},
+    },
+    fd::{self, MessageFds},
 };
 
+pub mod context;
+pub mod method;
+pub mod object;
+pub mod registration;
 pub mod signal;
 pub mod state;
+pub mod stream;
 
 type Shared<T> = Arc<RwLock<T>>;
-type MethodCall = (Bson, OneSender<Response>);
+/// Call parameters with the descriptors they refer, call context and response sender
+type MethodCall = (Bson, MessageFds, CallContext, OneSender<MethodResponse>);
+/// Method response with the descriptors embedded into the return value
+type MethodResponse = (Response, MessageFds);
 
 /// This service endpoints
 #[derive(Clone)]
@@ -43,13 +71,33 @@ pub struct Endpoints {
     /// receive a result from a callback
     methods: Shared<HashMap<String, Sender<MethodCall>>>,
     /// Registered signals. Sender is used to emit signals to subscribers
-    signals: Shared<HashMap<String, BroadcastSender<Message>>>,
+    signals: Shared<HashMap<String, SignalChannel>>,
     /// Registered states.
     /// Sender is used to nofity state change to subscribers
     /// Receiver used to get current walue when user makes watch request
-    states: Shared<HashMap<String, (BroadcastSender<Message>, WatchReceiver<Bson>)>>,
+    states: Shared<HashMap<String, (SignalChannel, WatchReceiver<Bson>)>>,
     /// Data for service inspection
     inspect_data: Shared<InspectData>,
+    /// User hook to report method handler panics
+    panic_hook: Shared<Option<PanicHook>>,
+    /// Calls being handled. Used to cancel calls by caller name and call seq
+    active_calls: Shared<HashMap<(String, u64), CancellationToken>>,
+    /// Registered streaming methods
+    stream_methods: Shared<HashMap<String, StreamHandler>>,
+    /// Running stream calls by caller name and call seq
+    active_streams: Shared<HashMap<(String, u64), StreamControl>>,
+    /// Registered channel handlers
+    channels: Shared<HashMap<String, ChannelHandler>>,
+    /// Open channels by opener name and call seq
+    active_channels: Shared<HashMap<(String, u64), ChannelState>>,
+    /// Signal subscriptions and state watches by subscriber name and subscription seq
+    active_subscriptions: Shared<HashMap<(String, u64), CancellationToken>>,
+    /// Registered object paths. Object endpoints are registered with names
+    /// qualified by the object path. See [object_path::qualify]
+    objects: Shared<BTreeSet<String>>,
+    /// Method and channel registration ids. Used to unregister endpoints
+    /// when their [Registration] handles are dropped
+    registrations: Shared<HashMap<(RegistrationKind, String), u64>>,
 }
 
 impl Endpoints {
@@ -59,60 +107,194 @@ impl Endpoints {
             signals: Arc::new(RwLock::new(HashMap::new())),
             states: Arc::new(RwLock::new(HashMap::new())),
             inspect_data: Arc::new(RwLock::new(InspectData::new())),
+            panic_hook: Arc::new(RwLock::new(None)),
+            active_calls: Arc::new(RwLock::new(HashMap::new())),
+            stream_methods: Arc::new(RwLock::new(HashMap::new())),
+            active_streams: Arc::new(RwLock::new(HashMap::new())),
+            channels: Arc::new(RwLock::new(HashMap::new())),
+            active_channels: Arc::new(RwLock::new(HashMap::new())),
+            active_subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            objects: Arc::new(RwLock::new(BTreeSet::new())),
+            registrations: Arc::new(RwLock::new(HashMap::new())),
         }
     }
+
+    /// Set a hook to report method handler panics. The panicked call is replied
+    /// with [BusError::Internal], and the method keeps handling calls
+    pub fn set_method_panic_hook(&mut self, hook: impl Fn(&MethodPanic) + Send + Sync + 'static) {
+        *self.panic_hook.write().unwrap() = Some(Arc::new(hook));
+    }
     /// Register service method. The function uses BSON internally for requests
-    /// and responses.\
+    /// and responses. Calls are handled concurrently with default [MethodOptions].\
     /// **P** is paramtere type. Should be a deserializable structure\
     /// **R** is method return type. Should be a serializable structure
     pub fn register_method<P, R, Ret>(
         &mut self,
         method_name: &str,
         callback: impl Fn(P) -> Ret + Send + Sync + 'static,
-    ) -> Result<()>
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        Ret: Future<Output = R> + Send + 'static,
+    {
+        self.register_method_with_options(method_name, MethodOptions::default(), callback)
+    }
+
+    /// Register service method with given execution **options**.
+    /// See [Endpoints::register_method]
+    pub fn register_method_with_options<P, R, Ret>(
+        &mut self,
+        method_name: &str,
+        options: MethodOptions,
+        callback: impl Fn(P) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        Ret: Future<Output = R> + Send + 'static,
+    {
+        self.register_method_with_context(method_name, options, move |params, _context| {
+            callback(params)
+        })
+    }
+
+    /// Register service method, which receives [CallContext] along with the parameters.
+    /// See [Endpoints::register_method]
+    pub fn register_method_with_context<P, R, Ret>(
+        &mut self,
+        method_name: &str,
+        options: MethodOptions,
+        callback: impl Fn(P, CallContext) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
     where
         P: DeserializeOwned + Send + 'static,
         R: Serialize + Send + 'static,
-        Ret: Future<Output = R> + Send,
+        Ret: Future<Output = R> + Send + 'static,
+    {
+        self.register_fallible_method_with_context(method_name, options, move |params, context| {
+            let result = callback(params, context);
+            async move { Ok::<R, ()>(result.await) }
+        })
+    }
+
+    /// Register service method, which may fail. The caller receives **E** as
+    /// [crate::errors::MethodError]. See [Endpoints::register_method]
+    pub fn register_fallible_method<P, R, E, Ret>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        E: Serialize + Send + 'static,
+        Ret: Future<Output = std::result::Result<R, E>> + Send + 'static,
+    {
+        self.register_fallible_method_with_context(
+            method_name,
+            MethodOptions::default(),
+            move |params, _context| callback(params),
+        )
+    }
+
+    /// Register service method, which may fail and receives [CallContext] along
+    /// with the parameters. See [Endpoints::register_fallible_method]
+    pub fn register_fallible_method_with_context<P, R, E, Ret>(
+        &mut self,
+        method_name: &str,
+        options: MethodOptions,
+        callback: impl Fn(P, CallContext) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        E: Serialize + Send + 'static,
+        Ret: Future<Output = std::result::Result<R, E>> + Send + 'static,
     {
         let method_name = method_name.into();
-        let mut rx = self.update_method_map(&method_name)?;
+        let (mut rx, registration) = self.update_method_map(&method_name, options.queue_size)?;
 
         // Add the method into the inspection register
-        self.inspect_data.write().unwrap().methods.push(format!(
-            "{}({}) -> {}",
-            method_name,
-            type_name::<P>().split("::").last().unwrap_or("Unknown"),
-            type_name::<R>().split("::").last().unwrap_or("Unknown")
-        ));
+        {
+            let mut inspect_data = self.inspect_data.write().unwrap();
+
+            inspect_data.methods.push(format!(
+                "{}({}) -> {}",
+                method_name,
+                type_name::<P>().split("::").last().unwrap_or("Unknown"),
+                type_name::<R>().split("::").last().unwrap_or("Unknown")
+            ));
+
+            if !options.metadata.is_empty() {
+                inspect_data
+                    .method_metadata
+                    .insert(method_name.clone(), options.metadata.clone());
+            }
+        }
+
+        let callback = Arc::new(callback);
+        let panic_hook = self.panic_hook.clone();
+        let semaphore = options
+            .concurrency
+            .map(|concurrency| Arc::new(Semaphore::new(concurrency.max(1))));
 
         tokio::spawn(async move {
             loop {
+                // Wait for a free handler slot before taking the next call. Meanwhile new calls
+                // are waiting in the queue, so no more than queue size calls are waiting
+                let permit = match semaphore {
+                    Some(ref semaphore) => match semaphore.clone().acquire_owned().await {
+                        Ok(permit) => Some(permit),
+                        Err(_) => return,
+                    },
+                    None => None,
+                };
+
                 match rx.recv().await {
-                    Some((params, calback_tx)) => {
-                        match bson::from_bson::<P>(params) {
-                            Ok(params) => {
-                                // Receive method call response
-                                let result = callback(params).await;
-
-                                // Deserialize and send user response
-                                calback_tx
-                                    .send(Response::Return(bson::to_bson(&result).unwrap()))
-                                    .unwrap();
-                            }
-                            Err(err) => {
-                                warn!(
-                                    "Failed to deserialize method call parameters: {}",
-                                    err.to_string()
-                                );
-
-                                calback_tx
-                                    .send(Response::Error(BusError::InvalidParameters(
-                                        err.to_string(),
-                                    )))
-                                    .unwrap();
-                            }
-                        }
+                    Some((params, fds, context, calback_tx)) => {
+                        let callback = callback.clone();
+                        let panic_hook = panic_hook.clone();
+                        let method_name = method_name.clone();
+
+                        tokio::spawn(async move {
+                            let caller_name = context.caller_name.clone();
+
+                            // Run user callback in a separate task to catch panics
+                            let invocation = tokio::spawn(async move {
+                                Self::invoke_method(callback.as_ref(), params, fds, context).await
+                            });
+
+                            let response = match invocation.await {
+                                Ok(response) => response,
+                                Err(err) if err.is_panic() => {
+                                    let panic = MethodPanic::new(
+                                        method_name,
+                                        caller_name,
+                                        err.into_panic(),
+                                    );
+
+                                    error!(
+                                        "Method `{}` panicked while handling a call from `{}`: {}",
+                                        panic.method_name, panic.caller_name, panic.message
+                                    );
+
+                                    let hook = panic_hook.read().unwrap().clone();
+                                    if let Some(hook) = hook {
+                                        hook(&panic);
+                                    }
+
+                                    (Response::Error(BusError::Internal), MessageFds::default())
+                                }
+                                Err(_) => {
+                                    (Response::Error(BusError::Internal), MessageFds::default())
+                                }
+                            };
+
+                            // Caller may be gone already
+                            let _ = calback_tx.send(response);
+                            drop(permit);
+                        });
                     }
                     None => {
                         trace!("Method {} shut down", method_name);
@@ -122,16 +304,69 @@ impl Endpoints {
             }
         });
 
-        Ok(())
+        Ok(registration)
+    }
+
+    /// Deserialize parameters and call user method callback
+    async fn invoke_method<P, R, E, Ret>(
+        callback: &impl Fn(P, CallContext) -> Ret,
+        params: Bson,
+        fds: MessageFds,
+        context: CallContext,
+    ) -> MethodResponse
+    where
+        P: DeserializeOwned,
+        R: Serialize,
+        E: Serialize,
+        Ret: Future<Output = std::result::Result<R, E>>,
+    {
+        match fd::from_bson::<P>(params, fds) {
+            Ok(params) => {
+                // Receive method call response. Serialize and send user response
+                let response = match callback(params, context).await {
+                    Ok(result) => {
+                        fd::to_bson(&result).map(|(data, fds)| (Response::Return(data), fds))
+                    }
+                    Err(err) => bson::to_bson(&err)
+                        .map(|data| (Response::MethodError(data), MessageFds::default())),
+                };
+
+                response.unwrap_or_else(|err| {
+                    error!("Failed to serialize method call result: {}", err);
+                    (Response::Error(BusError::Internal), MessageFds::default())
+                })
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to deserialize method call parameters: {}",
+                    err.to_string()
+                );
+
+                (
+                    Response::Error(BusError::InvalidParameters(err.to_string())),
+                    MessageFds::default(),
+                )
+            }
+        }
     }
 
     /// Adds new method to a method map
-    fn update_method_map(&mut self, method_name: &String) -> Result<Receiver<MethodCall>> {
+    fn update_method_map(
+        &mut self,
+        method_name: &String,
+        queue_size: usize,
+    ) -> Result<(Receiver<MethodCall>, Registration)> {
         // The function just creates a method handle, which performs type conversions
         // for incoming data and client replies. See [Method] for details
         let mut methods = self.methods.write().unwrap();
 
-        if methods.contains_key(method_name) {
+        if methods.contains_key(method_name)
+            || self
+                .stream_methods
+                .read()
+                .unwrap()
+                .contains_key(method_name)
+        {
             error!(
                 "Failed to register method `{}`. Already registered",
                 method_name
@@ -140,18 +375,205 @@ impl Endpoints {
             return Err(BusError::AlreadyRegistered.into());
         }
 
-        let (tx, rx) = mpsc::channel(32);
+        let (tx, rx) = mpsc::channel(queue_size.max(1));
 
         methods.insert(method_name.clone(), tx);
+        let registration = self.track_registration(RegistrationKind::Method, method_name);
 
         info!("Succesfully registered method: {}", method_name);
-        Ok(rx)
+        Ok((rx, registration))
+    }
+
+    /// Register method, which works on raw BSON documents. Parameters and return
+    /// values are ordinary call documents, so typed callers may call the method as well.
+    /// Call it with [Peer::call_raw]
+    pub fn register_raw_method<Ret>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(RawDocumentBuf) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        Ret: Future<Output = RawDocumentBuf> + Send + 'static,
+    {
+        self.register_method(method_name, callback)
+    }
+
+    /// Register streaming method. Handler returns a stream of items, which are sent to the caller
+    /// as it consumes them.\
+    /// **P** is paramtere type. Should be a deserializable structure\
+    /// **R** is stream item type. Should be a serializable structure
+    pub fn register_streaming_method<P, R, S>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P) -> S + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        S: Stream<Item = R> + Send + 'static,
+    {
+        self.register_streaming_method_with_context(method_name, move |params, _context| {
+            callback(params)
+        })
+    }
+
+    /// Register streaming method, which receives [CallContext] along with the parameters.
+    /// See [Endpoints::register_streaming_method]
+    pub fn register_streaming_method_with_context<P, R, S>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P, CallContext) -> S + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        S: Stream<Item = R> + Send + 'static,
+    {
+        // Lock methods first the same way [Endpoints::update_method_map] does
+        let methods = self.methods.read().unwrap();
+        let mut stream_methods = self.stream_methods.write().unwrap();
+
+        if stream_methods.contains_key(method_name) || methods.contains_key(method_name) {
+            error!(
+                "Failed to register streaming method `{}`. Already registered",
+                method_name
+            );
+
+            return Err(BusError::AlreadyRegistered.into());
+        }
+
+        // Add the method into the inspection register
+        self.inspect_data.write().unwrap().methods.push(format!(
+            "{}({}) -> stream {}",
+            method_name,
+            type_name::<P>().split("::").last().unwrap_or("Unknown"),
+            type_name::<R>().split("::").last().unwrap_or("Unknown")
+        ));
+
+        let handler: StreamHandler = Arc::new(move |params, context| {
+            let params = bson::from_bson::<P>(params).map_err(|err| {
+                warn!(
+                    "Failed to deserialize streaming method call parameters: {}",
+                    err.to_string()
+                );
+
+                BusError::InvalidParameters(err.to_string())
+            })?;
+
+            let stream = callback(params, context).map(|item| match bson::to_bson(&item) {
+                Ok(item) => Response::StreamItem(item),
+                Err(err) => {
+                    error!("Failed to serialize stream item: {}", err);
+                    Response::Error(BusError::Internal)
+                }
+            });
+
+            Ok(Box::pin(stream) as _)
+        });
+
+        stream_methods.insert(method_name.into(), handler);
+        let registration = self.track_registration(RegistrationKind::Method, method_name);
+
+        info!("Succesfully registered streaming method: {}", method_name);
+        Ok(registration)
+    }
+
+    /// Register duplex channel. Handler receives opener's init value and a pair
+    /// of channel halves to exchange items with the opener. Channel is closed
+    /// when the handler finishes.\
+    /// **I** is init value type. Should be a deserializable structure\
+    /// **In** is incoming item type. Should be a deserializable structure\
+    /// **Out** is outgoing item type. Should be a serializable structure
+    pub fn register_channel<I, In, Out, Ret>(
+        &mut self,
+        channel_name: &str,
+        callback: impl Fn(I, ChannelSender<Out>, ChannelReceiver<In>) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        I: DeserializeOwned + Send + 'static,
+        In: DeserializeOwned + Send + 'static,
+        Out: Serialize + Send + 'static,
+        Ret: Future<Output = ()> + Send + 'static,
+    {
+        self.register_channel_with_context(channel_name, move |init, _context, sender, receiver| {
+            callback(init, sender, receiver)
+        })
+    }
+
+    /// Register duplex channel, which handler receives [CallContext] along with the init value.
+    /// See [Endpoints::register_channel]
+    pub fn register_channel_with_context<I, In, Out, Ret>(
+        &mut self,
+        channel_name: &str,
+        callback: impl Fn(I, CallContext, ChannelSender<Out>, ChannelReceiver<In>) -> Ret
+            + Send
+            + Sync
+            + 'static,
+    ) -> Result<Registration>
+    where
+        I: DeserializeOwned + Send + 'static,
+        In: DeserializeOwned + Send + 'static,
+        Out: Serialize + Send + 'static,
+        Ret: Future<Output = ()> + Send + 'static,
+    {
+        let mut channels = self.channels.write().unwrap();
+
+        if channels.contains_key(channel_name) {
+            error!(
+                "Failed to register channel `{}`. Already registered",
+                channel_name
+            );
+
+            return Err(BusError::AlreadyRegistered.into());
+        }
+
+        // Add the channel into the inspection register
+        self.inspect_data.write().unwrap().channels.push(format!(
+            "{}({}) -> channel({}, {})",
+            channel_name,
+            type_name::<I>().split("::").last().unwrap_or("Unknown"),
+            type_name::<In>().split("::").last().unwrap_or("Unknown"),
+            type_name::<Out>().split("::").last().unwrap_or("Unknown")
+        ));
+
+        let handler: ChannelHandler = Arc::new(move |init, context, parts: ChannelParts| {
+            let init = bson::from_bson::<I>(init).map_err(|err| {
+                warn!(
+                    "Failed to deserialize channel init value: {}",
+                    err.to_string()
+                );
+
+                BusError::InvalidParameters(err.to_string())
+            })?;
+
+            let (sender, receiver) = parts.split::<Out, In>();
+
+            Ok(Box::pin(callback(init, context, sender, receiver)) as _)
+        });
+
+        channels.insert(channel_name.into(), handler);
+        let registration = self.track_registration(RegistrationKind::Channel, channel_name);
+
+        info!("Succesfully registered channel: {}", channel_name);
+        Ok(registration)
     }
 
     /// Register service signal.\
     /// **T** is a signal type. Should be a serializable structure.\
     /// **Returns** [Signal] handle which can be used to emit signal
     pub fn register_signal<T>(&mut self, signal_name: &str) -> Result<Signal<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.register_signal_with_options(signal_name, SignalOptions::default())
+    }
+
+    /// Register service signal with [SignalOptions]. See [Endpoints::register_signal]
+    pub fn register_signal_with_options<T>(
+        &mut self,
+        signal_name: &str,
+        options: SignalOptions,
+    ) -> Result<Signal<T>>
     where
         T: Serialize + 'static,
     {
@@ -167,18 +589,28 @@ impl Endpoints {
         }
 
         // Add the signal into the inspection register
-        self.inspect_data.write().unwrap().signals.push(format!(
-            "{}: {}",
-            signal_name,
-            type_name::<T>().split("::").last().unwrap_or("Unknown"),
-        ));
+        {
+            let mut inspect_data = self.inspect_data.write().unwrap();
+
+            inspect_data.signals.push(format!(
+                "{}: {}",
+                signal_name,
+                type_name::<T>().split("::").last().unwrap_or("Unknown"),
+            ));
 
-        let (tx, _rx) = broadcast::channel(5);
+            if !options.metadata.is_empty() {
+                inspect_data
+                    .signal_metadata
+                    .insert(signal_name.into(), options.metadata);
+            }
+        }
+
+        let channel = SignalChannel::new(options.capacity, options.lag_policy);
 
-        signals.insert(signal_name.into(), tx.clone());
+        signals.insert(signal_name.into(), channel.clone());
 
         info!("Succesfully registered signal: {}", signal_name);
-        Ok(Signal::new(signal_name.into(), tx))
+        Ok(Signal::new(signal_name.into(), channel, self.clone()))
     }
 
     /// Register service signal.\
@@ -186,6 +618,19 @@ impl Endpoints {
     /// **Returns** [State] handle which can be used to change state. Settings the state
     /// will emit state change to watchers.
     pub fn register_state<T>(&mut self, state_name: &str, initial_value: T) -> Result<State<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.register_state_with_options(state_name, initial_value, StateOptions::default())
+    }
+
+    /// Register service state with [StateOptions]. See [Endpoints::register_state]
+    pub fn register_state_with_options<T>(
+        &mut self,
+        state_name: &str,
+        initial_value: T,
+        options: StateOptions,
+    ) -> Result<State<T>>
     where
         T: Serialize + 'static,
     {
@@ -201,66 +646,802 @@ impl Endpoints {
         }
 
         // Add the state into the inspection register
-        self.inspect_data.write().unwrap().states.push(format!(
-            "{}: {}",
-            state_name,
-            type_name::<T>().split("::").last().unwrap_or("Unknown"),
-        ));
+        {
+            let mut inspect_data = self.inspect_data.write().unwrap();
+
+            inspect_data.states.push(format!(
+                "{}: {}",
+                state_name,
+                type_name::<T>().split("::").last().unwrap_or("Unknown"),
+            ));
+
+            if !options.metadata.is_empty() {
+                inspect_data
+                    .state_metadata
+                    .insert(state_name.into(), options.metadata);
+            }
+        }
 
         // Channel to send state update to subscribers
-        let (tx, _rx) = broadcast::channel(5);
+        let channel = SignalChannel::new(options.capacity, options.lag_policy);
 
         // Channel to get current value when someone is subscribing
         let bson = bson::to_bson(&initial_value).unwrap();
         let (watch_tx, watch_rx) = watch::channel(bson);
 
-        states.insert(state_name.into(), (tx.clone(), watch_rx));
+        states.insert(state_name.into(), (channel.clone(), watch_rx));
 
         info!("Succesfully registered state: {}", state_name);
-        Ok(State::new(state_name.into(), initial_value, tx, watch_tx))
+        Ok(State::new(
+            state_name.into(),
+            initial_value,
+            channel,
+            watch_tx,
+            self.clone(),
+        ))
+    }
+
+    /// Describe registered method parameters and return value for the inspection
+    pub fn describe_method<P: BusSchema, R: BusSchema>(&mut self, method_name: &str) -> Result<()> {
+        if !self.methods.read().unwrap().contains_key(method_name)
+            && !self
+                .stream_methods
+                .read()
+                .unwrap()
+                .contains_key(method_name)
+        {
+            error!(
+                "Failed to describe method `{}`. Not registered",
+                method_name
+            );
+            return Err(BusError::NotRegistered.into());
+        }
+
+        self.inspect_data.write().unwrap().method_schemas.insert(
+            method_name.into(),
+            MethodSchema {
+                params: P::schema(),
+                returns: R::schema(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Describe registered signal value for the inspection
+    pub fn describe_signal<T: BusSchema>(&mut self, signal_name: &str) -> Result<()> {
+        if !self.signals.read().unwrap().contains_key(signal_name) {
+            error!(
+                "Failed to describe signal `{}`. Not registered",
+                signal_name
+            );
+            return Err(BusError::NotRegistered.into());
+        }
+
+        self.inspect_data
+            .write()
+            .unwrap()
+            .signal_schemas
+            .insert(signal_name.into(), T::schema());
+
+        Ok(())
     }
 
-    /// Handle incoming method call
+    /// Describe registered state value for the inspection
+    pub fn describe_state<T: BusSchema>(&mut self, state_name: &str) -> Result<()> {
+        if !self.states.read().unwrap().contains_key(state_name) {
+            error!("Failed to describe state `{}`. Not registered", state_name);
+            return Err(BusError::NotRegistered.into());
+        }
+
+        self.inspect_data
+            .write()
+            .unwrap()
+            .state_schemas
+            .insert(state_name.into(), T::schema());
+
+        Ok(())
+    }
+
+    /// Unregister service method or streaming method. Calls being handled are finished,
+    /// next calls fail with [BusError::NotRegistered]
+    pub fn unregister_method(&mut self, method_name: &str) -> Result<()> {
+        let removed = self.methods.write().unwrap().remove(method_name).is_some()
+            || self
+                .stream_methods
+                .write()
+                .unwrap()
+                .remove(method_name)
+                .is_some();
+
+        if !removed {
+            error!(
+                "Failed to unregister method `{}`. Not registered",
+                method_name
+            );
+            return Err(BusError::NotRegistered.into());
+        }
+
+        self.inspect_data
+            .write()
+            .unwrap()
+            .remove_method(method_name);
+        self.registrations
+            .write()
+            .unwrap()
+            .remove(&(RegistrationKind::Method, method_name.into()));
+
+        info!("Succesfully unregistered method: {}", method_name);
+        Ok(())
+    }
+
+    /// Unregister duplex channel. Open channels are not closed
+    pub fn unregister_channel(&mut self, channel_name: &str) -> Result<()> {
+        if self
+            .channels
+            .write()
+            .unwrap()
+            .remove(channel_name)
+            .is_none()
+        {
+            error!(
+                "Failed to unregister channel `{}`. Not registered",
+                channel_name
+            );
+            return Err(BusError::NotRegistered.into());
+        }
+
+        self.inspect_data
+            .write()
+            .unwrap()
+            .remove_channel(channel_name);
+        self.registrations
+            .write()
+            .unwrap()
+            .remove(&(RegistrationKind::Channel, channel_name.into()));
+
+        info!("Succesfully unregistered channel: {}", channel_name);
+        Ok(())
+    }
+
+    /// Unregister service signal. Subscribers get [Response::StreamEnd].
+    /// Emitting the signal with an existing [Signal] handle does nothing
+    pub fn unregister_signal(&mut self, signal_name: &str) -> Result<()> {
+        let signal = self.signals.write().unwrap().remove(signal_name);
+
+        match signal {
+            Some(channel) => {
+                self.finish_signal(signal_name, &channel.tx);
+                Ok(())
+            }
+            None => {
+                error!(
+                    "Failed to unregister signal `{}`. Not registered",
+                    signal_name
+                );
+                Err(BusError::NotRegistered.into())
+            }
+        }
+    }
+
+    /// Unregister service state. Watchers get [Response::StreamEnd]
+    pub fn unregister_state(&mut self, state_name: &str) -> Result<()> {
+        let state = self.states.write().unwrap().remove(state_name);
+
+        match state {
+            Some((channel, _)) => {
+                self.finish_state(state_name, &channel.tx);
+                Ok(())
+            }
+            None => {
+                error!(
+                    "Failed to unregister state `{}`. Not registered",
+                    state_name
+                );
+                Err(BusError::NotRegistered.into())
+            }
+        }
+    }
+
+    /// Unregister the signal if it's still registered with the **signal_sender**.
+    /// Called when the [Signal] handle is dropped
+    pub(crate) fn release_signal(
+        &self,
+        signal_name: &str,
+        signal_sender: &BroadcastSender<Emission>,
+    ) {
+        let mut signals = self.signals.write().unwrap();
+
+        if signals
+            .get(signal_name)
+            .map(|registered| registered.tx.same_channel(signal_sender))
+            .unwrap_or_default()
+        {
+            signals.remove(signal_name);
+            drop(signals);
+
+            self.finish_signal(signal_name, signal_sender);
+        }
+    }
+
+    /// Start tracking a method or channel registration made under **name**
+    fn track_registration(&self, kind: RegistrationKind, name: &str) -> Registration {
+        let registration = Registration::new(kind, name.into(), self.clone());
+
+        self.registrations
+            .write()
+            .unwrap()
+            .insert((kind, name.into()), registration.id());
+
+        registration
+    }
+
+    /// Unregister the method or channel if it's still registered with the
+    /// **registration_id**. Called when the [Registration] handle is dropped
+    pub(crate) fn release_registration(
+        &self,
+        kind: RegistrationKind,
+        name: &str,
+        registration_id: u64,
+    ) {
+        let key = (kind, name.to_owned());
+
+        // Lock endpoint maps in the registration order to keep the id check
+        // and the removal atomic
+        match kind {
+            RegistrationKind::Method => {
+                let mut methods = self.methods.write().unwrap();
+                let mut stream_methods = self.stream_methods.write().unwrap();
+                let mut registrations = self.registrations.write().unwrap();
+
+                if registrations.get(&key) != Some(&registration_id) {
+                    return;
+                }
+
+                registrations.remove(&key);
+                if methods.remove(name).is_none() {
+                    stream_methods.remove(name);
+                }
+            }
+            RegistrationKind::Channel => {
+                let mut channels = self.channels.write().unwrap();
+                let mut registrations = self.registrations.write().unwrap();
+
+                if registrations.get(&key) != Some(&registration_id) {
+                    return;
+                }
+
+                registrations.remove(&key);
+                channels.remove(name);
+            }
+        }
+
+        let mut inspect_data = self.inspect_data.write().unwrap();
+        match kind {
+            RegistrationKind::Method => {
+                inspect_data.remove_method(name);
+                info!("Succesfully unregistered method: {}", name);
+            }
+            RegistrationKind::Channel => {
+                inspect_data.remove_channel(name);
+                info!("Succesfully unregistered channel: {}", name);
+            }
+        }
+    }
+
+    /// Unregister the state if it's still registered with the **state_change_sender**.
+    /// Called when the [State] handle is dropped
+    pub(crate) fn release_state(
+        &self,
+        state_name: &str,
+        state_change_sender: &BroadcastSender<Emission>,
+    ) {
+        let mut states = self.states.write().unwrap();
+
+        if states
+            .get(state_name)
+            .map(|(registered, _)| registered.tx.same_channel(state_change_sender))
+            .unwrap_or_default()
+        {
+            states.remove(state_name);
+            drop(states);
+
+            self.finish_state(state_name, state_change_sender);
+        }
+    }
+
+    /// Notify subscribers of the removed signal and drop it from the inspection
+    fn finish_signal(&self, signal_name: &str, signal_sender: &BroadcastSender<Emission>) {
+        // Subscription tasks forward the end and stop
+        let _ = signal_sender.send(Response::StreamEnd.into_message(0xFEEDC0DE).into());
+
+        self.inspect_data
+            .write()
+            .unwrap()
+            .remove_signal(signal_name);
+
+        info!("Succesfully unregistered signal: {}", signal_name);
+    }
+
+    /// Notify watchers of the removed state and drop it from the inspection
+    fn finish_state(&self, state_name: &str, state_change_sender: &BroadcastSender<Emission>) {
+        let _ = state_change_sender.send(Response::StreamEnd.into_message(0xFEEDC0DE).into());
+
+        self.inspect_data.write().unwrap().remove_state(state_name);
+
+        info!("Succesfully unregistered state: {}", state_name);
+    }
+
+    /// Register an object at the **path**. Endpoints of the object are registered
+    /// with names qualified by the path
+    pub fn register_object(&mut self, path: &str) -> Result<()> {
+        object_path::validate(path)?;
+
+        if path == ROOT_OBJECT_PATH || !self.objects.write().unwrap().insert(path.into()) {
+            error!("Failed to register object `{}`. Already registered", path);
+            return Err(BusError::AlreadyRegistered.into());
+        }
+
+        info!("Succesfully registered object: {}", path);
+        Ok(())
+    }
+
+    /// If an object is registered at the **path**
+    pub fn has_object(&self, path: &str) -> bool {
+        self.objects.read().unwrap().contains(path)
+    }
+
+    /// Unregister the object at the **path** and all its endpoints. Method tasks stop
+    /// after handling pending calls
+    pub fn unregister_object(&mut self, path: &str) -> Result<()> {
+        if !self.objects.write().unwrap().remove(path) {
+            error!("Failed to unregister object `{}`. Not registered", path);
+            return Err(BusError::NotRegistered.into());
+        }
+
+        let in_object = |name: &String| object_path::split(name).0 == path;
+
+        let methods = self
+            .methods
+            .read()
+            .unwrap()
+            .keys()
+            .chain(self.stream_methods.read().unwrap().keys())
+            .filter(|name| in_object(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        for method_name in methods {
+            self.unregister_method(&method_name)?;
+        }
+
+        let channels = self
+            .channels
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|name| in_object(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        for channel_name in channels {
+            self.unregister_channel(&channel_name)?;
+        }
+
+        let signals = self
+            .signals
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|name| in_object(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        for signal_name in signals {
+            self.unregister_signal(&signal_name)?;
+        }
+
+        let states = self
+            .states
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|name| in_object(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        for state_name in states {
+            self.unregister_state(&state_name)?;
+        }
+
+        info!("Succesfully unregistered object: {}", path);
+        Ok(())
+    }
+
+    /// Handle incoming method call. Doesn't wait for the method to return,
+    /// the response is sent from a separate task
     pub async fn handle_method_call(
         &self,
-        caller_name: &str,
         method_name: &str,
-        params: &Bson,
-        handle: &mut MessageHandle,
+        params: Bson,
+        context: CallContext,
+        mut handle: MessageHandle,
     ) {
         debug!(
             "Service `{}` requested method `{}` call",
-            caller_name, method_name
+            context.caller_name, method_name
         );
 
-        let seq = handle.id();
+        let seq = context.seq;
 
-        if method_name == karo_bus_common::inspect_data::INSPECT_METHOD {
-            handle.reply(&self.handle_inspect_call(seq)).await;
+        // Descriptors passed with rejected calls would stay unclaimed until
+        // the peer disconnects
+        let discard_fds = |params: &Bson| {
+            if let Some(fd_channel) = &context.fd_channel {
+                fd_channel.discard(params);
+            }
+        };
+
+        let (object, local_name) = object_path::split(method_name);
+        if local_name == karo_bus_common::inspect_data::INSPECT_METHOD {
+            discard_fds(&params);
+            handle.reply(&self.handle_inspect_call(seq, object)).await;
             return;
         }
 
         let method = self.methods.read().unwrap().get(method_name).cloned();
 
-        let response = if let Some(method) = method {
-            // Create oneshot channel to receive response
-            let (tx, rx) = oneshot::channel();
+        let method = match method {
+            Some(method) => method,
+            None => {
+                discard_fds(&params);
+                handle
+                    .reply(&BusError::NotRegistered.into_message(seq))
+                    .await;
+                return;
+            }
+        };
+
+        // Reserve a slot in the method queue. Reject the call if the queue is full
+        let slot = match method.try_reserve_owned() {
+            Ok(slot) => slot,
+            Err(err) => {
+                let error = match err {
+                    TrySendError::Full(_) => {
+                        warn!(
+                            "Method `{}` queue is full. Rejecting call from `{}`",
+                            method_name, context.caller_name
+                        );
+                        BusError::Busy
+                    }
+                    TrySendError::Closed(_) => {
+                        error!("Method `{}` handler is shut down", method_name);
+                        BusError::Internal
+                    }
+                };
+
+                discard_fds(&params);
+                handle.reply(&error.into_message(seq)).await;
+                return;
+            }
+        };
+
+        // Create oneshot channel to receive response
+        let (tx, rx) = oneshot::channel();
+
+        let fd_channel = context.fd_channel.clone();
+        let call_key = (context.caller_name.clone(), context.call_seq);
+        let cancellation = context.cancellation.clone();
+        let deadline = context.deadline;
+
+        // Callers which don't assign call seq can't cancel calls
+        if call_key.1 != 0 {
+            self.active_calls
+                .write()
+                .unwrap()
+                .insert(call_key.clone(), cancellation.clone());
+        }
+        let active_calls = self.active_calls.clone();
+        let method_name = method_name.to_owned();
+
+        // Await for user response
+        tokio::spawn(async move {
+            // Wait for descriptors embedded into the parameters. Other incoming
+            // messages are handled meanwhile
+            let (params, fds) = match &fd_channel {
+                Some(fd_channel) => match fd_channel.receive(params).await {
+                    Ok(received) => received,
+                    Err(err) => {
+                        warn!(
+                            "Failed to receive `{}` call descriptors: {}",
+                            method_name, err
+                        );
+
+                        active_calls.write().unwrap().remove(&call_key);
+                        handle.reply(&BusError::Internal.into_message(seq)).await;
+                        return;
+                    }
+                },
+                None => (params, MessageFds::default()),
+            };
 
             // Call user
-            method.send((params.clone(), tx)).await.unwrap();
-            // Await for his respons
-            rx.await.unwrap().into_message(seq)
-        } else {
-            BusError::NotRegistered.into_message(seq)
+            slot.send((params, fds, context, tx));
+
+            // Caller won't wait for the response after the deadline
+            let timeout = deadline.map(|deadline| {
+                deadline
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default()
+            });
+
+            let response = match timeout {
+                Some(timeout) => time::timeout(timeout, rx).await.unwrap_or_else(|_| {
+                    debug!(
+                        "Call {} from `{}` exceeded deadline",
+                        call_key.1, call_key.0
+                    );
+
+                    cancellation.cancel();
+                    Ok((Response::Error(BusError::Timeout), MessageFds::default()))
+                }),
+                None => rx.await,
+            };
+
+            active_calls.write().unwrap().remove(&call_key);
+
+            let response = match (response, fd_channel) {
+                // Pass descriptors embedded into the return value
+                (Ok((Response::Return(data), fds)), Some(fd_channel)) => {
+                    match fd_channel.send(data, &fds).await {
+                        Ok(data) => Response::Return(data).into_message(seq),
+                        Err(err) => {
+                            warn!("Failed to send return value descriptors: {}", err);
+                            BusError::Internal.into_message(seq)
+                        }
+                    }
+                }
+                (Ok((response, _)), _) => response.into_message(seq),
+                (Err(_), _) => BusError::Internal.into_message(seq),
+            };
+
+            handle.reply(&response).await;
+        });
+    }
+
+    /// Handle call cancellation request. Cancels [CallContext::cancellation]
+    /// token of the call if it's still running
+    pub fn handle_cancel(&self, caller_name: &str, call_seq: u64) {
+        let call_key = (caller_name.to_owned(), call_seq);
+
+        let cancellation = match self.active_calls.read().unwrap().get(&call_key) {
+            Some(cancellation) => Some(cancellation.clone()),
+            None => self
+                .active_streams
+                .read()
+                .unwrap()
+                .get(&call_key)
+                .map(|control| control.cancellation.clone()),
         };
 
-        handle.reply(&response);
+        // Opener dropped the channel. Stop both directions
+        let cancellation = cancellation.or_else(|| {
+            self.active_channels
+                .write()
+                .unwrap()
+                .remove(&call_key)
+                .map(|state| {
+                    state.send_credits.close();
+                    state.cancellation
+                })
+        });
+
+        match cancellation {
+            Some(cancellation) => {
+                debug!("Service `{}` cancelled call {}", caller_name, call_seq);
+                cancellation.cancel();
+            }
+            None => trace!(
+                "Service `{}` cancelled call {}, which is not running",
+                caller_name,
+                call_seq
+            ),
+        }
     }
 
-    /// Handle incoming method call
-    pub fn handle_inspect_call(&self, seq: u64) -> Message {
-        Response::Return(bson::to_bson(&*self.inspect_data.read().unwrap()).unwrap())
-            .into_message(seq)
+    /// Handle incoming streaming method call. Items are sent from a separate task
+    /// using *peer_sender*
+    pub async fn handle_stream_call(
+        &self,
+        method_name: &str,
+        params: Bson,
+        window: u32,
+        context: CallContext,
+        handle: &mut MessageHandle,
+        peer_sender: RpcSender,
+    ) {
+        debug!(
+            "Service `{}` requested streaming method `{}` call",
+            context.caller_name, method_name
+        );
+
+        let seq = context.seq;
+        let call_key = (context.caller_name.clone(), context.call_seq);
+        let control = StreamControl::new(window, context.cancellation.clone());
+
+        let handler = self
+            .stream_methods
+            .read()
+            .unwrap()
+            .get(method_name)
+            .cloned();
+
+        let stream = match handler.map(|handler| handler(params, context)) {
+            Some(Ok(stream)) => stream,
+            Some(Err(err)) => {
+                handle.reply(&err.into_message(seq)).await;
+                return;
+            }
+            None => {
+                handle
+                    .reply(&BusError::NotRegistered.into_message(seq))
+                    .await;
+                return;
+            }
+        };
+
+        self.active_streams
+            .write()
+            .unwrap()
+            .insert(call_key.clone(), control.clone());
+
+        // Confirm the call. Items will follow
+        handle.reply(&Response::Ok.into_message(seq)).await;
+
+        let active_streams = self.active_streams.clone();
+        start_stream_task(stream, seq, peer_sender, control, move || {
+            active_streams.write().unwrap().remove(&call_key);
+        });
+    }
+
+    /// Handle stream credit from a caller, which allows to send more stream or channel items
+    pub fn handle_stream_credit(&self, caller_name: &str, call_seq: u64, credits: u32) {
+        let call_key = (caller_name.to_owned(), call_seq);
+
+        if let Some(control) = self.active_streams.read().unwrap().get(&call_key) {
+            control.credits.add_permits(credits as usize);
+        } else if let Some(state) = self.active_channels.read().unwrap().get(&call_key) {
+            state.send_credits.add_permits(credits as usize);
+        }
+    }
+
+    /// Handle incoming channel open request. The handler is run in a separate task,
+    /// and sends its items using *peer_sender*
+    pub async fn handle_open_channel(
+        &self,
+        channel_name: &str,
+        init: Bson,
+        window: u32,
+        context: CallContext,
+        handle: &mut MessageHandle,
+        peer_sender: RpcSender,
+    ) {
+        debug!(
+            "Service `{}` requested channel `{}`",
+            context.caller_name, channel_name
+        );
+
+        let seq = context.seq;
+        let call_key = (context.caller_name.clone(), context.call_seq);
+        let cancellation = context.cancellation.clone();
+        let window = window.max(1);
+
+        let (data_tx, data_rx) = mpsc::channel(window as usize);
+        let send_credits = Arc::new(Semaphore::new(window as usize));
+
+        let parts = ChannelParts {
+            side: ChannelSide::Callee { seq },
+            peer_sender: peer_sender.clone(),
+            send_credits: send_credits.clone(),
+            data_rx,
+            window,
+            guard: None,
+        };
+
+        let handler = self.channels.read().unwrap().get(channel_name).cloned();
+
+        let future = match handler.map(|handler| handler(init, context, parts)) {
+            Some(Ok(future)) => future,
+            Some(Err(err)) => {
+                handle.reply(&err.into_message(seq)).await;
+                return;
+            }
+            None => {
+                handle
+                    .reply(&BusError::NotRegistered.into_message(seq))
+                    .await;
+                return;
+            }
+        };
+
+        self.active_channels.write().unwrap().insert(
+            call_key.clone(),
+            ChannelState {
+                data_tx: Some(data_tx),
+                send_credits,
+                cancellation,
+            },
+        );
+
+        // Confirm the channel. Items will follow in both directions
+        handle.reply(&Response::Ok.into_message(seq)).await;
+
+        let active_channels = self.active_channels.clone();
+        tokio::spawn(async move {
+            future.await;
+
+            trace!("Channel {} handler finished", seq);
+
+            // Let the opener know nothing else is expected in both directions
+            if active_channels.write().unwrap().remove(&call_key).is_some() {
+                let message = Response::StreamEnd.into_message(seq);
+                let _ = peer_sender.send(bson::to_bson(&message).unwrap()).await;
+            }
+        });
+    }
+
+    /// Handle incoming channel item from the opener
+    pub fn handle_channel_data(&self, caller_name: &str, call_seq: u64, data: Bson) {
+        let data_tx = match self
+            .active_channels
+            .read()
+            .unwrap()
+            .get(&(caller_name.to_owned(), call_seq))
+        {
+            Some(state) => state.data_tx.clone(),
+            None => {
+                trace!(
+                    "Service `{}` sent data into channel {}, which is not open",
+                    caller_name,
+                    call_seq
+                );
+                return;
+            }
+        };
+
+        // The opener can't send more than the window, so the queue can't overflow
+        if let Some(Err(err)) = data_tx.map(|data_tx| data_tx.try_send(Ok(data))) {
+            warn!(
+                "Failed to pass channel {} item to the handler: {}",
+                call_seq,
+                err.to_string()
+            );
+        }
+    }
+
+    /// Handle channel close request from the opener. The handler receives end of the stream
+    pub fn handle_channel_close(&self, caller_name: &str, call_seq: u64) {
+        if let Some(state) = self
+            .active_channels
+            .write()
+            .unwrap()
+            .get_mut(&(caller_name.to_owned(), call_seq))
+        {
+            state.data_tx = None;
+        }
+    }
+
+    /// Handle incoming inspection call of the object at the **path**
+    pub fn handle_inspect_call(&self, seq: u64, path: &str) -> Message {
+        let objects = self.objects.read().unwrap();
+        let inspect_data = self
+            .inspect_data
+            .read()
+            .unwrap()
+            .object_view(path, objects.iter());
+
+        // Intermediate nodes are inspectable to list their children
+        if path != ROOT_OBJECT_PATH && !objects.contains(path) && inspect_data.children.is_empty() {
+            return BusError::NotRegistered.into_message(seq);
+        }
+
+        Response::Return(bson::to_bson(&inspect_data).unwrap()).into_message(seq)
     }
 
     /// Handle incoming signal subscription
@@ -268,6 +1449,8 @@ impl Endpoints {
         &self,
         subscriber_name: &str,
         signal_name: &str,
+        subscription_seq: u64,
+        filter: Option<Filter>,
         handle: &mut MessageHandle,
         peer: &Peer,
     ) {
@@ -279,8 +1462,12 @@ impl Endpoints {
         let seq = handle.id();
         let signal = self.signals.read().unwrap().get(signal_name).cloned();
 
-        let response = if let Some(signal_sender) = signal {
-            peer.start_signal_sending_task(signal_sender.subscribe(), seq);
+        let response = if let Some(channel) = signal {
+            peer.start_signal_sending_task(
+                channel.subscribe(filter),
+                seq,
+                self.track_subscription(subscriber_name, subscription_seq),
+            );
             Response::Ok.into_message(seq)
         } else {
             BusError::NotRegistered.into_message(seq)
@@ -294,6 +1481,7 @@ impl Endpoints {
         &self,
         subscriber_name: &str,
         state_name: &str,
+        subscription_seq: u64,
         handle: &mut MessageHandle,
         peer: &Peer,
     ) {
@@ -305,10 +1493,14 @@ impl Endpoints {
         let seq = handle.id();
         let state = self.states.read().unwrap().get(state_name).cloned();
 
-        let response = if let Some((state_change_sender, value_watch)) = state {
+        let response = if let Some((channel, value_watch)) = state {
             let current_value = value_watch.borrow().clone();
 
-            peer.start_signal_sending_task(state_change_sender.subscribe(), seq);
+            peer.start_signal_sending_task(
+                channel.subscribe(None),
+                seq,
+                self.track_subscription(subscriber_name, subscription_seq),
+            );
             Response::StateChanged(current_value).into_message(seq)
         } else {
             BusError::NotRegistered.into_message(seq)
@@ -316,4 +1508,52 @@ impl Endpoints {
 
         handle.reply(&response);
     }
+
+    /// Handle subscriber dropping a signal subscription or a state watch.
+    /// Stops the task sending updates to the subscriber
+    pub fn handle_unsubscribe(&self, subscriber_name: &str, subscription_seq: u64) {
+        let subscription = self
+            .active_subscriptions
+            .write()
+            .unwrap()
+            .remove(&(subscriber_name.to_owned(), subscription_seq));
+
+        match subscription {
+            Some(cancellation) => {
+                debug!(
+                    "Service `{}` dropped subscription {}",
+                    subscriber_name, subscription_seq
+                );
+                cancellation.cancel();
+            }
+            None => trace!(
+                "Service `{}` dropped subscription {}, which is not active",
+                subscriber_name,
+                subscription_seq
+            ),
+        }
+    }
+
+    /// Register new subscription to be able to stop it on unsubscribe.
+    /// **Returns** a token, which the sending task watches
+    fn track_subscription(
+        &self,
+        subscriber_name: &str,
+        subscription_seq: u64,
+    ) -> CancellationToken {
+        let cancellation = CancellationToken::new();
+        let mut subscriptions = self.active_subscriptions.write().unwrap();
+
+        // Sending tasks cancel their tokens when finished
+        subscriptions.retain(|_, cancellation| !cancellation.is_cancelled());
+        // Renewed subscriptions replace the ones made before a reconnect
+        if let Some(previous) = subscriptions.insert(
+            (subscriber_name.to_owned(), subscription_seq),
+            cancellation.clone(),
+        ) {
+            previous.cancel();
+        }
+
+        cancellation
+    }
 }
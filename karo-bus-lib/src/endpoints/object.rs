@@ -0,0 +1,221 @@
+use std::future::Future;
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio_stream::Stream;
+
+use karo_bus_common::{object_path, schema::BusSchema};
+
+use crate::{
+    channel::{ChannelReceiver, ChannelSender},
+    endpoints::{
+        context::CallContext,
+        method::MethodOptions,
+        registration::Registration,
+        signal::{Signal, SignalOptions},
+        state::{State, StateOptions},
+        Endpoints,
+    },
+};
+
+/// Service object at an object path, e.g. `/devices/eth0`. Each object has its own
+/// methods, signals, and states, addressed by peers with [crate::Peer::object].
+/// Create with [crate::Bus::object]. Dropping the handle unregisters the object
+pub struct Object {
+    /// Object path
+    path: String,
+    /// Service endpoints. Object endpoints are registered with qualified names
+    endpoints: Endpoints,
+}
+
+impl Object {
+    pub(crate) fn new(path: &str, endpoints: Endpoints) -> Self {
+        Self {
+            path: path.into(),
+            endpoints,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Register object method. See [crate::Bus::register_method]
+    pub fn register_method<P, R, Ret>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        Ret: Future<Output = R> + Send + 'static,
+    {
+        self.endpoints
+            .register_method(&self.qualify(method_name), callback)
+    }
+
+    /// Register object method with given execution **options**.
+    /// See [crate::Bus::register_method_with_options]
+    pub fn register_method_with_options<P, R, Ret>(
+        &mut self,
+        method_name: &str,
+        options: MethodOptions,
+        callback: impl Fn(P) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        Ret: Future<Output = R> + Send + 'static,
+    {
+        self.endpoints
+            .register_method_with_options(&self.qualify(method_name), options, callback)
+    }
+
+    /// Register object method, which receives [CallContext] along with the parameters.
+    /// See [crate::Bus::register_method_with_context]
+    pub fn register_method_with_context<P, R, Ret>(
+        &mut self,
+        method_name: &str,
+        options: MethodOptions,
+        callback: impl Fn(P, CallContext) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        Ret: Future<Output = R> + Send + 'static,
+    {
+        self.endpoints
+            .register_method_with_context(&self.qualify(method_name), options, callback)
+    }
+
+    /// Register object method, which may fail. See [crate::Bus::register_fallible_method]
+    pub fn register_fallible_method<P, R, E, Ret>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        E: Serialize + Send + 'static,
+        Ret: Future<Output = std::result::Result<R, E>> + Send + 'static,
+    {
+        self.endpoints
+            .register_fallible_method(&self.qualify(method_name), callback)
+    }
+
+    /// Register object streaming method. See [crate::Bus::register_streaming_method]
+    pub fn register_streaming_method<P, R, S>(
+        &mut self,
+        method_name: &str,
+        callback: impl Fn(P) -> S + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        S: Stream<Item = R> + Send + 'static,
+    {
+        self.endpoints
+            .register_streaming_method(&self.qualify(method_name), callback)
+    }
+
+    /// Register object duplex channel. See [crate::Bus::register_channel]
+    pub fn register_channel<I, In, Out, Ret>(
+        &mut self,
+        channel_name: &str,
+        callback: impl Fn(I, ChannelSender<Out>, ChannelReceiver<In>) -> Ret + Send + Sync + 'static,
+    ) -> Result<Registration>
+    where
+        I: DeserializeOwned + Send + 'static,
+        In: DeserializeOwned + Send + 'static,
+        Out: Serialize + Send + 'static,
+        Ret: Future<Output = ()> + Send + 'static,
+    {
+        self.endpoints
+            .register_channel(&self.qualify(channel_name), callback)
+    }
+
+    /// Register object signal. See [crate::Bus::register_signal]
+    pub fn register_signal<T>(&mut self, signal_name: &str) -> Result<Signal<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.endpoints.register_signal(&self.qualify(signal_name))
+    }
+
+    /// Register object signal with [SignalOptions]. See [crate::Bus::register_signal]
+    pub fn register_signal_with_options<T>(
+        &mut self,
+        signal_name: &str,
+        options: SignalOptions,
+    ) -> Result<Signal<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.endpoints
+            .register_signal_with_options(&self.qualify(signal_name), options)
+    }
+
+    /// Register object state. See [crate::Bus::register_state]
+    pub fn register_state<T>(&mut self, state_name: &str, initial_value: T) -> Result<State<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.endpoints
+            .register_state(&self.qualify(state_name), initial_value)
+    }
+
+    /// Register object state with [StateOptions]. See [crate::Bus::register_state]
+    pub fn register_state_with_options<T>(
+        &mut self,
+        state_name: &str,
+        initial_value: T,
+        options: StateOptions,
+    ) -> Result<State<T>>
+    where
+        T: Serialize + 'static,
+    {
+        self.endpoints.register_state_with_options(
+            &self.qualify(state_name),
+            initial_value,
+            options,
+        )
+    }
+
+    /// Describe registered method. See [crate::Bus::describe_method]
+    pub fn describe_method<P: BusSchema, R: BusSchema>(&mut self, method_name: &str) -> Result<()> {
+        self.endpoints
+            .describe_method::<P, R>(&self.qualify(method_name))
+    }
+
+    /// Describe registered signal. See [crate::Bus::describe_signal]
+    pub fn describe_signal<T: BusSchema>(&mut self, signal_name: &str) -> Result<()> {
+        self.endpoints
+            .describe_signal::<T>(&self.qualify(signal_name))
+    }
+
+    /// Describe registered state. See [crate::Bus::describe_state]
+    pub fn describe_state<T: BusSchema>(&mut self, state_name: &str) -> Result<()> {
+        self.endpoints
+            .describe_state::<T>(&self.qualify(state_name))
+    }
+
+    /// Unregister the object and all its endpoints. Peers get
+    /// [karo_bus_common::errors::Error::NotRegistered] calling the object afterwards
+    pub fn unregister(mut self) -> Result<()> {
+        self.endpoints.unregister_object(&self.path)
+    }
+
+    fn qualify(&self, endpoint_name: &str) -> String {
+        object_path::qualify(&self.path, endpoint_name)
+    }
+}
+
+impl Drop for Object {
+    fn drop(&mut self) {
+        if self.endpoints.has_object(&self.path) {
+            let _ = self.endpoints.unregister_object(&self.path);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::Endpoints;
+
+/// Source of registration ids. Used to tell a registration from a later one
+/// made under the same name
+static NEXT_REGISTRATION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Registered endpoint kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum RegistrationKind {
+    /// Method or streaming method
+    Method,
+    /// Duplex channel
+    Channel,
+}
+
+/// Method or channel registration handle.
+/// Dropping the handle unregisters the endpoint. Use [Registration::detach] to keep
+/// the endpoint registered for the bus lifetime
+#[must_use = "dropping the registration unregisters the endpoint"]
+pub struct Registration {
+    /// Registered endpoint kind
+    kind: RegistrationKind,
+    /// Registered endpoint name
+    name: String,
+    /// Registration id. The endpoint is unregistered only if it's still
+    /// registered with the id
+    id: u64,
+    /// Service endpoints to unregister the endpoint from. None if detached
+    endpoints: Option<Endpoints>,
+}
+
+impl Registration {
+    pub(crate) fn new(kind: RegistrationKind, name: String, endpoints: Endpoints) -> Self {
+        Self {
+            kind,
+            name,
+            id: NEXT_REGISTRATION_ID.fetch_add(1, Ordering::Relaxed),
+            endpoints: Some(endpoints),
+        }
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Registered endpoint name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Unregister the endpoint. Calls being handled and open channels are finished
+    pub fn unregister(self) {}
+
+    /// Drop the handle keeping the endpoint registered. The endpoint can still be
+    /// removed by name, e.g. with [crate::Bus::unregister_method]
+    pub fn detach(mut self) {
+        self.endpoints = None;
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Some(endpoints) = &self.endpoints {
+            endpoints.release_registration(self.kind, &self.name, self.id);
+        }
+    }
+}
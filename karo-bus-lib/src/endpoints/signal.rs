@@ -1,39 +1,279 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::Arc};
 
+use anyhow::Result;
 use log::error;
 use serde::Serialize;
-use tokio::sync::broadcast::Sender as BroadcastSender;
+use tokio::sync::{
+    broadcast::{self, error::RecvError, Receiver as BroadcastReceiver, Sender as BroadcastSender},
+    Mutex as TokioMutex, Notify,
+};
 
-use karo_bus_common::messages::{IntoMessage, Message, Response};
+use karo_bus_common::{
+    errors::Error as BusError,
+    filter::Filter,
+    inspect_data::EndpointMetadata,
+    messages::{IntoMessage, Message, MessageBody, Response},
+};
 
-/// Signal handle, which can be used for signal emission
-pub struct Signal<T: Serialize> {
+use crate::{
+    endpoints::Endpoints,
+    fd::{self, MessageFds},
+};
+
+/// Default number of emissions a subscriber can fall behind
+pub const DEFAULT_SIGNAL_CAPACITY: usize = 5;
+
+/// What happens to a subscriber, which falls behind by the signal capacity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// Drop the oldest emissions. The subscriber gets the number of dropped
+    /// emissions as [crate::SubscriptionEvent::Lagged]
+    #[default]
+    DropOldest,
+    /// [Signal::emit_async] waits until the slowest subscriber has room.
+    /// [Signal::emit] can't wait and fails with [BusError::Busy] instead
+    Block,
+    /// Report the lag to the subscriber and end the subscription
+    Disconnect,
+}
+
+/// Signal registration options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignalOptions {
+    /// Description, version and deprecation returned through the inspection
+    pub metadata: EndpointMetadata,
+    /// Number of emissions a subscriber can fall behind. At least 1
+    pub capacity: usize,
+    /// What happens to lagging subscribers
+    pub lag_policy: LagPolicy,
+}
+
+impl Default for SignalOptions {
+    fn default() -> Self {
+        Self {
+            metadata: EndpointMetadata::default(),
+            capacity: DEFAULT_SIGNAL_CAPACITY,
+            lag_policy: LagPolicy::default(),
+        }
+    }
+}
+
+impl SignalOptions {
+    /// Set signal metadata
+    pub fn metadata(mut self, metadata: EndpointMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Set number of emissions a subscriber can fall behind
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Set what happens to lagging subscribers
+    pub fn lag_policy(mut self, lag_policy: LagPolicy) -> Self {
+        self.lag_policy = lag_policy;
+        self
+    }
+}
+
+/// Signal emission or state change sent to subscribers. Descriptors embedded into
+/// the value are shared by the subscribers and closed once every subscriber has sent
+/// or skipped the emission, or fell behind it
+#[derive(Clone)]
+pub(crate) struct Emission {
+    pub(crate) message: Message,
+    pub(crate) fds: Arc<MessageFds>,
+}
+
+impl From<Message> for Emission {
+    fn from(message: Message) -> Self {
+        Self {
+            message,
+            fds: Arc::default(),
+        }
+    }
+}
+
+/// Registered signal or state channel shared by the handle and the subscription tasks
+#[derive(Clone)]
+pub(crate) struct SignalChannel {
     /// Sender used by subscribers to reseive emissions
-    tx: BroadcastSender<Message>,
+    pub(crate) tx: BroadcastSender<Emission>,
+    /// Number of emissions a subscriber can fall behind
+    capacity: usize,
+    pub(crate) lag_policy: LagPolicy,
+    /// Notified every time a subscriber takes an emission. Blocked emitters wait for it
+    drained: Arc<Notify>,
+    /// Taken by blocked emitters, so concurrent emitters don't overrun subscribers
+    emitting: Arc<TokioMutex<()>>,
+}
+
+impl SignalChannel {
+    pub(crate) fn new(capacity: usize, lag_policy: LagPolicy) -> Self {
+        let capacity = capacity.max(1);
+        let (tx, _rx) = broadcast::channel(capacity);
+
+        Self {
+            tx,
+            capacity,
+            lag_policy,
+            drained: Arc::new(Notify::new()),
+            emitting: Arc::new(TokioMutex::new(())),
+        }
+    }
+
+    /// New subscriber receiver. The receiver skips emissions not matching the **filter**
+    pub(crate) fn subscribe(&self, filter: Option<Filter>) -> SignalReceiver {
+        SignalReceiver {
+            receiver: self.tx.subscribe(),
+            lag_policy: self.lag_policy,
+            drained: self.drained.clone(),
+            filter,
+        }
+    }
+
+    /// If the slowest subscriber has room for one more emission
+    pub(crate) fn has_room(&self) -> bool {
+        self.tx.len() < self.capacity
+    }
+
+    /// Wait until the slowest subscriber has room for one more emission
+    pub(crate) async fn ready(&self) {
+        loop {
+            let drained = self.drained.notified();
+            tokio::pin!(drained);
+            drained.as_mut().enable();
+
+            if self.has_room() {
+                return;
+            }
+
+            drained.await;
+        }
+    }
+}
+
+/// Subscriber side of a [SignalChannel]
+pub(crate) struct SignalReceiver {
+    receiver: BroadcastReceiver<Emission>,
+    pub(crate) lag_policy: LagPolicy,
+    drained: Arc<Notify>,
+    /// Subscriber filter
+    filter: Option<Filter>,
+}
+
+impl SignalReceiver {
+    /// Receive next emission matching the subscriber filter. See [BroadcastReceiver::recv]
+    pub(crate) async fn recv(&mut self) -> Result<Emission, RecvError> {
+        loop {
+            let result = self.receiver.recv().await;
+            self.drained.notify_waiters();
+
+            match (&result, &self.filter) {
+                (Ok(emission), Some(filter)) => match emission.message.body() {
+                    MessageBody::Response(Response::Signal(data)) if !filter.matches(data) => {}
+                    _ => return result,
+                },
+                _ => return result,
+            }
+        }
+    }
+}
+
+/// Signal handle, which can be used for signal emission.
+/// Dropping the handle unregisters the signal
+pub struct Signal<T: Serialize> {
+    /// Channel used by subscribers to reseive emissions
+    channel: SignalChannel,
     /// Registered signal name
     name: String,
+    /// Service endpoints to unregister the signal from
+    endpoints: Endpoints,
     _phantom: PhantomData<T>,
 }
 
 impl<T: Serialize> Signal<T> {
-    pub(crate) fn new(name: String, tx: BroadcastSender<Message>) -> Self {
+    pub(crate) fn new(name: String, channel: SignalChannel, endpoints: Endpoints) -> Self {
         Self {
-            tx,
+            channel,
             name,
+            endpoints,
             _phantom: PhantomData,
         }
     }
 
-    /// Emit signal with value of type **T**
-    pub fn emit(&self, value: T) {
-        if self.tx.receiver_count() == 0 {
-            return;
+    /// Unregister the signal. Subscribers get end of the subscription stream
+    pub fn unregister(self) {}
+
+    /// Number of active subscriptions. Subscribers dropping their streams are
+    /// not counted anymore
+    pub fn subscriber_count(&self) -> usize {
+        self.channel.tx.receiver_count()
+    }
+
+    /// Emit signal with value of type **T**. Lagging subscribers are handled
+    /// according to the signal [LagPolicy]. If the signal is registered with
+    /// [LagPolicy::Block] and the slowest subscriber has no room for the emission,
+    /// fails with [BusError::Busy]. Use [Signal::emit_async] to wait instead
+    pub fn emit(&self, value: T) -> Result<()> {
+        if self.channel.lag_policy == LagPolicy::Block {
+            let _emitting = self
+                .channel
+                .emitting
+                .try_lock()
+                .map_err(|_| BusError::Busy)?;
+
+            if !self.channel.has_room() {
+                return Err(BusError::Busy.into());
+            }
+
+            return self.send(&value);
+        }
+
+        self.send(&value)
+    }
+
+    /// Emit signal with value of type **T**. If the signal is registered with
+    /// [LagPolicy::Block], waits until the slowest subscriber has room for the emission
+    pub async fn emit_async(&self, value: T) -> Result<()> {
+        if self.channel.lag_policy == LagPolicy::Block {
+            let _emitting = self.channel.emitting.lock().await;
+            self.channel.ready().await;
+
+            return self.send(&value);
+        }
+
+        self.send(&value)
+    }
+
+    /// Serialize and send the value to subscribers
+    fn send(&self, value: &T) -> Result<()> {
+        let tx = &self.channel.tx;
+
+        if tx.receiver_count() == 0 {
+            return Ok(());
         }
 
-        let message = Response::Signal(bson::to_bson(&value).unwrap()).into_message(0xFEEDC0DE);
+        let (data, fds) = fd::to_bson(value)?;
+
+        let emission = Emission {
+            message: Response::Signal(data).into_message(0xFEEDC0DE),
+            fds: Arc::new(fds),
+        };
 
-        if let Err(err) = self.tx.send(message) {
+        // Subscribers may have gone meanwhile
+        if let Err(err) = tx.send(emission) {
             error!("Failed to emit signal `{}`: {:?}", self.name, err);
         }
+
+        Ok(())
+    }
+}
+
+impl<T: Serialize> Drop for Signal<T> {
+    fn drop(&mut self) {
+        self.endpoints.release_signal(&self.name, &self.channel.tx);
     }
 }
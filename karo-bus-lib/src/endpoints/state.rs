@@ -1,47 +1,133 @@
+use anyhow::Result;
 use bson::Bson;
 use log::{error, warn};
 use serde::Serialize;
-use tokio::sync::{broadcast::Sender as BroadcastSender, watch::Sender as WatchSender};
+use tokio::sync::watch::Sender as WatchSender;
 
-use karo_bus_common::messages::{IntoMessage, Message, Response};
+use karo_bus_common::{
+    errors::Error as BusError,
+    inspect_data::EndpointMetadata,
+    messages::{IntoMessage, Response},
+};
+
+use crate::endpoints::{
+    signal::{LagPolicy, SignalChannel, DEFAULT_SIGNAL_CAPACITY},
+    Endpoints,
+};
 
 pub type ExternalStateGetter = Box<dyn Fn() -> Bson + Send + Sync>;
 
+/// State registration options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateOptions {
+    /// Description, version and deprecation returned through the inspection
+    pub metadata: EndpointMetadata,
+    /// Number of state changes a watcher can fall behind. At least 1
+    pub capacity: usize,
+    /// What happens to lagging watchers
+    pub lag_policy: LagPolicy,
+}
+
+impl Default for StateOptions {
+    fn default() -> Self {
+        Self {
+            metadata: EndpointMetadata::default(),
+            capacity: DEFAULT_SIGNAL_CAPACITY,
+            lag_policy: LagPolicy::default(),
+        }
+    }
+}
+
+impl StateOptions {
+    /// Set state metadata
+    pub fn metadata(mut self, metadata: EndpointMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Set number of state changes a watcher can fall behind
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Set what happens to lagging watchers
+    pub fn lag_policy(mut self, lag_policy: LagPolicy) -> Self {
+        self.lag_policy = lag_policy;
+        self
+    }
+}
+
 /// State handle, which can be used for state changes notifications.
-/// Locally can be managed using [State::set] and [State::get] methods
+/// Locally can be managed using [State::set] and [State::get] methods.
+/// Dropping the handle unregisters the state
 pub struct State<T: Serialize> {
-    /// Sender used by subscribers to receive state changes
-    tx: BroadcastSender<Message>,
+    /// Channel used by subscribers to receive state changes
+    channel: SignalChannel,
     /// Watch to notify service about current state value change
     watch_tx: WatchSender<Bson>,
     /// Registered state name
     name: String,
     /// Current value
     value: T,
+    /// Service endpoints to unregister the state from
+    endpoints: Endpoints,
 }
 
 impl<T: Serialize> State<T> {
     pub(crate) fn new(
         name: String,
         value: T,
-        tx: BroadcastSender<Message>,
+        channel: SignalChannel,
         watch_tx: WatchSender<Bson>,
+        endpoints: Endpoints,
     ) -> Self {
         Self {
-            tx,
+            channel,
             watch_tx,
             name,
             value,
+            endpoints,
         }
     }
 
-    /// Set new state value. Will notify subscribers about the ctate change
-    pub fn set(&mut self, value: T) {
-        if self.tx.receiver_count() == 0 {
-            return;
+    /// Unregister the state. Watchers get end of the watch stream
+    pub fn unregister(self) {}
+
+    /// Number of active watches. Watchers dropping their streams are not counted anymore
+    pub fn watcher_count(&self) -> usize {
+        self.channel.tx.receiver_count()
+    }
+
+    /// Set new state value. Will notify subscribers about the ctate change.
+    /// If the state is registered with [LagPolicy::Block] and the slowest watcher
+    /// has no room for the change, fails with [BusError::Busy] and keeps the
+    /// current value. Use [State::set_async] to wait instead
+    pub fn set(&mut self, value: T) -> Result<()> {
+        if self.channel.lag_policy == LagPolicy::Block && !self.channel.has_room() {
+            return Err(BusError::Busy.into());
+        }
+
+        self.update(value)
+    }
+
+    /// Set new state value. If the state is registered with [LagPolicy::Block],
+    /// waits until the slowest watcher has room for the change
+    pub async fn set_async(&mut self, value: T) -> Result<()> {
+        if self.channel.lag_policy == LagPolicy::Block {
+            self.channel.ready().await;
         }
 
-        let bson = bson::to_bson(&value).unwrap();
+        self.update(value)
+    }
+
+    /// Update current value and notify watchers
+    fn update(&mut self, value: T) -> Result<()> {
+        if self.channel.tx.receiver_count() == 0 {
+            return Ok(());
+        }
+
+        let bson = bson::to_bson(&value)?;
         self.value = value;
 
         // First notify watch so new clients could get current value
@@ -54,9 +140,11 @@ impl<T: Serialize> State<T> {
 
         let message = Response::StateChanged(bson).into_message(0xFEEDC0DE);
 
-        if let Err(err) = self.tx.send(message) {
+        if let Err(err) = self.channel.tx.send(message.into()) {
             error!("Failed to send state schange `{}`: {:?}", self.name, err);
         }
+
+        Ok(())
     }
 
     /// Get current state value
@@ -64,3 +152,9 @@ impl<T: Serialize> State<T> {
         &self.value
     }
 }
+
+impl<T: Serialize> Drop for State<T> {
+    fn drop(&mut self) {
+        self.endpoints.release_state(&self.name, &self.channel.tx);
+    }
+}
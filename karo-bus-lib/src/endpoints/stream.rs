@@ -0,0 +1,94 @@
+use std::{pin::Pin, sync::Arc};
+
+use bson::Bson;
+use log::*;
+use tokio::sync::Semaphore;
+use tokio_stream::{Stream, StreamExt};
+
+use karo_bus_common::{
+    errors::Error as BusError,
+    messages::{IntoMessage, Response},
+};
+use karo_common_rpc::rpc_sender::RpcSender;
+
+use super::context::{CallContext, CancellationToken};
+
+/// Default number of stream items a callee may send before receiving a credit
+pub const DEFAULT_STREAM_WINDOW: u32 = 16;
+
+/// Stream of responses produced by a streaming method handler
+pub(crate) type ResponseStream = Pin<Box<dyn Stream<Item = Response> + Send>>;
+/// Type erased streaming method handler. Fails if parameters can't be deserialized
+pub(crate) type StreamHandler =
+    Arc<dyn Fn(Bson, CallContext) -> Result<ResponseStream, BusError> + Send + Sync>;
+
+/// Running stream call state
+#[derive(Clone)]
+pub(crate) struct StreamControl {
+    /// Number of items the callee is allowed to send
+    pub credits: Arc<Semaphore>,
+    /// Cancelled if the caller dropped the stream
+    pub cancellation: CancellationToken,
+}
+
+impl StreamControl {
+    pub fn new(window: u32, cancellation: CancellationToken) -> Self {
+        Self {
+            credits: Arc::new(Semaphore::new(window.max(1) as usize)),
+            cancellation,
+        }
+    }
+}
+
+/// Start task, which sends stream items to the caller as long as it has credits.
+/// Calls *on_finish* when the stream ends or the caller goes away
+pub(crate) fn start_stream_task(
+    mut stream: ResponseStream,
+    seq: u64,
+    peer_sender: RpcSender,
+    control: StreamControl,
+    on_finish: impl FnOnce() + Send + 'static,
+) {
+    tokio::spawn(async move {
+        loop {
+            // Wait until the caller is ready to receive next item
+            tokio::select! {
+                _ = control.cancellation.cancelled() => break,
+                permit = control.credits.acquire() => match permit {
+                    Ok(permit) => permit.forget(),
+                    Err(_) => break,
+                },
+            }
+
+            let item = tokio::select! {
+                _ = control.cancellation.cancelled() => break,
+                item = stream.next() => item,
+            };
+
+            let (response, last) = match item {
+                Some(response) => {
+                    let last = matches!(response, Response::Error(_) | Response::MethodError(_));
+                    (response, last)
+                }
+                None => (Response::StreamEnd, true),
+            };
+
+            let message = response.into_message(seq);
+            if peer_sender
+                .send(bson::to_bson(&message).unwrap())
+                .await
+                .is_err()
+            {
+                warn!("Failed to send stream item to a caller. Probably closed. Stopping stream");
+                break;
+            }
+
+            if last {
+                break;
+            }
+        }
+
+        trace!("Stream call {} finished", seq);
+        on_finish();
+    });
+}
@@ -0,0 +1,37 @@
+use std::fmt::Display;
+
+use bson::Bson;
+use serde::de::DeserializeOwned;
+
+/// Error returned by a remote method handler registered with
+/// [crate::Bus::register_fallible_method]. `Peer::call` returns the error wrapped
+/// into [anyhow::Error]. Use `downcast_ref::<MethodError>()` to get it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodError {
+    /// Serialized user error
+    data: Bson,
+}
+
+impl MethodError {
+    pub(crate) fn new(data: Bson) -> Self {
+        Self { data }
+    }
+
+    /// Raw error value
+    pub fn data(&self) -> &Bson {
+        &self.data
+    }
+
+    /// Deserialize the error into a method error type **E**
+    pub fn deserialize<E: DeserializeOwned>(&self) -> Result<E, bson::de::Error> {
+        bson::from_bson(self.data.clone())
+    }
+}
+
+impl Display for MethodError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Method returned an error: {}", self.data)
+    }
+}
+
+impl std::error::Error for MethodError {}
@@ -0,0 +1,11 @@
+/// Bus connection events. See [crate::Bus::events]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusEvent {
+    /// Connection to the hub is lost. Contains the reason the hub has sent,
+    /// or `Connection lost` if the connection closed without one.
+    /// Existing peer connections keep working, but new connections can't be made
+    /// until the bus reconnects
+    HubDisconnected(String),
+    /// The bus reconnected and registered at the hub again
+    HubReconnected,
+}
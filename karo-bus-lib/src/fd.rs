@@ -0,0 +1,237 @@
+use std::{
+    cell::RefCell,
+    os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd},
+};
+
+use bson::Bson;
+use karo_bus_common::schema::{BusSchema, Schema};
+use serde::{de, de::DeserializeOwned, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Document key, which marks a descriptor placeholder in a message payload
+const FD_PLACEHOLDER_KEY: &str = "$karo_bus_fd";
+
+thread_local! {
+    /// Descriptors of the message being serialized or deserialized on this thread
+    static MESSAGE_FDS: RefCell<Option<MessageFds>> = RefCell::new(None);
+}
+
+/// Descriptors embedded into a single message payload. Placeholders refer the descriptors
+/// by their index in the message, so a payload can't claim descriptors of other messages.
+/// Descriptors, which are never sent or deserialized, are closed on drop
+#[derive(Debug, Default)]
+pub(crate) struct MessageFds {
+    fds: Vec<Option<OwnedFd>>,
+}
+
+impl MessageFds {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.fds.is_empty()
+    }
+
+    /// Add the descriptor. Returns a key to refer the descriptor
+    pub(crate) fn push(&mut self, fd: OwnedFd) -> i64 {
+        self.fds.push(Some(fd));
+        self.fds.len() as i64 - 1
+    }
+
+    /// Borrow the descriptor to send it
+    pub(crate) fn get(&self, key: i64) -> Option<BorrowedFd<'_>> {
+        let fd = self.fds.get(usize::try_from(key).ok()?)?;
+        fd.as_ref().map(OwnedFd::as_fd)
+    }
+
+    /// Take the descriptor. Every descriptor can be taken only once
+    fn take(&mut self, key: i64) -> Option<OwnedFd> {
+        self.fds.get_mut(usize::try_from(key).ok()?)?.take()
+    }
+}
+
+/// Restores previous message descriptors when serialization or deserialization
+/// is finished, even if it panics
+struct MessageFdsScope {
+    previous: Option<MessageFds>,
+}
+
+impl MessageFdsScope {
+    fn enter(fds: MessageFds) -> Self {
+        Self {
+            previous: MESSAGE_FDS.with(|current| current.replace(Some(fds))),
+        }
+    }
+
+    /// Take descriptors left in the scope
+    fn exit(self) -> MessageFds {
+        MESSAGE_FDS
+            .with(|current| current.take())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for MessageFdsScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        MESSAGE_FDS.with(|current| current.replace(previous));
+    }
+}
+
+/// Serialize the **value** collecting embedded [BusFd] descriptors. If serialization
+/// fails, already collected descriptors are closed
+pub(crate) fn to_bson<T: Serialize + ?Sized>(
+    value: &T,
+) -> Result<(Bson, MessageFds), bson::ser::Error> {
+    let scope = MessageFdsScope::enter(MessageFds::default());
+    let data = bson::to_bson(value);
+    let fds = scope.exit();
+
+    data.map(|data| (data, fds))
+}
+
+/// Deserialize the **data** taking embedded [BusFd] descriptors from the message **fds**.
+/// Descriptors not referred by the data are closed
+pub(crate) fn from_bson<T: DeserializeOwned>(
+    data: Bson,
+    fds: MessageFds,
+) -> Result<T, bson::de::Error> {
+    let scope = MessageFdsScope::enter(fds);
+    let value = bson::from_bson(data);
+    scope.exit();
+
+    value
+}
+
+/// Placeholder, which replaces a descriptor in a message payload
+#[derive(Serialize, Deserialize)]
+struct Placeholder {
+    #[serde(rename = "$karo_bus_fd")]
+    key: i64,
+}
+
+/// File descriptor, which can be used in method parameters, return values and signals.
+/// The bus passes descriptors to the peer out of band using SCM_RIGHTS.\
+/// Serializing the value duplicates the descriptor, so the sender keeps its own copy.
+/// The value can be serialized only to be sent over the bus
+#[derive(Debug)]
+pub struct BusFd {
+    fd: OwnedFd,
+}
+
+impl BusFd {
+    pub fn new(fd: impl Into<OwnedFd>) -> Self {
+        Self { fd: fd.into() }
+    }
+
+    /// Take the descriptor
+    pub fn into_inner(self) -> OwnedFd {
+        self.fd
+    }
+
+    /// Duplicate the descriptor
+    pub fn try_clone(&self) -> std::io::Result<Self> {
+        Ok(Self {
+            fd: self.fd.try_clone()?,
+        })
+    }
+}
+
+impl From<OwnedFd> for BusFd {
+    fn from(fd: OwnedFd) -> Self {
+        Self { fd }
+    }
+}
+
+impl From<BusFd> for OwnedFd {
+    fn from(fd: BusFd) -> Self {
+        fd.fd
+    }
+}
+
+impl AsFd for BusFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for BusFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl BusSchema for BusFd {
+    fn schema() -> Schema {
+        Schema::Fd
+    }
+}
+
+impl Serialize for BusFd {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fd = self.fd.try_clone().map_err(ser::Error::custom)?;
+
+        let key = MESSAGE_FDS
+            .with(|current| current.borrow_mut().as_mut().map(|fds| fds.push(fd)))
+            .ok_or_else(|| {
+                ser::Error::custom("File descriptor can be serialized only to be sent over the bus")
+            })?;
+
+        Placeholder { key }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BusFd {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let placeholder = Placeholder::deserialize(deserializer)?;
+
+        MESSAGE_FDS
+            .with(|current| {
+                current
+                    .borrow_mut()
+                    .as_mut()
+                    .and_then(|fds| fds.take(placeholder.key))
+            })
+            .map(BusFd::from)
+            .ok_or_else(|| de::Error::custom("File descriptor is not received"))
+    }
+}
+
+/// Collect descriptor placeholders from a message payload
+pub(crate) fn placeholders(data: &Bson) -> Vec<i64> {
+    let mut keys = vec![];
+    collect_placeholders(data, &mut keys);
+
+    keys
+}
+
+fn collect_placeholders(data: &Bson, keys: &mut Vec<i64>) {
+    match data {
+        Bson::Document(document) => match document.get_i64(FD_PLACEHOLDER_KEY) {
+            Ok(key) if document.len() == 1 => keys.push(key),
+            _ => document
+                .values()
+                .for_each(|value| collect_placeholders(value, keys)),
+        },
+        Bson::Array(array) => array
+            .iter()
+            .for_each(|value| collect_placeholders(value, keys)),
+        _ => {}
+    }
+}
+
+/// Replace descriptor placeholder keys in a message payload
+pub(crate) fn replace_placeholders(data: &mut Bson, replace: &impl Fn(i64) -> Option<i64>) {
+    match data {
+        Bson::Document(document) => match document.get_i64(FD_PLACEHOLDER_KEY) {
+            Ok(key) if document.len() == 1 => {
+                if let Some(new_key) = replace(key) {
+                    document.insert(FD_PLACEHOLDER_KEY, new_key);
+                }
+            }
+            _ => document
+                .iter_mut()
+                .for_each(|(_, value)| replace_placeholders(value, replace)),
+        },
+        Bson::Array(array) => array
+            .iter_mut()
+            .for_each(|value| replace_placeholders(value, replace)),
+        _ => {}
+    }
+}
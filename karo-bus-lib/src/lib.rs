@@ -1,7 +1,58 @@
+//! Karo platform service bus client library.
+//!
+//! # Migration: endpoint registration handles
+//!
+//! Registering a method or a channel returns a [Registration] handle, and dropping it
+//! unregisters the endpoint. Dropping [Signal] and [State] handles unregisters the signal
+//! and the state as well. Code, which ignored the registration result, e.g.
+//! `bus.register_method("add", add)?;`, now unregisters the method right away.
+//! Keep the handle for as long as the endpoint should be served, or call
+//! [Registration::detach] to keep the endpoint registered for the bus lifetime:
+//!
+//! ```ignore
+//! bus.register_method("add", add)?.detach();
+//! ```
+
 pub mod bus;
+mod channel;
 mod connections;
 mod endpoints;
+pub mod errors;
+pub mod events;
+mod fd;
 mod monitor;
+mod shared_buffer;
 mod utils;
 
+pub use async_trait::async_trait;
 pub use bus::Bus;
+pub use channel::{ChannelReceiver, ChannelSender};
+pub use connections::{
+    call_stream::CallStream, peer::Peer, peer_object::PeerObject, subscription::SubscriptionEvent,
+};
+pub use endpoints::{
+    context::{CallContext, CancellationToken},
+    method::{MethodOptions, MethodPanic},
+    object::Object,
+    registration::Registration,
+    signal::{LagPolicy, Signal, SignalOptions},
+    state::{State, StateOptions},
+};
+pub use errors::MethodError;
+pub use events::BusEvent;
+pub use fd::BusFd;
+pub use karo_bus_common::{
+    filter::Filter,
+    inspect_data::EndpointMetadata,
+    schema::{BusSchema, Schema, SchemaField},
+};
+pub use karo_bus_macros::{interface, BusSchema};
+pub use shared_buffer::{SharedBuffer, SHARED_BUFFER_THRESHOLD};
+
+/// Items used by the code generated with [interface]
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow::Result;
+    pub use async_trait::async_trait;
+    pub use tokio_stream::Stream;
+}
@@ -0,0 +1,275 @@
+use std::{
+    ffi::CStr,
+    fmt::{self, Debug, Formatter},
+    io::{Error as IoError, ErrorKind},
+    num::NonZeroUsize,
+    ops::Deref,
+    os::unix::io::{AsRawFd, FromRawFd, OwnedFd},
+    ptr::NonNull,
+    slice,
+};
+
+use karo_bus_common::schema::{BusSchema, Schema};
+use nix::{
+    fcntl::{fcntl, FcntlArg, SealFlag},
+    sys::{
+        memfd::{memfd_create, MemFdCreateFlag},
+        mman::{mmap, munmap, MapFlags, ProtFlags},
+        stat::fstat,
+    },
+    unistd::ftruncate,
+};
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::fd::BusFd;
+
+/// Buffers of this size and bigger are passed in shared memory
+pub const SHARED_BUFFER_THRESHOLD: usize = 64 * 1024;
+
+/// Document key, which marks a shared memory buffer in a message payload
+const SHARED_BUFFER_KEY: &str = "$karo_bus_shm";
+
+/// Seals, which make shared memory contents immutable. The receiver refuses
+/// to map a buffer without these
+fn required_seals() -> SealFlag {
+    SealFlag::F_SEAL_WRITE | SealFlag::F_SEAL_SHRINK | SealFlag::F_SEAL_GROW
+}
+
+/// Byte buffer, which can be used in method parameters, return values and signals.
+/// Buffers of [SHARED_BUFFER_THRESHOLD] bytes and bigger are placed into a sealed memfd,
+/// and only the descriptor is sent to the peer. The receiver maps the buffer read-only,
+/// so big payloads are not copied through the socket.\
+/// Smaller buffers are sent inline as BSON binary data
+pub struct SharedBuffer {
+    data: BufferData,
+}
+
+enum BufferData {
+    Inline(Vec<u8>),
+    /// Read-only shared memory mapping. We keep the descriptor to forward the buffer
+    Mapped {
+        fd: OwnedFd,
+        ptr: NonNull<u8>,
+        len: usize,
+    },
+}
+
+// Mapping is read-only and sealed, so it's safe to share between threads
+unsafe impl Send for SharedBuffer {}
+unsafe impl Sync for SharedBuffer {}
+
+impl SharedBuffer {
+    /// Create a buffer with a copy of the **data**
+    pub fn new(data: &[u8]) -> Result<Self, IoError> {
+        Self::build(data.len(), |buffer| buffer.copy_from_slice(data))
+    }
+
+    /// Create a buffer of **len** bytes and let **fill** write the contents in place.
+    /// Saves a copy if the data is produced right into the buffer
+    pub fn build(len: usize, fill: impl FnOnce(&mut [u8])) -> Result<Self, IoError> {
+        if len < SHARED_BUFFER_THRESHOLD {
+            let mut data = vec![0; len];
+            fill(&mut data);
+
+            return Ok(Self {
+                data: BufferData::Inline(data),
+            });
+        }
+
+        let fd = create_sealed_memfd(len, fill)?;
+        Self::map(fd)
+    }
+
+    /// If the buffer is placed in shared memory
+    pub fn is_shared(&self) -> bool {
+        matches!(self.data, BufferData::Mapped { .. })
+    }
+
+    /// Map sealed memfd read-only
+    fn map(fd: OwnedFd) -> Result<Self, IoError> {
+        let seals = SealFlag::from_bits_truncate(fcntl(fd.as_raw_fd(), FcntlArg::F_GET_SEALS)?);
+
+        if !seals.contains(required_seals()) {
+            return Err(IoError::new(
+                ErrorKind::InvalidData,
+                "Shared buffer descriptor is not sealed",
+            ));
+        }
+
+        let len = fstat(fd.as_raw_fd())?.st_size as usize;
+
+        let len = match NonZeroUsize::new(len) {
+            Some(len) => len,
+            None => {
+                return Ok(Self {
+                    data: BufferData::Inline(vec![]),
+                })
+            }
+        };
+
+        let ptr = unsafe {
+            mmap(
+                None,
+                len,
+                ProtFlags::PROT_READ,
+                MapFlags::MAP_SHARED,
+                fd.as_raw_fd(),
+                0,
+            )?
+        };
+
+        Ok(Self {
+            data: BufferData::Mapped {
+                fd,
+                ptr: NonNull::new(ptr as *mut u8).unwrap(),
+                len: len.get(),
+            },
+        })
+    }
+}
+
+/// Create memfd of **len** bytes, fill it, and seal it
+fn create_sealed_memfd(len: usize, fill: impl FnOnce(&mut [u8])) -> Result<OwnedFd, IoError> {
+    let name = CStr::from_bytes_with_nul(b"karo-bus-buffer\0").unwrap();
+
+    let fd = memfd_create(
+        name,
+        MemFdCreateFlag::MFD_CLOEXEC | MemFdCreateFlag::MFD_ALLOW_SEALING,
+    )?;
+    // Safe, because we own just created descriptor
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    ftruncate(fd.as_raw_fd(), len as i64)?;
+
+    // Writable mapping should be unmapped before sealing the memfd for writing
+    unsafe {
+        let ptr = mmap(
+            None,
+            NonZeroUsize::new(len).unwrap(),
+            ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+            MapFlags::MAP_SHARED,
+            fd.as_raw_fd(),
+            0,
+        )?;
+
+        fill(slice::from_raw_parts_mut(ptr as *mut u8, len));
+
+        munmap(ptr, len)?;
+    }
+
+    fcntl(
+        fd.as_raw_fd(),
+        FcntlArg::F_ADD_SEALS(required_seals() | SealFlag::F_SEAL_SEAL),
+    )?;
+
+    Ok(fd)
+}
+
+impl Deref for SharedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.data {
+            BufferData::Inline(data) => data,
+            BufferData::Mapped { ptr, len, .. } => unsafe {
+                slice::from_raw_parts(ptr.as_ptr(), *len)
+            },
+        }
+    }
+}
+
+impl AsRef<[u8]> for SharedBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Drop for SharedBuffer {
+    fn drop(&mut self) {
+        if let BufferData::Mapped { ptr, len, .. } = self.data {
+            let _ = unsafe { munmap(ptr.as_ptr() as _, len) };
+        }
+    }
+}
+
+impl Debug for SharedBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedBuffer")
+            .field("len", &self.len())
+            .field("shared", &self.is_shared())
+            .finish()
+    }
+}
+
+impl BusSchema for SharedBuffer {
+    fn schema() -> Schema {
+        Schema::Binary
+    }
+}
+
+impl Serialize for SharedBuffer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.data {
+            BufferData::Inline(data) => serializer.serialize_bytes(data),
+            BufferData::Mapped { fd, .. } => {
+                // Duplicates the descriptor the same way [BusFd] does
+                let fd = BusFd::new(fd.try_clone().map_err(serde::ser::Error::custom)?);
+
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(SHARED_BUFFER_KEY, &fd)?;
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedBuffer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SharedBufferVisitor)
+    }
+}
+
+struct SharedBufferVisitor;
+
+impl<'de> Visitor<'de> for SharedBufferVisitor {
+    type Value = SharedBuffer;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "binary data or a shared memory descriptor")
+    }
+
+    fn visit_bytes<E: de::Error>(self, data: &[u8]) -> Result<Self::Value, E> {
+        self.visit_byte_buf(data.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, data: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(SharedBuffer {
+            data: BufferData::Inline(data),
+        })
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut data = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+        while let Some(byte) = seq.next_element::<u8>()? {
+            data.push(byte);
+        }
+
+        self.visit_byte_buf(data)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        match map.next_key::<String>()? {
+            Some(key) if key == SHARED_BUFFER_KEY => {
+                let fd: BusFd = map.next_value()?;
+
+                SharedBuffer::map(fd.into_inner()).map_err(de::Error::custom)
+            }
+            _ => Err(de::Error::custom("Expected a shared memory descriptor")),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+#![allow(dead_code)]
+
+use std::{env, path::Path, time::Duration};
+
+use json::JsonValue;
+use log::LevelFilter;
+use tempdir::TempDir;
+use tokio::{
+    fs::OpenOptions,
+    io::AsyncWriteExt,
+    sync::mpsc::{self, Sender},
+    time,
+};
+
+use karo_bus_common::HUB_SOCKET_PATH_ENV;
+use karo_bus_hub::{args::Args, hub::Hub};
+use karo_bus_lib::{Bus, Peer};
+
+pub async fn start_hub(socket_path: &str, service_files_dir: &str) -> Sender<()> {
+    env::set_var(HUB_SOCKET_PATH_ENV, socket_path);
+
+    let args = Args {
+        log_level: LevelFilter::Debug,
+        service_files_dir: service_files_dir.into(),
+        ..Default::default()
+    };
+
+    // let _ = pretty_env_logger::formatted_builder()
+    //     .filter_level(args.log_level)
+    //     .try_init();
+
+    let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>(1);
+
+    tokio::spawn(async move {
+        let mut hub = Hub::new(args, shutdown_rx);
+        hub.run().await.expect("Failed to run hub");
+
+        println!("Shutting hub down");
+    });
+
+    println!("Succesfully started hub socket");
+    shutdown_tx
+}
+
+pub async fn write_service_file(service_dir: &Path, service_name: &str, content: JsonValue) {
+    let service_file_path = service_dir.join(format!("{}.service", service_name));
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .open(service_file_path.as_path())
+        .await
+        .expect("Failed to create service file");
+
+    file.write_all(json::stringify(content).as_bytes())
+        .await
+        .expect("Failed to write service file content");
+    file.flush().await.expect("Failed to flush service file");
+}
+
+/// Hub serving a temporary socket and service files directory
+pub struct TestHub {
+    /// Hub socket directory. Removed when the hub is dropped
+    socket_dir: TempDir,
+    /// Service files directory. Removed when the hub is dropped
+    service_dir: TempDir,
+    /// Sender to shut down the hub
+    shutdown_tx: Sender<()>,
+}
+
+impl TestHub {
+    /// Start a hub with an empty service files directory named after the **test_name**
+    pub async fn start(test_name: &str) -> Self {
+        let socket_dir =
+            TempDir::new("karo_hub_socket_dir").expect("Failed to create socket tempdir");
+        let service_dir = TempDir::new(test_name).expect("Failed to create tempdir");
+
+        let socket_path: String = socket_dir
+            .path()
+            .join("karo_hub.socket")
+            .as_os_str()
+            .to_str()
+            .unwrap()
+            .into();
+
+        let shutdown_tx = start_hub(
+            &socket_path,
+            service_dir.path().as_os_str().to_str().unwrap(),
+        )
+        .await;
+        // Lets wait until hub starts
+        time::sleep(Duration::from_millis(10)).await;
+
+        Self {
+            socket_dir,
+            service_dir,
+            shutdown_tx,
+        }
+    }
+
+    pub fn service_dir(&self) -> &Path {
+        self.service_dir.path()
+    }
+
+    /// Write a service file, which allows connections from the **incoming_connections**
+    pub async fn write_service_file(&self, service_name: &str, incoming_connections: &[&str]) {
+        let mut content = JsonValue::new_object();
+        content["exec"] = "/**/*".into();
+        content["incoming_connections"] = incoming_connections.to_vec().into();
+
+        write_service_file(self.service_dir(), service_name, content).await;
+    }
+
+    /// Write a service file and register the service
+    pub async fn register_service(&self, service_name: &str, incoming_connections: &[&str]) -> Bus {
+        self.write_service_file(service_name, incoming_connections)
+            .await;
+
+        Bus::register(service_name)
+            .await
+            .expect("Failed to register service")
+    }
+
+    /// Register a service and a caller service connected to it.
+    /// Returns the service bus, the caller bus and the caller connection to the service
+    pub async fn register_services(
+        &self,
+        service_name: &str,
+        caller_name: &str,
+    ) -> (Bus, Bus, Peer) {
+        let bus = self.register_service(service_name, &[caller_name]).await;
+        let mut caller_bus = self.register_service(caller_name, &[]).await;
+
+        let peer = caller_bus
+            .connect(service_name)
+            .await
+            .expect("Failed to connect to the target service");
+
+        (bus, caller_bus, peer)
+    }
+
+    pub async fn shutdown(self) {
+        self.shutdown_tx
+            .send(())
+            .await
+            .expect("Failed to send shutdown request to the hub");
+    }
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use std::time::Duration;
+
+use tempdir::TempDir;
+use tokio::time;
+
+use karo_bus_lib::{Bus, BusEvent};
+
+use common::{start_hub, write_service_file};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_hub_restart() {
+    let socket_dir = TempDir::new("karo_hub_socket_dir").expect("Failed to create socket tempdir");
+    let socket_path: String = socket_dir
+        .path()
+        .join("karo_hub.socket")
+        .as_os_str()
+        .to_str()
+        .unwrap()
+        .into();
+
+    let service_dir = TempDir::new("test_hub_restart").expect("Failed to create tempdir");
+
+    let shutdown_tx = start_hub(
+        &socket_path,
+        service_dir.path().as_os_str().to_str().unwrap(),
+    )
+    .await;
+    // Lets wait until hub starts
+    time::sleep(Duration::from_millis(10)).await;
+
+    let service_file_json = json::parse(
+        r#"
+            {
+                "exec": "/**/*",
+                "incoming_connections": ["com.hub_restart.caller"]
+            }
+            "#,
+    )
+    .unwrap();
+
+    let register_service_name = "com.hub_restart.register";
+    write_service_file(service_dir.path(), register_service_name, service_file_json).await;
+
+    let mut bus1 = Bus::register(register_service_name)
+        .await
+        .expect("Failed to register service");
+
+    let _method = bus1
+        .register_method("method", |value: i32| async move { value + 1 })
+        .expect("Failed to register method");
+
+    let service_file_json = json::parse(
+        r#"
+        {
+            "exec": "/**/*",
+            "incoming_connections": []
+        }
+        "#,
+    )
+    .unwrap();
+
+    let service_name = "com.hub_restart.caller";
+    write_service_file(service_dir.path(), service_name, service_file_json).await;
+
+    let mut bus2 = Bus::register(service_name)
+        .await
+        .expect("Failed to register service");
+
+    let mut events = bus2.events();
+
+    let mut peer = bus2
+        .connect(register_service_name)
+        .await
+        .expect("Failed to connect to the target service");
+
+    shutdown_tx
+        .send(())
+        .await
+        .expect("Failed to send shutdown request to the hub");
+
+    let event = time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("Hub disconnection event timed out")
+        .expect("Failed to receive bus event");
+    assert!(matches!(event, BusEvent::HubDisconnected(_)));
+
+    // Peer connections don't depend on the hub
+    assert_eq!(
+        peer.call::<i32, i32>("method", &41)
+            .await
+            .expect("Failed to make a call while hub is down"),
+        42
+    );
+
+    let shutdown_tx = start_hub(
+        &socket_path,
+        service_dir.path().as_os_str().to_str().unwrap(),
+    )
+    .await;
+
+    let event = time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("Hub reconnection event timed out")
+        .expect("Failed to receive bus event");
+    assert_eq!(event, BusEvent::HubReconnected);
+
+    assert_eq!(
+        peer.call::<i32, i32>("method", &1)
+            .await
+            .expect("Failed to make a call after hub restart"),
+        2
+    );
+
+    shutdown_tx
+        .send(())
+        .await
+        .expect("Failed to send shutdown request to the hub");
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time;
+use tokio_stream::StreamExt;
+
+use karo_bus_lib::{async_trait, interface};
+
+use common::TestHub;
+
+#[interface]
+pub trait Thermostat {
+    async fn set_target(&self, target: f64) -> bool;
+    async fn target(&self) -> f64;
+    async fn add(&self, a: i32, b: i32) -> i32;
+    async fn reset(&self);
+
+    #[signal]
+    fn overheated() -> f64;
+    #[state]
+    fn temperature() -> f64;
+}
+
+#[derive(Default)]
+struct ThermostatService {
+    target: Mutex<f64>,
+}
+
+#[async_trait]
+impl Thermostat for ThermostatService {
+    async fn set_target(&self, target: f64) -> bool {
+        if target > 100.0 {
+            return false;
+        }
+
+        *self.target.lock().unwrap() = target;
+        true
+    }
+
+    async fn target(&self) -> f64 {
+        *self.target.lock().unwrap()
+    }
+
+    async fn add(&self, a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    async fn reset(&self) {
+        *self.target.lock().unwrap() = 0.0;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_interface() {
+    let hub = TestHub::start("test_interface").await;
+
+    let register_service_name = "com.register_interface";
+    let service_name = "com.use_interface";
+    let (mut bus1, _bus2, peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let mut endpoints =
+        ThermostatEndpoints::register(&mut bus1, Arc::new(ThermostatService::default()))
+            .expect("Failed to register interface");
+
+    // Interface endpoints are registered only once
+    ThermostatEndpoints::register(&mut bus1, Arc::new(ThermostatService::default()))
+        .err()
+        .expect("Registered interface twice");
+
+    let mut thermostat = ThermostatProxy::new(peer);
+
+    assert!(thermostat
+        .set_target(21.5)
+        .await
+        .expect("Failed to make a call"));
+    assert!(!thermostat
+        .set_target(120.0)
+        .await
+        .expect("Failed to make a call"));
+    assert_eq!(
+        thermostat.target().await.expect("Failed to make a call"),
+        21.5
+    );
+    assert_eq!(
+        thermostat.add(2, 3).await.expect("Failed to make a call"),
+        5
+    );
+
+    thermostat.reset().await.expect("Failed to make a call");
+    assert_eq!(
+        thermostat.target().await.expect("Failed to make a call"),
+        0.0
+    );
+
+    // Interface methods are plain methods for untyped peers
+    let sum: i32 = thermostat
+        .peer()
+        .call("add", &(4, 5))
+        .await
+        .expect("Failed to make a call");
+    assert_eq!(sum, 9);
+
+    let mut overheated = thermostat
+        .overheated()
+        .await
+        .expect("Failed to subscribe to the signal");
+
+    endpoints
+        .overheated
+        .emit(95.0)
+        .expect("Failed to emit signal");
+    assert_eq!(
+        time::timeout(Duration::from_secs(1), overheated.next())
+            .await
+            .expect("Signal timed out"),
+        Some(95.0)
+    );
+
+    endpoints
+        .temperature
+        .set(22.0)
+        .expect("Failed to set state");
+
+    hub.shutdown().await;
+}
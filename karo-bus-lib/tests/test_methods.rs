@@ -1,152 +1,1242 @@
-use std::{env, path::Path, time::Duration};
-
-use json::JsonValue;
-use log::LevelFilter;
-use tempdir::TempDir;
-use tokio::{
-    fs::OpenOptions,
-    io::AsyncWriteExt,
-    sync::mpsc::{self, Sender},
-    time,
+mod common;
+
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    time::{Duration, Instant},
 };
 
-use karo_bus_common::HUB_SOCKET_PATH_ENV;
-use karo_bus_hub::{args::Args, hub::Hub};
-use karo_bus_lib::Bus;
+use bson::{doc, RawDocumentBuf};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc, time};
+use tokio_stream::StreamExt;
 
-async fn start_hub(socket_path: &str, service_files_dir: &str) -> Sender<()> {
-    env::set_var(HUB_SOCKET_PATH_ENV, socket_path);
+use karo_bus_common::inspect_data::{InspectData, INSPECT_METHOD};
+use karo_bus_lib::{
+    BusFd, BusSchema, CallContext, ChannelReceiver, ChannelSender, EndpointMetadata, MethodError,
+    MethodOptions, MethodPanic, Schema, SchemaField, SharedBuffer, SignalOptions, StateOptions,
+    SHARED_BUFFER_THRESHOLD,
+};
 
-    let args = Args {
-        log_level: LevelFilter::Debug,
-        service_files_dir: service_files_dir.into(),
-    };
+use common::TestHub;
 
-    // let _ = pretty_env_logger::formatted_builder()
-    //     .filter_level(args.log_level)
-    //     .try_init();
+#[tokio::test(flavor = "multi_thread")]
+async fn test_methods() {
+    let hub = TestHub::start("test_method_calls").await;
 
-    let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>(1);
+    let register_service_name = "com.register_method";
+    let service_name = "com.call_method";
+    let (mut bus1, _bus2, peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
 
-    tokio::spawn(async move {
-        let mut hub = Hub::new(args, shutdown_rx);
-        hub.run().await.expect("Failed to run hub");
+    let _method = bus1
+        .register_method("method", |value: i32| async move {
+            return format!("Hello, {}", value);
+        })
+        .expect("Failed to register method");
 
-        println!("Shutting hub down");
+    // Invalid method
+    peer.call::<String, String>("non_existing_method", &"invalid_string".into())
+        .await
+        .expect_err("Invalid method call succeeded");
+
+    // Invalid param
+    peer.call::<String, String>("method", &"invalid_string".into())
+        .await
+        .expect_err("Invalid param method call succeeded");
+
+    // Invalid return
+    peer.call::<String, i32>("method", &"invalid_string".into())
+        .await
+        .expect_err("Invalid return method call succeeded");
+
+    // Valid call
+    assert_eq!(
+        peer.call::<i32, String>("method", &42)
+            .await
+            .expect("Failed to make a valid call"),
+        "Hello, 42"
+    );
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_concurrent_methods() {
+    let hub = TestHub::start("test_concurrent_methods").await;
+
+    let register_service_name = "com.register_concurrent";
+    let service_name = "com.call_concurrent";
+    let (mut bus1, _bus2, peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let _slow = bus1
+        .register_method("slow", |value: i32| async move {
+            time::sleep(Duration::from_millis(300)).await;
+            value
+        })
+        .expect("Failed to register method");
+
+    let _serialized = bus1
+        .register_method_with_options(
+            "serialized",
+            MethodOptions::serialized().queue_size(1),
+            |value: i32| async move {
+                time::sleep(Duration::from_millis(300)).await;
+                value
+            },
+        )
+        .expect("Failed to register method");
+
+    // Concurrent calls don't wait for each other
+    let start = Instant::now();
+    let calls = (0..4).map(|value| {
+        let mut peer = peer.clone();
+        tokio::spawn(async move { peer.call::<i32, i32>("slow", &value).await })
     });
 
-    println!("Succesfully started hub socket");
-    shutdown_tx
+    for (value, call) in calls.collect::<Vec<_>>().into_iter().enumerate() {
+        assert_eq!(
+            call.await
+                .unwrap()
+                .expect("Failed to make a concurrent call"),
+            value as i32
+        );
+    }
+    assert!(start.elapsed() < Duration::from_millis(900));
+
+    // One call is running, one is queued, the rest are rejected
+    let calls = (0..5)
+        .map(|value| {
+            let mut peer = peer.clone();
+            tokio::spawn(async move { peer.call::<i32, i32>("serialized", &value).await })
+        })
+        .collect::<Vec<_>>();
+
+    let mut handled = 0;
+    let mut busy = 0;
+    for (value, call) in calls.into_iter().enumerate() {
+        match call.await.unwrap() {
+            Ok(result) => {
+                assert_eq!(result, value as i32);
+                handled += 1;
+            }
+            Err(err) => {
+                assert!(err.to_string().contains("busy"), "{}", err);
+                busy += 1;
+            }
+        }
+    }
+    assert_eq!(handled, 2);
+    assert_eq!(busy, 3);
+
+    hub.shutdown().await;
 }
 
-async fn write_service_file(service_dir: &Path, service_name: &str, content: JsonValue) {
-    let service_file_path = service_dir.join(format!("{}.service", service_name));
+#[tokio::test(flavor = "multi_thread")]
+async fn test_method_context() {
+    let hub = TestHub::start("test_method_context").await;
+
+    let register_service_name = "com.register_context";
+    let service_name = "com.call_context";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let _whoami = bus1
+        .register_method_with_context(
+            "whoami",
+            MethodOptions::default(),
+            |_: (), context: CallContext| async move {
+                (
+                    context.caller_name.clone(),
+                    context.header("request-id").cloned(),
+                    context.credentials.and_then(|credentials| credentials.pid),
+                )
+            },
+        )
+        .expect("Failed to register method");
 
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(service_file_path.as_path())
+    let (caller_name, request_id, pid) = peer
+        .call_with_headers::<(), (String, Option<String>, Option<i32>)>(
+            "whoami",
+            &(),
+            HashMap::from([("request-id".into(), "42".into())]),
+        )
         .await
-        .expect("Failed to create service file");
+        .expect("Failed to make a call with headers");
 
-    file.write_all(json::stringify(content).as_bytes())
+    assert_eq!(caller_name, service_name);
+    assert_eq!(request_id, Some("42".into()));
+    // Both services live in the test process
+    assert_eq!(pid, Some(std::process::id() as i32));
+
+    // No headers set
+    let (_, request_id, _) = peer
+        .call::<(), (String, Option<String>, Option<i32>)>("whoami", &())
         .await
-        .expect("Failed to write service file content");
-    file.flush().await.expect("Failed to flush service file");
+        .expect("Failed to make a call");
+    assert_eq!(request_id, None);
+
+    hub.shutdown().await;
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum DivisionError {
+    DivisionByZero,
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn test_methods() {
-    let socket_dir = TempDir::new("karo_hub_socket_dir").expect("Failed to create socket tempdir");
-    let socket_path: String = socket_dir
-        .path()
-        .join("karo_hub.socket")
-        .as_os_str()
-        .to_str()
-        .unwrap()
-        .into();
-
-    let service_dir = TempDir::new("test_method_calls").expect("Failed to create tempdir");
-
-    let shutdown_tx = start_hub(
-        &socket_path,
-        service_dir.path().as_os_str().to_str().unwrap(),
+async fn test_fallible_methods() {
+    let hub = TestHub::start("test_fallible_methods").await;
+
+    let register_service_name = "com.register_fallible";
+    let service_name = "com.call_fallible";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let _divide = bus1
+        .register_fallible_method("divide", |(a, b): (i32, i32)| async move {
+            if b == 0 {
+                Err(DivisionError::DivisionByZero)
+            } else {
+                Ok(a / b)
+            }
+        })
+        .expect("Failed to register method");
+
+    assert_eq!(
+        peer.call::<(i32, i32), i32>("divide", &(42, 2))
+            .await
+            .expect("Failed to make a valid call"),
+        21
+    );
+
+    let err = peer
+        .call::<(i32, i32), i32>("divide", &(42, 0))
+        .await
+        .expect_err("Division by zero succeeded");
+
+    let method_error = err
+        .downcast_ref::<MethodError>()
+        .expect("Expected method error");
+    assert_eq!(
+        method_error
+            .deserialize::<DivisionError>()
+            .expect("Failed to deserialize method error"),
+        DivisionError::DivisionByZero
+    );
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_method_panics() {
+    let hub = TestHub::start("test_method_panics").await;
+
+    let register_service_name = "com.register_panicking";
+    let service_name = "com.call_panicking";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let (panic_tx, mut panic_rx) = mpsc::channel::<MethodPanic>(1);
+    bus1.set_method_panic_hook(move |panic| {
+        let _ = panic_tx.try_send(panic.clone());
+    });
+
+    let _inverse = bus1
+        .register_method("inverse", |value: i32| async move {
+            if value == 0 {
+                panic!("Can't inverse zero");
+            }
+
+            100 / value
+        })
+        .expect("Failed to register method");
+
+    peer.call::<i32, i32>("inverse", &0)
+        .await
+        .expect_err("Panicked call succeeded");
+
+    let panic = time::timeout(Duration::from_secs(1), panic_rx.recv())
+        .await
+        .expect("Panic hook wasn't called")
+        .unwrap();
+    assert_eq!(panic.method_name, "inverse");
+    assert_eq!(panic.caller_name, service_name);
+    assert_eq!(panic.message, "Can't inverse zero");
+
+    // The method keeps working after a panic
+    assert_eq!(
+        peer.call::<i32, i32>("inverse", &4)
+            .await
+            .expect("Failed to call method after a panic"),
+        25
+    );
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_call_timeouts() {
+    let hub = TestHub::start("test_call_timeouts").await;
+
+    let register_service_name = "com.register_timeouts";
+    let service_name = "com.call_timeouts";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let (cancelled_tx, mut cancelled_rx) = mpsc::channel::<bool>(2);
+    let _sleep = bus1
+        .register_method_with_context(
+            "sleep",
+            MethodOptions::default(),
+            move |millis: u64, context: CallContext| {
+                let cancelled_tx = cancelled_tx.clone();
+
+                async move {
+                    let cancelled = tokio::select! {
+                        _ = context.cancellation.cancelled() => true,
+                        _ = time::sleep(Duration::from_millis(millis)) => false,
+                    };
+
+                    let _ = cancelled_tx.send(cancelled).await;
+                    millis
+                }
+            },
+        )
+        .expect("Failed to register method");
+
+    // Fast enough call
+    assert_eq!(
+        peer.call_with_timeout::<u64, u64>("sleep", &10, Duration::from_secs(1))
+            .await
+            .expect("Failed to make a call with timeout"),
+        10
+    );
+    assert_eq!(cancelled_rx.recv().await, Some(false));
+
+    // Timed out call cancels the handler
+    peer.call_with_timeout::<u64, u64>("sleep", &5000, Duration::from_millis(100))
+        .await
+        .expect_err("Slow call succeeded");
+
+    let cancelled = time::timeout(Duration::from_secs(1), cancelled_rx.recv())
+        .await
+        .expect("Handler wasn't cancelled");
+    assert_eq!(cancelled, Some(true));
+
+    // Dropped call cancels the handler too
+    let _ = time::timeout(
+        Duration::from_millis(100),
+        peer.call::<u64, u64>("sleep", &5000),
     )
     .await;
-    // Lets wait until hub starts
-    time::sleep(Duration::from_millis(10)).await;
-
-    // Create service file second
-    let service_file_json = json::parse(
-        r#"
-            {
-                "exec": "/**/*",
-                "incoming_connections": ["com.call_method"]
+
+    let cancelled = time::timeout(Duration::from_secs(1), cancelled_rx.recv())
+        .await
+        .expect("Handler wasn't cancelled");
+    assert_eq!(cancelled, Some(true));
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_streaming_methods() {
+    let hub = TestHub::start("test_streaming_methods").await;
+
+    let register_service_name = "com.register_streaming";
+    let service_name = "com.call_streaming";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let _count = bus1
+        .register_streaming_method("count", |count: u32| tokio_stream::iter(0..count))
+        .expect("Failed to register streaming method");
+
+    // Stream longer than the flow control window
+    let items: Vec<u32> = peer
+        .call_stream::<u32, u32>("count", &100)
+        .await
+        .expect("Failed to make a streaming call")
+        .map(|item| item.expect("Invalid stream item"))
+        .collect()
+        .await;
+    assert_eq!(items, (0..100).collect::<Vec<u32>>());
+
+    // Empty stream
+    let mut stream = peer
+        .call_stream::<u32, u32>("count", &0)
+        .await
+        .expect("Failed to make a streaming call");
+    assert!(stream.next().await.is_none());
+
+    // Invalid method
+    peer.call_stream::<u32, u32>("non_existing_method", &0)
+        .await
+        .err()
+        .expect("Invalid streaming call succeeded");
+
+    // Invalid param
+    peer.call_stream::<String, u32>("count", &"invalid_string".into())
+        .await
+        .err()
+        .expect("Invalid param streaming call succeeded");
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_channels() {
+    let hub = TestHub::start("test_channels").await;
+
+    let register_service_name = "com.register_channels";
+    let service_name = "com.open_channels";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    // Interactive session. Replies to every item
+    let _shout = bus1
+        .register_channel(
+        "shout",
+        |prefix: String, mut tx: ChannelSender<String>, mut rx: ChannelReceiver<String>| async move {
+            while let Some(Ok(line)) = rx.next().await {
+                if tx
+                    .send(&format!("{}{}", prefix, line.to_uppercase()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
             }
-            "#,
+        },
     )
-    .unwrap();
+    .expect("Failed to register channel");
 
-    let register_service_name = "com.register_method";
-    write_service_file(service_dir.path(), register_service_name, service_file_json).await;
+    // Bulk upload. Replies once the opener closes its sending half
+    let _sum = bus1
+        .register_channel(
+            "sum",
+            |_: (), mut tx: ChannelSender<u64>, mut rx: ChannelReceiver<u64>| async move {
+                let mut sum = 0;
+                while let Some(Ok(item)) = rx.next().await {
+                    sum += item;
+                }
 
-    let mut bus1 = Bus::register(register_service_name)
+                let _ = tx.send(&sum).await;
+            },
+        )
+        .expect("Failed to register channel");
+
+    let (mut tx, mut rx) = peer
+        .open_channel::<String, String, String>("shout", &"> ".into())
         .await
-        .expect("Failed to register service");
+        .expect("Failed to open a channel");
 
-    bus1.register_method("method", |value: i32| async move {
-        return format!("Hello, {}", value);
-    })
-    .expect("Failed to register method");
-
-    // Create service file first
-    let service_file_json = json::parse(
-        r#"
-        {
-            "exec": "/**/*",
-            "incoming_connections": []
-        }
-        "#,
-    )
-    .unwrap();
+    for i in 0..50 {
+        tx.send(&format!("line {}", i))
+            .await
+            .expect("Failed to send channel item");
 
-    let service_name = "com.call_method";
-    write_service_file(service_dir.path(), service_name, service_file_json).await;
+        let reply = rx
+            .next()
+            .await
+            .expect("Channel unexpectedly closed")
+            .expect("Invalid channel item");
+        assert_eq!(reply, format!("> LINE {}", i));
+    }
+
+    // Handler finishes when we close our half
+    tx.close().await;
+    assert!(rx.next().await.is_none());
+
+    // Upload longer than the flow control window
+    let (mut tx, mut rx) = peer
+        .open_channel::<(), u64, u64>("sum", &())
+        .await
+        .expect("Failed to open a channel");
+
+    for i in 0..100 {
+        tx.send(&i).await.expect("Failed to send channel item");
+    }
+    tx.close().await;
+
+    let sum = rx
+        .next()
+        .await
+        .expect("Channel unexpectedly closed")
+        .expect("Invalid channel item");
+    assert_eq!(sum, (0..100).sum::<u64>());
+    assert!(rx.next().await.is_none());
+
+    // Invalid channel
+    peer.open_channel::<(), u64, u64>("non_existing_channel", &())
+        .await
+        .err()
+        .expect("Invalid channel open succeeded");
+
+    // Invalid init value
+    peer.open_channel::<u32, u64, u64>("sum", &42)
+        .await
+        .err()
+        .expect("Invalid init value channel open succeeded");
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_fd_passing() {
+    let hub = TestHub::start("test_fd_passing").await;
+
+    let register_service_name = "com.register_fds";
+    let service_name = "com.call_fds";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    // Descriptor in parameters
+    let _write_greeting = bus1
+        .register_method("write_greeting", |fd: BusFd| async move {
+            let mut stream = UnixStream::from(fd.into_inner());
+            stream.write_all(b"hello").is_ok()
+        })
+        .expect("Failed to register method");
+
+    // Descriptor in a return value
+    let _open_greeting = bus1
+        .register_method("open_greeting", |name: String| async move {
+            let (mut local, remote) = UnixStream::pair().expect("Failed to create socket pair");
+            local
+                .write_all(format!("hello {}", name).as_bytes())
+                .expect("Failed to write greeting");
+
+            BusFd::new(remote)
+        })
+        .expect("Failed to register method");
+
+    let (mut local, remote) = UnixStream::pair().expect("Failed to create socket pair");
+    let written: bool = peer
+        .call("write_greeting", &BusFd::new(remote))
+        .await
+        .expect("Failed to make a call");
+    assert!(written);
+
+    let mut greeting = [0; 5];
+    local
+        .read_exact(&mut greeting)
+        .expect("Failed to read greeting");
+    assert_eq!(&greeting, b"hello");
+
+    let fd: BusFd = peer
+        .call("open_greeting", &"bus".to_owned())
+        .await
+        .expect("Failed to make a call");
+
+    let mut greeting = String::new();
+    UnixStream::from(fd.into_inner())
+        .read_to_string(&mut greeting)
+        .expect("Failed to read greeting");
+    assert_eq!(greeting, "hello bus");
+
+    // Descriptor in a signal. Every subscriber gets its own duplicate
+    let signal = bus1
+        .register_signal::<BusFd>("greeting_sockets")
+        .expect("Failed to register signal");
+
+    let mut first = peer
+        .subscribe::<BusFd>("greeting_sockets")
+        .await
+        .expect("Failed to subscribe");
+    let mut second = peer
+        .subscribe::<BusFd>("greeting_sockets")
+        .await
+        .expect("Failed to subscribe");
+
+    let (mut local, remote) = UnixStream::pair().expect("Failed to create socket pair");
+    signal
+        .emit(BusFd::new(remote))
+        .expect("Failed to emit signal");
+
+    for (subscription, greeting) in [(&mut first, b"a"), (&mut second, b"b")] {
+        let fd = time::timeout(Duration::from_secs(1), subscription.next())
+            .await
+            .expect("Signal timed out")
+            .expect("Subscription ended");
+
+        UnixStream::from(fd.into_inner())
+            .write_all(greeting)
+            .expect("Failed to write greeting");
+    }
+
+    let mut greetings = [0; 2];
+    local
+        .read_exact(&mut greetings)
+        .expect("Failed to read greetings");
+    assert_eq!(&greetings, b"ab");
+
+    // Rejected calls close passed descriptors. The socket reads EOF when every copy is closed
+    let (mut local, remote) = UnixStream::pair().expect("Failed to create socket pair");
+    let remote = BusFd::new(remote);
+    peer.call::<BusFd, bool>("non_existing_method", &remote)
+        .await
+        .expect_err("Call to unregistered method succeeded");
+    drop(remote);
+
+    local
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .expect("Failed to set read timeout");
+    assert_eq!(
+        local
+            .read(&mut [0; 1])
+            .expect("Rejected call descriptor wasn't closed"),
+        0
+    );
+
+    // Descriptors can be serialized only to be sent over the bus
+    let (_, remote) = UnixStream::pair().expect("Failed to create socket pair");
+    assert!(bson::to_bson(&BusFd::new(remote)).is_err());
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_shared_buffers() {
+    let hub = TestHub::start("test_shared_buffers").await;
+
+    let register_service_name = "com.register_buffers";
+    let service_name = "com.call_buffers";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    // Returns buffer checksum and if it's received in shared memory
+    let _checksum = bus1
+        .register_method("checksum", |buffer: SharedBuffer| async move {
+            (
+                buffer.iter().map(|byte| *byte as u64).sum::<u64>(),
+                buffer.is_shared(),
+            )
+        })
+        .expect("Failed to register method");
+
+    let _make_buffer = bus1
+        .register_method("make_buffer", |len: u32| async move {
+            SharedBuffer::build(len as usize, |buffer| {
+                buffer
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(i, byte)| *byte = i as u8)
+            })
+            .expect("Failed to create shared buffer")
+        })
+        .expect("Failed to register method");
+
+    // Big buffer goes through shared memory
+    let data = vec![7u8; 4 * 1024 * 1024];
+    let buffer = SharedBuffer::new(&data).expect("Failed to create shared buffer");
+    assert!(buffer.is_shared());
+
+    let (checksum, shared): (u64, bool) = peer
+        .call("checksum", &buffer)
+        .await
+        .expect("Failed to make a call");
+    assert_eq!(checksum, 7 * data.len() as u64);
+    assert!(shared);
+
+    // Small buffer is sent inline
+    let buffer = SharedBuffer::new(&[1, 2, 3]).expect("Failed to create shared buffer");
+    assert!(!buffer.is_shared());
+
+    let (checksum, shared): (u64, bool) = peer
+        .call("checksum", &buffer)
+        .await
+        .expect("Failed to make a call");
+    assert_eq!(checksum, 6);
+    assert!(!shared);
+
+    // Shared buffer in a return value
+    let len = SHARED_BUFFER_THRESHOLD as u32 * 2;
+    let buffer: SharedBuffer = peer
+        .call("make_buffer", &len)
+        .await
+        .expect("Failed to make a call");
+    assert!(buffer.is_shared());
+    assert_eq!(buffer.len(), len as usize);
+    assert!(buffer.iter().enumerate().all(|(i, byte)| *byte == i as u8));
+
+    hub.shutdown().await;
+}
+
+#[derive(Serialize, Deserialize)]
+struct Greeting {
+    name: String,
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_raw_methods() {
+    let hub = TestHub::start("test_raw_methods").await;
+
+    let register_service_name = "com.register_raw";
+    let service_name = "com.call_raw";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    // Reads a single field without parsing the whole document
+    let _name = bus1
+        .register_raw_method("name", |params: RawDocumentBuf| async move {
+            let name = params.get_str("name").unwrap_or_default();
+
+            RawDocumentBuf::from_document(&doc! { "greeting": format!("hello {}", name) }).unwrap()
+        })
+        .expect("Failed to register raw method");
+
+    let _sum = bus1
+        .register_method("sum", |(a, b): (i32, i32)| async move { a + b })
+        .expect("Failed to register method");
+
+    let params = RawDocumentBuf::from_document(&doc! { "name": "bus", "payload": [1, 2, 3] })
+        .expect("Failed to encode params");
+
+    let response = peer
+        .call_raw("name", &params)
+        .await
+        .expect("Failed to make a raw call");
+    assert_eq!(response.get_str("greeting").unwrap(), "hello bus");
+
+    // Raw and typed endpoints interoperate
+    let _greet = bus1
+        .register_method("greet", |params: Greeting| async move {
+            Greeting {
+                name: format!("hello {}", params.name),
+            }
+        })
+        .expect("Failed to register method");
+
+    let response = peer
+        .call_raw("greet", &params)
+        .await
+        .expect("Failed to make a raw call to a typed method");
+    assert_eq!(response.get_str("name").unwrap(), "hello bus");
 
-    let mut bus2 = Bus::register(service_name)
+    let response: RawDocumentBuf = peer
+        .call(
+            "name",
+            &Greeting {
+                name: "typed".into(),
+            },
+        )
         .await
-        .expect("Failed to register service");
+        .expect("Failed to make a typed call to a raw method");
+    assert_eq!(response.get_str("greeting").unwrap(), "hello typed");
 
-    let peer = bus2
-        .connect(register_service_name)
+    // Typed method doesn't accept non-matching documents
+    peer.call_raw("sum", &params)
         .await
-        .expect("Failed to connect to the target service");
+        .err()
+        .expect("Raw call with invalid params succeeded");
 
     // Invalid method
-    peer.call::<String, String>("non_existing_method", &"invalid_string".into())
+    peer.call_raw("non_existing_method", &params)
         .await
-        .expect_err("Invalid method call succeeded");
+        .err()
+        .expect("Invalid raw call succeeded");
 
-    // Invalid param
-    peer.call::<String, String>("method", &"invalid_string".into())
+    hub.shutdown().await;
+}
+
+#[derive(Serialize, Deserialize, BusSchema)]
+struct Setpoint {
+    zone: String,
+    #[serde(rename = "temperature")]
+    value: f64,
+    mode: Option<Mode>,
+}
+
+#[derive(Serialize, Deserialize, BusSchema)]
+enum Mode {
+    Heat,
+    Cool,
+}
+
+#[derive(Serialize, Deserialize, BusSchema)]
+#[serde(rename_all = "camelCase")]
+struct Schedule {
+    start_time: u32,
+    #[serde(default)]
+    repeat_count: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_time: Option<u32>,
+    days: Vec<Day>,
+}
+
+#[derive(Serialize, Deserialize, BusSchema)]
+#[serde(rename_all = "snake_case")]
+enum Day {
+    WorkDay,
+    WeekEnd,
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_method_schemas() {
+    let hub = TestHub::start("test_method_schemas").await;
+
+    let register_service_name = "com.register_schemas";
+    let service_name = "com.inspect_schemas";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    // Heating is allowed up to 30 degrees in any zone
+    let _set = bus1
+        .register_method("set", |setpoint: Setpoint| async move {
+            setpoint.value < 30.0
+                || (matches!(setpoint.mode, Some(Mode::Cool)) && !setpoint.zone.is_empty())
+        })
+        .expect("Failed to register method");
+
+    bus1.describe_method::<Setpoint, bool>("set")
+        .expect("Failed to describe method");
+
+    let _signal = bus1
+        .register_signal::<Vec<u32>>("readings")
+        .expect("Failed to register signal");
+    bus1.describe_signal::<Vec<u32>>("readings")
+        .expect("Failed to describe signal");
+
+    // Only registered endpoints can be described
+    bus1.describe_method::<(), ()>("non_existing_method")
+        .err()
+        .expect("Described non existing method");
+    bus1.describe_state::<i32>("non_existing_state")
+        .err()
+        .expect("Described non existing state");
+
+    let inspect_data: InspectData = peer
+        .call(INSPECT_METHOD, &())
         .await
-        .expect_err("Invalid param method call succeeded");
+        .expect("Failed to inspect service");
 
-    // Invalid return
-    peer.call::<String, i32>("method", &"invalid_string".into())
+    let schema = inspect_data
+        .method_schemas
+        .get("set")
+        .expect("Method schema is missing");
+
+    assert_eq!(schema.params, Setpoint::schema());
+    assert_eq!(schema.returns, Schema::Bool);
+    assert_eq!(
+        inspect_data.signal_schemas.get("readings"),
+        Some(&Vec::<u32>::schema())
+    );
+
+    match &schema.params {
+        Schema::Object { name, fields } => {
+            assert_eq!(name, "Setpoint");
+
+            let names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+            assert_eq!(names, vec!["zone", "temperature", "mode"]);
+        }
+        schema => panic!("Unexpected schema {:?}", schema),
+    }
+
+    // Generic tooling builds and checks arguments from the schema
+    let template = schema.params.template();
+    schema
+        .params
+        .validate(&template)
+        .expect("Template doesn't match the schema");
+    schema
+        .params
+        .validate(&bson::bson!({ "zone": "kitchen", "temperature": 21, "mode": "Dry" }))
+        .err()
+        .expect("Invalid argument passed validation");
+
+    let accepted: bool = peer
+        .call("set", &template)
         .await
-        .expect_err("Invalid return method call succeeded");
+        .expect("Failed to call method with a template argument");
+    assert!(accepted);
 
-    // Valid call
+    // Schemas follow serde renaming rules. Defaulted fields may be missing
     assert_eq!(
-        peer.call::<i32, String>("method", &42)
+        Schedule::schema(),
+        Schema::Object {
+            name: "Schedule".into(),
+            fields: vec![
+                SchemaField::new("startTime", Schema::Int),
+                SchemaField::new(
+                    "repeatCount",
+                    Schema::Optional {
+                        value: Box::new(Schema::Int)
+                    }
+                ),
+                SchemaField::new("endTime", Option::<u32>::schema()),
+                SchemaField::new("days", Vec::<Day>::schema()),
+            ],
+        }
+    );
+    assert_eq!(
+        Day::schema(),
+        Schema::Enum {
+            name: "Day".into(),
+            variants: vec!["work_day".into(), "week_end".into()],
+        }
+    );
+    let schedule = Schedule {
+        start_time: 8,
+        repeat_count: 0,
+        end_time: None,
+        days: vec![Day::WorkDay, Day::WeekEnd],
+    };
+    Schedule::schema()
+        .validate(&bson::to_bson(&schedule).expect("Failed to serialize schedule"))
+        .expect("Serialized value doesn't match the schema");
+    Schedule::schema()
+        .validate(&bson::bson!({ "startTime": 8, "days": ["week_end"] }))
+        .expect("Defaulted fields are required");
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_endpoint_metadata() {
+    let hub = TestHub::start("test_endpoint_metadata").await;
+
+    let register_service_name = "com.register_metadata";
+    let service_name = "com.inspect_metadata";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let _set_target = bus1
+        .register_method_with_options(
+            "set_target",
+            MethodOptions::default().metadata(
+                EndpointMetadata::new()
+                    .description("Set target temperature")
+                    .version("1.0")
+                    .deprecated("2.0", Some("set_targets")),
+            ),
+            |target: f64| async move { target < 30.0 },
+        )
+        .expect("Failed to register method");
+
+    let _set_targets = bus1
+        .register_method_with_options(
+            "set_targets",
+            MethodOptions::default().metadata(EndpointMetadata::new().version("2.0")),
+            |targets: Vec<f64>| async move { targets.iter().all(|target| *target < 30.0) },
+        )
+        .expect("Failed to register method");
+
+    let _plain = bus1
+        .register_method("plain", |_: ()| async move {})
+        .expect("Failed to register method");
+
+    let _signal = bus1
+        .register_signal_with_options::<f64>(
+            "overheated",
+            SignalOptions::default()
+                .metadata(EndpointMetadata::new().description("Temperature is too high")),
+        )
+        .expect("Failed to register signal");
+
+    let _state = bus1
+        .register_state_with_options(
+            "temperature",
+            21.0,
+            StateOptions::default().metadata(EndpointMetadata::new().deprecated("2.0", None)),
+        )
+        .expect("Failed to register state");
+
+    let inspect_data: InspectData = peer
+        .call(INSPECT_METHOD, &())
+        .await
+        .expect("Failed to inspect service");
+
+    let metadata = inspect_data
+        .method_metadata
+        .get("set_target")
+        .expect("Method metadata is missing");
+    assert_eq!(
+        metadata.description.as_deref(),
+        Some("Set target temperature")
+    );
+    assert_eq!(metadata.version.as_deref(), Some("1.0"));
+
+    let deprecation = metadata
+        .deprecated
+        .as_ref()
+        .expect("Deprecation is missing");
+    assert_eq!(deprecation.since, "2.0");
+    assert_eq!(deprecation.replacement.as_deref(), Some("set_targets"));
+
+    assert!(inspect_data.method_metadata["set_targets"]
+        .deprecated
+        .is_none());
+    // Endpoints without metadata are not listed
+    assert!(!inspect_data.method_metadata.contains_key("plain"));
+
+    assert_eq!(
+        inspect_data.signal_metadata["overheated"]
+            .description
+            .as_deref(),
+        Some("Temperature is too high")
+    );
+    assert!(inspect_data.state_metadata["temperature"]
+        .deprecated
+        .is_some());
+
+    // Deprecated methods keep working. The caller is warned in the log
+    for _ in 0..2 {
+        let accepted: bool = peer
+            .call("set_target", &21.5)
             .await
-            .expect("Failed to make a valid call"),
-        "Hello, 42"
+            .expect("Failed to make a call");
+        assert!(accepted);
+    }
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_object_paths() {
+    let hub = TestHub::start("test_object_paths").await;
+
+    let register_service_name = "com.register_objects";
+    let service_name = "com.call_objects";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let _list = bus1
+        .register_method("list", |_: ()| async move { 2 })
+        .expect("Failed to register method");
+
+    // Objects with the same interface
+    let mut objects = vec![];
+    let mut methods = vec![];
+    let mut states = vec![];
+    for device in ["eth0", "eth1"] {
+        let mut object = bus1
+            .object(&format!("/devices/{}", device))
+            .expect("Failed to register object");
+
+        methods.push(
+            object
+                .register_method("name", move |_: ()| async move { device.to_string() })
+                .expect("Failed to register object method"),
+        );
+        states.push(
+            object
+                .register_state("up", device == "eth0")
+                .expect("Failed to register object state"),
+        );
+
+        objects.push(object);
+    }
+
+    assert!(bus1.object("/devices/eth0").is_err());
+    assert!(bus1.object("devices/eth2").is_err());
+    assert!(bus1.object("/devices//eth2").is_err());
+
+    for device in ["eth0", "eth1"] {
+        let name: String = peer
+            .object(&format!("/devices/{}", device))
+            .call("name", &())
+            .await
+            .expect("Failed to call object method");
+        assert_eq!(name, device);
+    }
+
+    // Object endpoints are not listed at the root
+    let inspect_data: InspectData = peer
+        .call(INSPECT_METHOD, &())
+        .await
+        .expect("Failed to inspect service");
+    assert_eq!(inspect_data.methods.len(), 1);
+    assert!(inspect_data.methods[0].starts_with("list"));
+    assert_eq!(inspect_data.children, vec!["/devices".to_string()]);
+
+    // Intermediate nodes list their children
+    let inspect_data = peer
+        .object("/devices")
+        .inspect()
+        .await
+        .expect("Failed to inspect object");
+    assert!(inspect_data.methods.is_empty());
+    assert_eq!(
+        inspect_data.children,
+        vec!["/devices/eth0".to_string(), "/devices/eth1".to_string()]
+    );
+
+    let inspect_data = peer
+        .object("/devices/eth0")
+        .inspect()
+        .await
+        .expect("Failed to inspect object");
+    assert_eq!(inspect_data.methods.len(), 1);
+    assert!(inspect_data.methods[0].starts_with("name"));
+    assert_eq!(inspect_data.states, vec!["up: bool".to_string()]);
+    assert!(inspect_data.children.is_empty());
+
+    // Unplug a device
+    objects
+        .remove(0)
+        .unregister()
+        .expect("Failed to unregister object");
+
+    let err = peer
+        .object("/devices/eth0")
+        .call::<(), String>("name", &())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not registered"), "{}", err);
+
+    let name: String = peer
+        .object("/devices/eth1")
+        .call("name", &())
+        .await
+        .expect("Failed to call object method");
+    assert_eq!(name, "eth1");
+
+    // The path can be reused
+    let mut object = bus1
+        .object("/devices/eth0")
+        .expect("Failed to register object");
+    let _name = object
+        .register_method("name", |_: ()| async move { "eth0.new".to_string() })
+        .expect("Failed to register object method");
+
+    let name: String = peer
+        .object("/devices/eth0")
+        .call("name", &())
+        .await
+        .expect("Failed to call object method");
+    assert_eq!(name, "eth0.new");
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unregister_endpoints() {
+    let hub = TestHub::start("test_unregister_endpoints").await;
+
+    let register_service_name = "com.register_unregistered";
+    let service_name = "com.use_unregistered";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let add = bus1
+        .register_method("add", |(a, b): (i32, i32)| async move { a + b })
+        .expect("Failed to register method");
+    let _count = bus1
+        .register_streaming_method("count", |n: u32| tokio_stream::iter(0..n))
+        .expect("Failed to register streaming method");
+
+    let signal = bus1
+        .register_signal::<i32>("tick")
+        .expect("Failed to register signal");
+    let _state = bus1
+        .register_state("level", 1)
+        .expect("Failed to register state");
+
+    let sum: i32 = peer.call("add", &(1, 2)).await.expect("Failed to call");
+    assert_eq!(sum, 3);
+
+    let mut ticks = peer
+        .subscribe::<i32>("tick")
+        .await
+        .expect("Failed to subscribe");
+    let mut levels = Box::pin(peer.watch::<i32>("level").await.expect("Failed to watch"));
+
+    // Methods are unregistered by name
+    bus1.unregister_method("add")
+        .expect("Failed to unregister method");
+    bus1.unregister_method("count")
+        .expect("Failed to unregister streaming method");
+    bus1.unregister_method("add")
+        .err()
+        .expect("Unregistered method twice");
+
+    let err = peer.call::<_, i32>("add", &(1, 2)).await.unwrap_err();
+    assert!(err.to_string().contains("not registered"), "{}", err);
+
+    // Dropping the handle unregisters the signal and ends subscriptions
+    signal.emit(1).expect("Failed to emit signal");
+    drop(signal);
+
+    assert_eq!(ticks.next().await, Some(1));
+    assert_eq!(
+        time::timeout(Duration::from_secs(1), ticks.next()).await,
+        Ok(None)
     );
 
-    shutdown_tx
-        .send(())
+    // Unregistering by name ends watches
+    bus1.unregister_state("level")
+        .expect("Failed to unregister state");
+
+    let ended = time::timeout(Duration::from_secs(1), async move {
+        while levels.next().await.is_some() {}
+    })
+    .await;
+    assert!(ended.is_ok(), "Watch stream didn't end");
+
+    // The inspection doesn't list removed endpoints
+    let inspect_data: InspectData = peer
+        .call(INSPECT_METHOD, &())
+        .await
+        .expect("Failed to inspect service");
+    assert!(inspect_data.methods.is_empty());
+    assert!(inspect_data.signals.is_empty());
+    assert!(inspect_data.states.is_empty());
+
+    // Names can be reused
+    let new_add = bus1
+        .register_method("add", |(a, b): (i32, i32)| async move { a + b + 1 })
+        .expect("Failed to register method again");
+    let sum: i32 = peer.call("add", &(1, 2)).await.expect("Failed to call");
+    assert_eq!(sum, 4);
+
+    // Dropping a stale registration doesn't unregister the new one
+    drop(add);
+    let sum: i32 = peer.call("add", &(1, 2)).await.expect("Failed to call");
+    assert_eq!(sum, 4);
+
+    // Dropping the registration unregisters the method
+    assert_eq!(new_add.name(), "add");
+    new_add.unregister();
+
+    let err = peer.call::<_, i32>("add", &(1, 2)).await.unwrap_err();
+    assert!(err.to_string().contains("not registered"), "{}", err);
+    bus1.unregister_method("add")
+        .err()
+        .expect("Unregistered released method");
+
+    // Detached registrations stay registered until removed by name
+    bus1.register_method("add", |(a, b): (i32, i32)| async move { a + b })
+        .expect("Failed to register method again")
+        .detach();
+    let sum: i32 = peer.call("add", &(1, 2)).await.expect("Failed to call");
+    assert_eq!(sum, 3);
+
+    bus1.unregister_method("add")
+        .expect("Failed to unregister detached method");
+
+    let inspect_data: InspectData = peer
+        .call(INSPECT_METHOD, &())
         .await
-        .expect("Failed to send shutdown request to the hub");
+        .expect("Failed to inspect service");
+    assert!(inspect_data.methods.is_empty());
+
+    hub.shutdown().await;
 }
@@ -1,169 +1,354 @@
-use std::{
-    env,
-    path::Path,
-    sync::{Arc, Mutex},
-    time::Duration,
-};
-
-use json::JsonValue;
-use log::LevelFilter;
-use tempdir::TempDir;
-use tokio::{
-    fs::OpenOptions,
-    io::AsyncWriteExt,
-    sync::mpsc::{self, Sender},
-    time,
-};
-
-use karo_bus_common::HUB_SOCKET_PATH_ENV;
-use karo_bus_hub::{args::Args, hub::Hub};
-use karo_bus_lib::Bus;
-
-async fn start_hub(socket_path: &str, service_files_dir: &str) -> Sender<()> {
-    env::set_var(HUB_SOCKET_PATH_ENV, socket_path);
-
-    let args = Args {
-        log_level: LevelFilter::Debug,
-        service_files_dir: service_files_dir.into(),
-    };
-
-    // let _ = pretty_env_logger::formatted_builder()
-    //     .filter_level(args.log_level)
-    //     .try_init();
-
-    let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>(1);
-
-    tokio::spawn(async move {
-        let mut hub = Hub::new(args, shutdown_rx);
-        hub.run().await.expect("Failed to run hub");
-
-        println!("Shutting hub down");
-    });
-
-    println!("Succesfully started hub socket");
-    shutdown_tx
-}
+mod common;
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::time;
+use tokio_stream::StreamExt;
+
+use karo_bus_common::errors::Error as BusError;
+use karo_bus_lib::{Filter, LagPolicy, SignalOptions, SubscriptionEvent};
+
+use common::TestHub;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_signals() {
+    let hub = TestHub::start("test_signals").await;
+
+    let register_service_name = "com.register_signal";
+    let service_name = "com.subscribe_on_signal";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let signal = bus1
+        .register_signal::<i32>("signal")
+        .expect("Failed to register signal");
 
-async fn write_service_file(service_dir: &Path, service_name: &str, content: JsonValue) {
-    let service_file_path = service_dir.join(format!("{}.service", service_name));
+    // Invalid signal
+    assert!(
+        peer.subscribe::<i32>("non_existing_signal").await.is_err(),
+        "Invalid signal subscription succeeded"
+    );
 
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(service_file_path.as_path())
+    // Invalid param. The subscription ends on the first emission it can't read
+    let mut invalid = peer
+        .subscribe::<String>("signal")
         .await
-        .expect("Failed to create service file");
+        .expect("Failed to subscribe to the signal");
 
-    file.write_all(json::stringify(content).as_bytes())
+    // Valid subscription
+    let mut values = peer
+        .subscribe::<i32>("signal")
         .await
-        .expect("Failed to write service file content");
-    file.flush().await.expect("Failed to flush service file");
+        .expect("Failed to subscribe to the signal");
+
+    signal.emit(42).expect("Failed to emit signal");
+    assert_eq!(
+        time::timeout(Duration::from_secs(1), invalid.next()).await,
+        Ok(None)
+    );
+    assert_eq!(values.next().await, Some(42));
+
+    hub.shutdown().await;
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn test_signals() {
-    let socket_dir = TempDir::new("karo_hub_socket_dir").expect("Failed to create socket tempdir");
-    let socket_path: String = socket_dir
-        .path()
-        .join("karo_hub.socket")
-        .as_os_str()
-        .to_str()
-        .unwrap()
-        .into();
-
-    let service_dir = TempDir::new("test_signals").expect("Failed to create tempdir");
-
-    let shutdown_tx = start_hub(
-        &socket_path,
-        service_dir.path().as_os_str().to_str().unwrap(),
-    )
-    .await;
-    // Lets wait until hub starts
-    time::sleep(Duration::from_millis(10)).await;
-
-    // Create service file second
-    let service_file_json = json::parse(
-        r#"
-            {
-                "exec": "/**/*",
-                "incoming_connections": ["com.subscribe_on_signal"]
-            }
-            "#,
-    )
-    .unwrap();
+async fn test_unsubscribe() {
+    let hub = TestHub::start("test_unsubscribe").await;
 
-    let register_service_name = "com.register_signal";
-    write_service_file(service_dir.path(), register_service_name, service_file_json).await;
+    let register_service_name = "com.register_unsubscribe";
+    let service_name = "com.unsubscribe";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
 
-    let mut bus1 = Bus::register(register_service_name)
+    let signal = bus1
+        .register_signal::<i32>("tick")
+        .expect("Failed to register signal");
+    let state = bus1
+        .register_state("level", 1)
+        .expect("Failed to register state");
+
+    let mut first = peer
+        .subscribe::<i32>("tick")
         .await
-        .expect("Failed to register service");
+        .expect("Failed to subscribe");
+    let second = peer
+        .subscribe::<i32>("tick")
+        .await
+        .expect("Failed to subscribe");
+    let levels = peer.watch::<i32>("level").await.expect("Failed to watch");
+
+    assert_eq!(signal.subscriber_count(), 2);
+    assert_eq!(state.watcher_count(), 1);
+
+    // Publisher stops sending to dropped streams
+    drop(second);
+    drop(levels);
+
+    let start = Instant::now();
+    while signal.subscriber_count() != 1 || state.watcher_count() != 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(1),
+            "Subscriptions are still active"
+        );
+        time::sleep(Duration::from_millis(10)).await;
+    }
+
+    signal.emit(42).expect("Failed to emit signal");
+    assert_eq!(first.next().await, Some(42));
+
+    hub.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_subscription_events() {
+    let hub = TestHub::start("test_subscription_events").await;
+
+    let register_service_name = "com.register_subscription_events";
+    let service_name = "com.subscription_events";
+    let (mut bus1, mut bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
 
     let signal = bus1
-        .register_signal::<i32>("signal")
+        .register_signal::<i32>("tick")
         .expect("Failed to register signal");
+    let mut state = bus1
+        .register_state("level", 1)
+        .expect("Failed to register state");
+
+    let mut ticks = peer
+        .subscribe_events::<i32>("tick")
+        .await
+        .expect("Failed to subscribe");
+    let mut levels = peer
+        .watch_events::<i32>("level")
+        .await
+        .expect("Failed to watch");
 
-    // Create service file first
-    let service_file_json = json::parse(
-        r#"
-        {
-            "exec": "/**/*",
-            "incoming_connections": []
-        }
-        "#,
-    )
-    .unwrap();
+    // Watches start with the current value
+    assert_eq!(levels.next().await, Some(SubscriptionEvent::Value(1)));
 
-    let service_name = "com.subscribe_on_signal";
-    write_service_file(service_dir.path(), service_name, service_file_json).await;
+    state.set(2).expect("Failed to set state");
+    assert_eq!(levels.next().await, Some(SubscriptionEvent::Value(2)));
+
+    signal.emit(42).expect("Failed to emit signal");
+    assert_eq!(ticks.next().await, Some(SubscriptionEvent::Value(42)));
+
+    // Nothing marks a gap while the connection stays up
+    assert!(time::timeout(Duration::from_millis(100), ticks.next())
+        .await
+        .is_err());
+
+    // The publisher subscribes back using the connection it didn't request
+    let _status = bus2
+        .register_signal::<i32>("status")
+        .expect("Failed to register signal");
 
-    let mut bus2 = Bus::register(service_name)
+    let mut subscriber = bus1
+        .connect(service_name)
         .await
-        .expect("Failed to register service");
+        .expect("Failed to get the incoming connection");
+    let mut statuses = subscriber
+        .subscribe_events::<i32>("status")
+        .await
+        .expect("Failed to subscribe");
+
+    // Dropping any handle of the connection closes it
+    drop(subscriber);
+
+    // The publisher doesn't reconnect, so its subscription ends
+    assert_eq!(
+        time::timeout(Duration::from_secs(5), statuses.next())
+            .await
+            .expect("Subscription didn't end"),
+        None
+    );
+
+    // The subscriber requested the connection. It reconnects and renews subscriptions
+    assert_eq!(
+        time::timeout(Duration::from_secs(5), ticks.next())
+            .await
+            .expect("Subscription wasn't renewed"),
+        Some(SubscriptionEvent::Resubscribed)
+    );
+
+    signal.emit(43).expect("Failed to emit signal");
+    assert_eq!(ticks.next().await, Some(SubscriptionEvent::Value(43)));
+
+    // Watches get the current value after renewal
+    assert_eq!(
+        time::timeout(Duration::from_secs(5), levels.next())
+            .await
+            .expect("Watch wasn't renewed"),
+        Some(SubscriptionEvent::Resubscribed)
+    );
+    assert_eq!(levels.next().await, Some(SubscriptionEvent::Value(2)));
+
+    state.set(3).expect("Failed to set state");
+    assert_eq!(levels.next().await, Some(SubscriptionEvent::Value(3)));
 
-    let peer = bus2
-        .connect(register_service_name)
+    hub.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_signal_lag_policies() {
+    let hub = TestHub::start("test_signal_lag_policies").await;
+
+    let register_service_name = "com.register_signal_lag";
+    let service_name = "com.signal_lag";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let dropping = bus1
+        .register_signal_with_options::<i32>("dropping", SignalOptions::default().capacity(2))
+        .expect("Failed to register signal");
+    let disconnecting = bus1
+        .register_signal_with_options::<i32>(
+            "disconnecting",
+            SignalOptions::default()
+                .capacity(2)
+                .lag_policy(LagPolicy::Disconnect),
+        )
+        .expect("Failed to register signal");
+    let blocking = bus1
+        .register_signal_with_options::<i32>(
+            "blocking",
+            SignalOptions::default()
+                .capacity(1)
+                .lag_policy(LagPolicy::Block),
+        )
+        .expect("Failed to register signal");
+
+    // Subscribers can't take emissions until we yield
+    let mut dropped = peer
+        .subscribe_events::<i32>("dropping")
         .await
-        .expect("Failed to connect to the target service");
+        .expect("Failed to subscribe");
+    for value in 1..=6 {
+        dropping.emit(value).expect("Failed to emit signal");
+    }
 
-    // Value to be set on call back
-    let signal_value = Arc::new(Mutex::new(0));
-    let signal_value_clone = signal_value.clone();
+    assert_eq!(dropped.next().await, Some(SubscriptionEvent::Lagged(4)));
+    assert_eq!(dropped.next().await, Some(SubscriptionEvent::Value(5)));
+    assert_eq!(dropped.next().await, Some(SubscriptionEvent::Value(6)));
 
-    // Invalid signal
-    peer.subscribe("non_existing_signal", |_: i32| async move {
-        panic!("This should never happen");
-    })
-    .await
-    .expect_err("Invalid signal subscription succeeded");
-
-    // Invalid param
-    peer.subscribe("signal", |_: String| async move {
-        panic!("This should never happen");
-    })
-    .await
-    .expect("Failed to subscribe to the signal");
-
-    signal.emit(42);
-    time::sleep(Duration::from_millis(10)).await;
-    assert_eq!(*signal_value.lock().unwrap(), 0);
-
-    // Valid subscriptions
-    peer.subscribe("signal", move |value: i32| {
-        let signal_value = signal_value_clone.clone();
-        async move {
-            *signal_value.lock().unwrap() = value;
-        }
-    })
-    .await
-    .expect("Failed to subscribe to the signal");
-
-    signal.emit(42);
-    time::sleep(Duration::from_millis(10)).await;
-    assert_eq!(*signal_value.lock().unwrap(), 42);
-
-    shutdown_tx
-        .send(())
+    let mut disconnected = peer
+        .subscribe_events::<i32>("disconnecting")
+        .await
+        .expect("Failed to subscribe");
+    for value in 1..=6 {
+        disconnecting.emit(value).expect("Failed to emit signal");
+    }
+
+    assert_eq!(
+        disconnected.next().await,
+        Some(SubscriptionEvent::Lagged(4))
+    );
+    assert_eq!(
+        time::timeout(Duration::from_secs(1), disconnected.next()).await,
+        Ok(None)
+    );
+
+    // Blocked emitter waits for the subscriber, so nothing is lost
+    let blocked = peer
+        .subscribe_events::<i32>("blocking")
+        .await
+        .expect("Failed to subscribe");
+
+    // Sync emission can't wait. It fails instead of dropping the oldest emission
+    blocking.emit(1).expect("Failed to emit signal");
+    let err = blocking.emit(2).unwrap_err();
+    assert!(
+        matches!(err.downcast_ref::<BusError>(), Some(BusError::Busy)),
+        "{}",
+        err
+    );
+
+    for value in 2..=6 {
+        blocking
+            .emit_async(value)
+            .await
+            .expect("Failed to emit signal");
+    }
+
+    let events: Vec<_> = blocked.take(6).collect().await;
+    assert_eq!(
+        events,
+        (1..=6).map(SubscriptionEvent::Value).collect::<Vec<_>>()
+    );
+
+    hub.shutdown().await;
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Reading {
+    zone: String,
+    value: f64,
+}
+
+#[tokio::test]
+async fn test_filtered_subscription() {
+    let hub = TestHub::start("test_filtered_subscription").await;
+
+    let register_service_name = "com.register_filtered_subscription";
+    let service_name = "com.filtered_subscription";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let readings = bus1
+        .register_signal::<Reading>("reading")
+        .expect("Failed to register signal");
+
+    let mut hot_kitchen = peer
+        .subscribe_filtered::<Reading>(
+            "reading",
+            Filter::eq("zone", "kitchen").and(Filter::gt("value", 20)),
+        )
         .await
-        .expect("Failed to send shutdown request to the hub");
+        .expect("Failed to subscribe");
+    let mut all = peer
+        .subscribe::<Reading>("reading")
+        .await
+        .expect("Failed to subscribe");
+
+    let emissions = [("kitchen", 18.0), ("garage", 25.0), ("kitchen", 22.5)];
+    for (zone, value) in emissions {
+        readings
+            .emit(Reading {
+                zone: zone.into(),
+                value,
+            })
+            .expect("Failed to emit signal");
+    }
+
+    // Unfiltered subscribers still get everything
+    for (zone, value) in emissions {
+        assert_eq!(
+            all.next().await,
+            Some(Reading {
+                zone: zone.into(),
+                value
+            })
+        );
+    }
+
+    assert_eq!(
+        hot_kitchen.next().await,
+        Some(Reading {
+            zone: "kitchen".into(),
+            value: 22.5
+        })
+    );
+    assert!(
+        time::timeout(Duration::from_millis(100), hot_kitchen.next())
+            .await
+            .is_err()
+    );
+
+    hub.shutdown().await;
 }
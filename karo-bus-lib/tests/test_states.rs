@@ -1,172 +1,106 @@
-use std::{
-    env,
-    path::Path,
-    sync::{Arc, Mutex},
-    time::Duration,
-};
-
-use json::JsonValue;
-use log::LevelFilter;
-use tempdir::TempDir;
-use tokio::{
-    fs::OpenOptions,
-    io::AsyncWriteExt,
-    sync::mpsc::{self, Sender},
-    time,
-};
-
-use karo_bus_common::HUB_SOCKET_PATH_ENV;
-use karo_bus_hub::{args::Args, hub::Hub};
-use karo_bus_lib::Bus;
-
-async fn start_hub(socket_path: &str, service_files_dir: &str) -> Sender<()> {
-    env::set_var(HUB_SOCKET_PATH_ENV, socket_path);
-
-    let args = Args {
-        log_level: LevelFilter::Debug,
-        service_files_dir: service_files_dir.into(),
-    };
-
-    // let _ = pretty_env_logger::formatted_builder()
-    //     .filter_level(args.log_level)
-    //     .try_init();
-
-    let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>(1);
-
-    tokio::spawn(async move {
-        let mut hub = Hub::new(args, shutdown_rx);
-        hub.run().await.expect("Failed to run hub");
-
-        println!("Shutting hub down");
-    });
-
-    println!("Succesfully started hub socket");
-    shutdown_tx
-}
+mod common;
 
-async fn write_service_file(service_dir: &Path, service_name: &str, content: JsonValue) {
-    let service_file_path = service_dir.join(format!("{}.service", service_name));
+use std::time::Duration;
 
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(service_file_path.as_path())
-        .await
-        .expect("Failed to create service file");
+use tokio::time;
+use tokio_stream::StreamExt;
 
-    file.write_all(json::stringify(content).as_bytes())
-        .await
-        .expect("Failed to write service file content");
-    file.flush().await.expect("Failed to flush service file");
-}
+use karo_bus_common::errors::Error as BusError;
+use karo_bus_lib::{LagPolicy, StateOptions, SubscriptionEvent};
+
+use common::TestHub;
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_states() {
-    let socket_dir = TempDir::new("karo_hub_socket_dir").expect("Failed to create socket tempdir");
-    let socket_path: String = socket_dir
-        .path()
-        .join("karo_hub.socket")
-        .as_os_str()
-        .to_str()
-        .unwrap()
-        .into();
-
-    let service_dir = TempDir::new("test_states").expect("Failed to create tempdir");
-
-    let shutdown_tx = start_hub(
-        &socket_path,
-        service_dir.path().as_os_str().to_str().unwrap(),
-    )
-    .await;
-    // Lets wait until hub starts
-    time::sleep(Duration::from_millis(10)).await;
-
-    // Create service file second
-    let service_file_json = json::parse(
-        r#"
-            {
-                "exec": "/**/*",
-                "incoming_connections": ["com.watch_state"]
-            }
-            "#,
-    )
-    .unwrap();
+    let hub = TestHub::start("test_states").await;
 
     let register_service_name = "com.register_state";
-    write_service_file(service_dir.path(), register_service_name, service_file_json).await;
-
-    let mut bus1 = Bus::register(register_service_name)
-        .await
-        .expect("Failed to register service");
+    let service_name = "com.watch_state";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
 
     let mut state = bus1
         .register_state::<i32>("state", 42)
-        .expect("Failed to register signal");
-
-    // Create service file first
-    let service_file_json = json::parse(
-        r#"
-        {
-            "exec": "/**/*",
-            "incoming_connections": []
-        }
-        "#,
-    )
-    .unwrap();
+        .expect("Failed to register state");
 
-    let service_name = "com.watch_state";
-    write_service_file(service_dir.path(), service_name, service_file_json).await;
+    // Invalid state
+    assert!(
+        peer.watch::<i32>("non_existing_state").await.is_err(),
+        "Invalid state watch suceeded"
+    );
 
-    let mut bus2 = Bus::register(service_name)
+    // Invalid type. The watch ends on the current value it can't read
+    let mut invalid = peer
+        .watch::<String>("state")
         .await
-        .expect("Failed to register service");
+        .expect("Failed to watch state");
+    assert_eq!(
+        time::timeout(Duration::from_secs(1), invalid.next()).await,
+        Ok(None)
+    );
+
+    state.set(11).expect("Failed to set state");
 
-    let peer = bus2
-        .connect(register_service_name)
+    // Valid watch starts with the current value
+    let mut values = peer
+        .watch::<i32>("state")
         .await
-        .expect("Failed to connect to the target service");
+        .expect("Failed to watch state");
+    assert_eq!(values.next().await, Some(11));
 
-    // Value to be set on call back
-    let state_value = Arc::new(Mutex::new(0));
-    let state_value_clone = state_value.clone();
+    state.set(42).expect("Failed to set state");
+    assert_eq!(values.next().await, Some(42));
 
-    // Invalid state
-    peer.watch("non_existing_state", |_: i32| async move {
-        panic!("This should never happen");
-    })
-    .await
-    .expect_err("Invalid state watch suceeded");
-
-    // Invalid param
-    peer.watch("state", |_: String| async move {
-        panic!("This should never happen");
-    })
-    .await
-    .expect_err("Got state with invalid type");
-
-    state.set(11);
-    time::sleep(Duration::from_millis(10)).await;
-    assert_eq!(*state_value.lock().unwrap(), 0);
-
-    // Valid subscriptions
-    assert_eq!(
-        peer.watch("state", move |value: i32| {
-            let state_value = state_value_clone.clone();
-            async move {
-                *state_value.lock().unwrap() = value;
-            }
-        })
+    hub.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_state_lag_policies() {
+    let hub = TestHub::start("test_state_lag_policies").await;
+
+    let register_service_name = "com.register_state_lag";
+    let service_name = "com.state_lag";
+    let (mut bus1, _bus2, mut peer) = hub
+        .register_services(register_service_name, service_name)
+        .await;
+
+    let mut blocking_state = bus1
+        .register_state_with_options(
+            "blocking_level",
+            1,
+            StateOptions::default()
+                .capacity(1)
+                .lag_policy(LagPolicy::Block),
+        )
+        .expect("Failed to register state");
+
+    // Watchers can't take changes until we yield. Blocked state waits for them
+    let levels = peer
+        .watch_events::<i32>("blocking_level")
         .await
-        .expect("Failed to watch state"),
-        11
+        .expect("Failed to watch");
+
+    // Sync update can't wait. It fails instead of dropping the oldest change
+    blocking_state.set(2).expect("Failed to set state");
+    let err = blocking_state.set(3).unwrap_err();
+    assert!(
+        matches!(err.downcast_ref::<BusError>(), Some(BusError::Busy)),
+        "{}",
+        err
     );
+    assert_eq!(blocking_state.get(), &2);
 
-    state.set(42);
-    time::sleep(Duration::from_millis(10)).await;
-    assert_eq!(*state_value.lock().unwrap(), 42);
-
-    shutdown_tx
-        .send(())
+    blocking_state
+        .set_async(3)
         .await
-        .expect("Failed to send shutdown request to the hub");
+        .expect("Failed to set state");
+
+    let events: Vec<_> = levels.take(3).collect().await;
+    assert_eq!(
+        events,
+        (1..=3).map(SubscriptionEvent::Value).collect::<Vec<_>>()
+    );
+
+    hub.shutdown().await;
 }
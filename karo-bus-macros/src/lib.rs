@@ -0,0 +1,331 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, DeriveInput, Error, FnArg, Ident, ItemTrait, Pat,
+    ReturnType, TraitItem, TraitItemFn, Type,
+};
+
+mod schema;
+
+/// Interface endpoint declared in a trait
+enum Endpoint {
+    /// `async fn name(&self, args..) -> R;`
+    Method {
+        item: Box<TraitItemFn>,
+        args: Vec<(Ident, Type)>,
+        ret: Type,
+    },
+    /// `#[signal] fn name() -> T;`
+    Signal { name: Ident, ty: Type },
+    /// `#[state] fn name() -> T;`
+    State { name: Ident, ty: Type },
+}
+
+/// Generate typed service endpoints from a trait.
+///
+/// ```ignore
+/// #[karo_bus_lib::interface]
+/// pub trait Thermostat {
+///     async fn set_target(&self, target: f64) -> bool;
+///     async fn target(&self) -> f64;
+///
+///     #[signal]
+///     fn overheated() -> f64;
+///     #[state]
+///     fn temperature() -> f64;
+/// }
+/// ```
+///
+/// Generates:
+/// - `Thermostat` trait with async methods only. Services implement it using
+///   `#[karo_bus_lib::async_trait]`
+/// - `ThermostatEndpoints::register(&mut bus, Arc<impl Thermostat>)`, which registers methods,
+///   signals and states. States start with default values.
+///   Returned structure contains signal and state handles
+/// - `ThermostatProxy::new(peer)`, which has a typed async function per method,
+///   and a subscription function per signal and state
+///
+/// Methods are registered with their own names. Single argument methods take the argument as
+/// parameters, and multiple arguments are passed as a tuple.
+///
+/// `#[interface(schema)]` also describes endpoint schemas for the service inspection.
+/// All argument, return, signal and state types should implement `BusSchema` in this case
+#[proc_macro_attribute]
+pub fn interface(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as ItemTrait);
+
+    let schema = match syn::parse::<Option<Ident>>(args) {
+        Ok(None) => false,
+        Ok(Some(arg)) if arg == "schema" => true,
+        Ok(Some(arg)) => {
+            return Error::new(arg.span(), "Unknown interface option. Expected `schema`")
+                .to_compile_error()
+                .into()
+        }
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    match expand(item, schema) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(mut item: ItemTrait, schema: bool) -> Result<TokenStream2, Error> {
+    let endpoints = item
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Fn(item) => Some(parse_endpoint(item)),
+            _ => None,
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Signals and states are not implemented by the service
+    item.items.retain(|item| match item {
+        TraitItem::Fn(item) => !has_attr(item, "signal") && !has_attr(item, "state"),
+        _ => true,
+    });
+    item.supertraits.push(syn::parse_quote!(Send));
+    item.supertraits.push(syn::parse_quote!(Sync));
+    item.supertraits.push(syn::parse_quote!('static));
+
+    let vis = &item.vis;
+    let trait_name = &item.ident;
+    let proxy_name = format_ident!("{}Proxy", trait_name);
+    let endpoints_name = format_ident!("{}Endpoints", trait_name);
+
+    let mut proxy_functions = vec![];
+    let mut registrations = vec![];
+    let mut handle_fields = vec![];
+    let mut handle_registrations = vec![];
+    let mut descriptions = vec![];
+
+    for endpoint in endpoints {
+        match endpoint {
+            Endpoint::Method { item, args, ret } => {
+                let name = &item.sig.ident;
+                let name_str = name.to_string();
+                let attrs = &item.attrs;
+                let arg_names = args.iter().map(|(name, _)| name).collect::<Vec<_>>();
+                let arg_types = args.iter().map(|(_, ty)| ty).collect::<Vec<_>>();
+
+                let (params, params_pattern, params_type) = match args.len() {
+                    0 => (quote!(()), quote!(_), quote!(())),
+                    1 => (
+                        quote!(#(#arg_names)*),
+                        quote!(#(#arg_names)*),
+                        quote!(#(#arg_types)*),
+                    ),
+                    _ => (
+                        quote!((#(#arg_names),*)),
+                        quote!((#(#arg_names),*)),
+                        quote!((#(#arg_types),*)),
+                    ),
+                };
+
+                proxy_functions.push(quote! {
+                    #(#attrs)*
+                    pub async fn #name(&mut self, #(#arg_names: #arg_types),*)
+                        -> ::karo_bus_lib::__private::Result<#ret>
+                    {
+                        self.peer.call(#name_str, &#params).await
+                    }
+                });
+
+                registrations.push(quote! {
+                    let __service = ::std::sync::Arc::clone(&service);
+                    __registrations.push(bus.register_method(#name_str, move |#params_pattern: #params_type| {
+                        let __service = ::std::sync::Arc::clone(&__service);
+                        async move { __service.#name(#(#arg_names),*).await }
+                    })?);
+                });
+
+                descriptions.push(quote! {
+                    bus.describe_method::<#params_type, #ret>(#name_str)?;
+                });
+            }
+            Endpoint::Signal { name, ty } => {
+                let name_str = name.to_string();
+
+                proxy_functions.push(quote! {
+                    pub async fn #name(&mut self)
+                        -> ::karo_bus_lib::__private::Result<
+                            impl ::karo_bus_lib::__private::Stream<Item = #ty>
+                        >
+                    {
+                        self.peer.subscribe(#name_str).await
+                    }
+                });
+
+                handle_fields.push(quote!(pub #name: ::karo_bus_lib::Signal<#ty>));
+                handle_registrations.push(quote!(#name: bus.register_signal(#name_str)?));
+                descriptions.push(quote!(bus.describe_signal::<#ty>(#name_str)?;));
+            }
+            Endpoint::State { name, ty } => {
+                let name_str = name.to_string();
+
+                proxy_functions.push(quote! {
+                    pub async fn #name(&mut self)
+                        -> ::karo_bus_lib::__private::Result<
+                            impl ::karo_bus_lib::__private::Stream<Item = #ty>
+                        >
+                    {
+                        self.peer.watch(#name_str).await
+                    }
+                });
+
+                handle_fields.push(quote!(pub #name: ::karo_bus_lib::State<#ty>));
+                handle_registrations.push(quote! {
+                    #name: bus.register_state(#name_str, <#ty as ::std::default::Default>::default())?
+                });
+                descriptions.push(quote!(bus.describe_state::<#ty>(#name_str)?;));
+            }
+        }
+    }
+
+    if !schema {
+        descriptions.clear();
+    }
+
+    let proxy_doc = format!("Typed client of the [{}] interface", trait_name);
+    let endpoints_doc = format!(
+        "Registered [{}] interface. Contains signal and state handles. \
+         Dropping it unregisters the interface",
+        trait_name
+    );
+
+    Ok(quote! {
+        #[::karo_bus_lib::__private::async_trait]
+        #item
+
+        #[doc = #proxy_doc]
+        #vis struct #proxy_name {
+            peer: ::karo_bus_lib::Peer,
+        }
+
+        impl #proxy_name {
+            pub fn new(peer: ::karo_bus_lib::Peer) -> Self {
+                Self { peer }
+            }
+
+            /// Underlying peer connection
+            pub fn peer(&mut self) -> &mut ::karo_bus_lib::Peer {
+                &mut self.peer
+            }
+
+            #(#proxy_functions)*
+        }
+
+        #[doc = #endpoints_doc]
+        #vis struct #endpoints_name {
+            #(#handle_fields,)*
+            /// Method registrations. Dropped along with the interface
+            __registrations: ::std::vec::Vec<::karo_bus_lib::Registration>,
+        }
+
+        impl #endpoints_name {
+            /// Register interface endpoints handled by the **service**
+            pub fn register<S: #trait_name>(
+                bus: &mut ::karo_bus_lib::Bus,
+                service: ::std::sync::Arc<S>,
+            ) -> ::karo_bus_lib::__private::Result<Self> {
+                let mut __registrations = ::std::vec::Vec::new();
+                #({ #registrations })*
+
+                let __endpoints = Self {
+                    #(#handle_registrations,)*
+                    __registrations,
+                };
+
+                #(#descriptions)*
+
+                Ok(__endpoints)
+            }
+        }
+    })
+}
+
+fn has_attr(item: &TraitItemFn, name: &str) -> bool {
+    item.attrs.iter().any(|attr| attr.path().is_ident(name))
+}
+
+fn return_type(item: &TraitItemFn) -> Type {
+    match &item.sig.output {
+        ReturnType::Default => syn::parse_quote!(()),
+        ReturnType::Type(_, ty) => (**ty).clone(),
+    }
+}
+
+fn parse_endpoint(item: &TraitItemFn) -> Result<Endpoint, Error> {
+    let name = item.sig.ident.clone();
+
+    if has_attr(item, "signal") || has_attr(item, "state") {
+        if !item.sig.inputs.is_empty() || matches!(item.sig.output, ReturnType::Default) {
+            return Err(Error::new(
+                item.sig.span(),
+                "Signals and states should be declared as `fn name() -> Type;`",
+            ));
+        }
+
+        let ty = return_type(item);
+
+        return Ok(if has_attr(item, "signal") {
+            Endpoint::Signal { name, ty }
+        } else {
+            Endpoint::State { name, ty }
+        });
+    }
+
+    if item.sig.asyncness.is_none()
+        || !matches!(item.sig.inputs.first(), Some(FnArg::Receiver(receiver)) if receiver.reference.is_some() && receiver.mutability.is_none())
+    {
+        return Err(Error::new(
+            item.sig.span(),
+            "Interface methods should be declared as `async fn name(&self, ..) -> Type;`",
+        ));
+    }
+
+    let args = item
+        .sig
+        .inputs
+        .iter()
+        .skip(1)
+        .enumerate()
+        .map(|(i, arg)| match arg {
+            FnArg::Typed(arg) => {
+                let name = match &*arg.pat {
+                    Pat::Ident(pat) => pat.ident.clone(),
+                    _ => Ident::new(&format!("arg{}", i), Span::call_site()),
+                };
+
+                Ok((name, (*arg.ty).clone()))
+            }
+            FnArg::Receiver(receiver) => Err(Error::new(receiver.span(), "Unexpected receiver")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Endpoint::Method {
+        item: Box::new(item.clone()),
+        ret: return_type(item),
+        args,
+    })
+}
+
+/// Describe structure BSON representation for the service inspection.
+/// Implements `karo_bus_lib::BusSchema`.
+///
+/// Named fields become object fields. `#[serde(rename = "..")]`, `#[serde(rename_all = "..")]`
+/// and `#[serde(skip)]` are taken into account. Fields with `#[serde(default)]` or
+/// `#[serde(skip_serializing_if = "..")]` are described as optional. Newtypes are described by the inner type, and enums with
+/// unit variants only by their variant names. Other enums are described as any value
+#[proc_macro_derive(BusSchema)]
+pub fn derive_bus_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match schema::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
@@ -0,0 +1,303 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    punctuated::Punctuated, Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields,
+    GenericParam, Lit, Meta, MetaNameValue, Token, Type, WherePredicate,
+};
+
+/// Expand `#[derive(BusSchema)]`
+pub(crate) fn expand(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let container = SerdeAttrs::parse(&input.attrs)?;
+
+    let schema = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => {
+                let mut schema_fields = vec![];
+
+                for field in &fields.named {
+                    let serde = SerdeAttrs::parse(&field.attrs)?;
+                    if serde.skip {
+                        continue;
+                    }
+
+                    let field_name = serde.rename.unwrap_or_else(|| {
+                        let field_name = field.ident.as_ref().unwrap().to_string();
+                        match container.rename_all {
+                            Some(rule) => rule.apply_to_field(&field_name),
+                            None => field_name,
+                        }
+                    });
+
+                    let mut schema = type_schema(&field.ty);
+                    // Fields, which may be missing in the document
+                    if container.default || serde.default || serde.skip_serializing_if {
+                        schema = quote! {
+                            match #schema {
+                                schema @ ::karo_bus_lib::Schema::Optional { .. } => schema,
+                                schema => ::karo_bus_lib::Schema::Optional {
+                                    value: ::std::boxed::Box::new(schema),
+                                },
+                            }
+                        };
+                    }
+
+                    schema_fields.push(quote! {
+                        ::karo_bus_lib::SchemaField::new(#field_name, #schema)
+                    });
+                }
+
+                quote! {
+                    ::karo_bus_lib::Schema::Object {
+                        name: #name_str.into(),
+                        fields: vec![#(#schema_fields),*],
+                    }
+                }
+            }
+            // Newtypes are serialized as the inner value
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                type_schema(&fields.unnamed[0].ty)
+            }
+            Fields::Unnamed(fields) => {
+                let items = fields.unnamed.iter().map(|field| type_schema(&field.ty));
+
+                quote! {
+                    ::karo_bus_lib::Schema::Tuple {
+                        items: vec![#(#items),*],
+                    }
+                }
+            }
+            Fields::Unit => quote!(::karo_bus_lib::Schema::Null),
+        },
+        Data::Enum(data) => {
+            if data
+                .variants
+                .iter()
+                .all(|variant| matches!(variant.fields, Fields::Unit))
+            {
+                let variants = data
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        Ok(SerdeAttrs::parse(&variant.attrs)?
+                            .rename
+                            .unwrap_or_else(|| {
+                                let variant_name = variant.ident.to_string();
+                                match container.rename_all {
+                                    Some(rule) => rule.apply_to_variant(&variant_name),
+                                    None => variant_name,
+                                }
+                            }))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+
+                quote! {
+                    ::karo_bus_lib::Schema::Enum {
+                        name: #name_str.into(),
+                        variants: vec![#(#variants.into()),*],
+                    }
+                }
+            } else {
+                // Variants with data have several serde representations
+                quote!(::karo_bus_lib::Schema::Any)
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span,
+                "BusSchema can't be derived for unions",
+            ))
+        }
+    };
+
+    // Generic parameters should describe themselves as well
+    let type_params = input
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(param.ident.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let where_clause = input.generics.make_where_clause();
+    for param in type_params {
+        let predicate: WherePredicate = syn::parse_quote!(#param: ::karo_bus_lib::BusSchema);
+        where_clause.predicates.push(predicate);
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::karo_bus_lib::BusSchema for #name #ty_generics #where_clause {
+            fn schema() -> ::karo_bus_lib::Schema {
+                #schema
+            }
+        }
+    })
+}
+
+fn type_schema(ty: &Type) -> TokenStream2 {
+    quote!(<#ty as ::karo_bus_lib::BusSchema>::schema())
+}
+
+/// Serde attributes, which change the serialized structure
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<RenameRule>,
+    skip: bool,
+    /// Field or all container fields are defaulted if missing
+    default: bool,
+    skip_serializing_if: bool,
+}
+
+impl SerdeAttrs {
+    fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut result = Self::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+
+            for meta in metas {
+                match meta {
+                    Meta::NameValue(MetaNameValue {
+                        path,
+                        value:
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Str(value),
+                                ..
+                            }),
+                        ..
+                    }) if path.is_ident("rename") => result.rename = Some(value.value()),
+                    Meta::NameValue(MetaNameValue {
+                        path,
+                        value:
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Str(value),
+                                ..
+                            }),
+                        ..
+                    }) if path.is_ident("rename_all") => {
+                        result.rename_all =
+                            Some(RenameRule::parse(&value.value()).ok_or_else(|| {
+                                Error::new(value.span(), "Unknown `rename_all` rule")
+                            })?)
+                    }
+                    Meta::List(list)
+                        if list.path.is_ident("rename") || list.path.is_ident("rename_all") =>
+                    {
+                        return Err(Error::new_spanned(
+                            list,
+                            "BusSchema doesn't support separate serialize and deserialize names",
+                        ))
+                    }
+                    Meta::Path(path)
+                        if path.is_ident("skip") || path.is_ident("skip_serializing") =>
+                    {
+                        result.skip = true
+                    }
+                    Meta::Path(path) if path.is_ident("default") => result.default = true,
+                    Meta::NameValue(MetaNameValue { path, .. }) if path.is_ident("default") => {
+                        result.default = true
+                    }
+                    Meta::NameValue(MetaNameValue { path, .. })
+                        if path.is_ident("skip_serializing_if") =>
+                    {
+                        result.skip_serializing_if = true
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Serde `rename_all` rule
+#[derive(Clone, Copy)]
+enum RenameRule {
+    LowerCase,
+    UpperCase,
+    PascalCase,
+    CamelCase,
+    SnakeCase,
+    ScreamingSnakeCase,
+    KebabCase,
+    ScreamingKebabCase,
+}
+
+impl RenameRule {
+    fn parse(rule: &str) -> Option<Self> {
+        Some(match rule {
+            "lowercase" => Self::LowerCase,
+            "UPPERCASE" => Self::UpperCase,
+            "PascalCase" => Self::PascalCase,
+            "camelCase" => Self::CamelCase,
+            "snake_case" => Self::SnakeCase,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnakeCase,
+            "kebab-case" => Self::KebabCase,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebabCase,
+            _ => return None,
+        })
+    }
+
+    /// Rename snake case field the same way serde does
+    fn apply_to_field(self, field: &str) -> String {
+        match self {
+            Self::LowerCase | Self::SnakeCase => field.into(),
+            Self::UpperCase | Self::ScreamingSnakeCase => field.to_ascii_uppercase(),
+            Self::PascalCase => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for ch in field.chars() {
+                    if ch == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(ch.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(ch);
+                    }
+                }
+                pascal
+            }
+            Self::CamelCase => {
+                let pascal = Self::PascalCase.apply_to_field(field);
+                pascal[..1].to_ascii_lowercase() + &pascal[1..]
+            }
+            Self::KebabCase => field.replace('_', "-"),
+            Self::ScreamingKebabCase => field.to_ascii_uppercase().replace('_', "-"),
+        }
+    }
+
+    /// Rename pascal case variant the same way serde does
+    fn apply_to_variant(self, variant: &str) -> String {
+        match self {
+            Self::PascalCase => variant.into(),
+            Self::LowerCase => variant.to_ascii_lowercase(),
+            Self::UpperCase => variant.to_ascii_uppercase(),
+            Self::CamelCase => variant[..1].to_ascii_lowercase() + &variant[1..],
+            Self::SnakeCase => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                snake
+            }
+            Self::ScreamingSnakeCase => Self::SnakeCase
+                .apply_to_variant(variant)
+                .to_ascii_uppercase(),
+            Self::KebabCase => Self::SnakeCase.apply_to_variant(variant).replace('_', "-"),
+            Self::ScreamingKebabCase => Self::ScreamingSnakeCase
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+        }
+    }
+}
@@ -84,10 +84,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await
         .expect("Failed to register monitor");
 
-    bus.register_method(MONITOR_METHOD, |message: MonitorMessage| async move {
-        handle_message(&message)
-    })
-    .expect("Failed to register signalling function");
+    let _monitor_method = bus
+        .register_method(MONITOR_METHOD, |message: MonitorMessage| async move {
+            handle_message(&message)
+        })
+        .expect("Failed to register signalling function");
 
     let _peer = bus
         .connect_await(&args.target_service)